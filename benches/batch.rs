@@ -0,0 +1,49 @@
+//  This Source Code Form is subject to the terms of the Mozilla Public
+//  License, v. 2.0. If a copy of the MPL was not distributed with this
+//  file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+#[macro_use]
+extern crate criterion;
+extern crate feather_code;
+
+use std::hint::black_box;
+
+use criterion::Criterion;
+use feather_code::barcode::code128::{decode_batch, Code128, Code128Buf, Encoding, Pattern};
+use feather_code::barcode::format::{Decode, Encode};
+
+/// 10,000 synthetic lot numbers mixing all three symbologies
+fn synthetic_codes() -> Vec<Vec<u8>> {
+    (0..10_000)
+        .map(|i| {
+            let text = format!("Lot{}-{:06}x", i % 97, i * 7919);
+            let code: Code128Buf<Pattern> = text.encode().unwrap();
+
+            code.symbols().iter().map(Encoding::as_u8).collect()
+        })
+        .collect()
+}
+
+fn batch(c: &mut Criterion) {
+    let symbols = synthetic_codes();
+    let codes: Vec<Code128<u8>> = symbols.iter().map(|s| Code128::new(s)).collect();
+
+    c.bench_function("decode 10k single", |b| {
+        b.iter(|| {
+            black_box(&codes).iter().map(Decode::<String>::decode).collect::<Vec<_>>()
+        })
+    });
+    c.bench_function("decode 10k batch", |b| b.iter(|| decode_batch(black_box(&codes))));
+
+    #[cfg(feature = "rayon")]
+    c.bench_function("decode 10k parallel", |b| {
+        b.iter(|| feather_code::barcode::code128::par_decode_batch(black_box(&codes)))
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(50);
+    targets = batch
+}
+criterion_main!(benches);
@@ -0,0 +1,80 @@
+//  This Source Code Form is subject to the terms of the Mozilla Public
+//  License, v. 2.0. If a copy of the MPL was not distributed with this
+//  file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+#[macro_use]
+extern crate criterion;
+extern crate feather_code;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use criterion::Criterion;
+use feather_code::barcode::code128::Code128Builder;
+
+/// The system allocator, counting allocations and reallocations
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// Push `text` one character at a time, as when appending fields
+fn fill(mut builder: Code128Builder, text: &str) -> Code128Builder {
+    for c in text.chars() {
+        builder = builder.push_b(c.encode_utf8(&mut [0; 4]));
+    }
+
+    builder
+}
+
+fn allocations<F: FnOnce()>(f: F) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    f();
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+fn builder(c: &mut Criterion) {
+    let long: String = (0..12).map(|i| format!("Lot{}-{:02}", i, i * 7)).collect();
+    assert!(long.len() > 64);
+
+    // Filling these 86 characters allocates 6 times with new, growing the
+    // buffer, and once with with_capacity
+    let new = allocations(|| { black_box(fill(Code128Builder::new(), &long)); });
+    let reserved = allocations(|| {
+        black_box(fill(Code128Builder::with_capacity(long.len()), &long));
+    });
+    assert!(reserved < new, "with_capacity allocated {} times, new {}", reserved, new);
+
+    c.bench_function("fill builder new", |b| {
+        b.iter(|| fill(Code128Builder::new(), black_box(&long)))
+    });
+    c.bench_function("fill builder with_capacity", |b| {
+        b.iter(|| fill(Code128Builder::with_capacity(long.len()), black_box(&long)))
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(1000);
+    targets = builder
+}
+criterion_main!(benches);
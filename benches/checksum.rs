@@ -0,0 +1,36 @@
+//  This Source Code Form is subject to the terms of the Mozilla Public
+//  License, v. 2.0. If a copy of the MPL was not distributed with this
+//  file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+#[macro_use]
+extern crate criterion;
+extern crate feather_code;
+
+use std::hint::black_box;
+
+use criterion::Criterion;
+use feather_code::barcode::code128::{compute_checksum, Code128, Symbology};
+use feather_code::barcode::format::Format;
+
+const PJJ123C: [u8; 10] = [103, 48, 42, 42, 17, 18, 19, 35, 54, 106];
+
+fn checksum(c: &mut Criterion) {
+    let data: Vec<u8> = (0..200).map(|i| i % 96).collect();
+
+    c.bench_function("verify short", |b| {
+        b.iter(|| Code128::new(black_box(&PJJ123C[..])).checksum())
+    });
+    c.bench_function("compute short", |b| {
+        b.iter(|| compute_checksum::<u8>(Symbology::A, black_box(&PJJ123C[1..8])))
+    });
+    c.bench_function("compute long", |b| {
+        b.iter(|| compute_checksum::<u8>(Symbology::B, black_box(&data)))
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(1000);
+    targets = checksum
+}
+criterion_main!(benches);
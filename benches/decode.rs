@@ -0,0 +1,63 @@
+//  This Source Code Form is subject to the terms of the Mozilla Public
+//  License, v. 2.0. If a copy of the MPL was not distributed with this
+//  file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+#[macro_use]
+extern crate criterion;
+extern crate feather_code;
+
+use std::hint::black_box;
+
+use criterion::Criterion;
+use feather_code::barcode::code128::{encode_with, Code128, EncodeOptions, Encoding, Pattern};
+use feather_code::barcode::format::Decode;
+
+const PJJ123C: [u8; 10] = [103, 48, 42, 42, 17, 18, 19, 35, 54, 106];
+
+/// A barcode of well over 100 symbols, mixing all three symbologies
+fn long_code() -> Vec<u8> {
+    let text: String = (0..40).map(|i| format!("Lot{}-{:04}", i, i * 37)).collect();
+    let code = encode_with(text.as_bytes(), &EncodeOptions { allow_oversize: true }).unwrap();
+
+    code.symbols().iter().map(Encoding::as_u8).collect()
+}
+
+fn decode(c: &mut Criterion) {
+    let short_patterns: Vec<Pattern> = PJJ123C.iter().map(|&u| Pattern::from(u)).collect();
+    let long = long_code();
+    let long_patterns: Vec<Pattern> = long.iter().map(|&u| Pattern::from(u)).collect();
+
+    assert!(long.len() > 100);
+
+    c.bench_function("decode short u8", |b| {
+        b.iter(|| Decode::<String>::decode(&Code128::new(black_box(&PJJ123C[..]))))
+    });
+    c.bench_function("decode short Pattern", |b| {
+        b.iter(|| Decode::<String>::decode(&Code128::new(black_box(&short_patterns[..]))))
+    });
+    c.bench_function("decode long u8", |b| {
+        b.iter(|| Decode::<String>::decode(&Code128::new(black_box(&long[..]))))
+    });
+    c.bench_function("decode long Pattern", |b| {
+        b.iter(|| Decode::<String>::decode(&Code128::new(black_box(&long_patterns[..]))))
+    });
+
+    // One character, which decode_cow borrows
+    let single: [u8; 4] = [104, 56, 57, 106];
+    c.bench_function("decode single char", |b| {
+        b.iter(|| Decode::<String>::decode(&Code128::new(black_box(&single[..]))))
+    });
+    c.bench_function("decode_cow single char", |b| {
+        b.iter(|| Code128::new(black_box(&single[..])).decode_cow())
+    });
+    c.bench_function("decode_cow long u8", |b| {
+        b.iter(|| Code128::new(black_box(&long[..])).decode_cow())
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(1000);
+    targets = decode
+}
+criterion_main!(benches);
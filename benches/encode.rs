@@ -0,0 +1,57 @@
+//  This Source Code Form is subject to the terms of the Mozilla Public
+//  License, v. 2.0. If a copy of the MPL was not distributed with this
+//  file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+#[macro_use]
+extern crate criterion;
+extern crate feather_code;
+
+use std::hint::black_box;
+
+use criterion::Criterion;
+use feather_code::barcode::code128::{encode_bytes, encode_with, encoded_len, EncodeOptions};
+use feather_code::barcode::code128::{Code128Buf, Pattern};
+use feather_code::barcode::format::Encode;
+
+fn encode(c: &mut Criterion) {
+    let long: String = (0..40).map(|i| format!("Lot{}-{:04}", i, i * 37)).collect();
+    let latin1: Vec<u8> = (0..=255).collect();
+
+    c.bench_function("encode short str", |b| {
+        b.iter(|| Encode::<Code128Buf<Pattern>>::encode(black_box("PJJ123C")))
+    });
+    // Longer than MAX_SYMBOLS, so past the default limit
+    let oversize = EncodeOptions { allow_oversize: true };
+    c.bench_function("encode long str", |b| {
+        b.iter(|| encode_with(black_box(long.as_bytes()), &oversize))
+    });
+
+    // Inputs chosen to exercise the planner's choices between symbologies
+    let numeric = "4218402050012345678901234567890123456789";
+    let alphanumeric = "HackFSU Feather Codes Lot PJJ123C Rev b";
+    let control = "\x02PJJ\x1d123C\x1d\x04ack\x03lot\rline\n\x1e";
+    let c_switching = "A12B3456c78901D2e3456789f0123G45h67890123";
+
+    for &(name, text) in &[("numeric", numeric),
+                           ("alphanumeric", alphanumeric),
+                           ("control", control),
+                           ("code C switching", c_switching)] {
+        c.bench_function(&format!("encode {} str", name), |b| {
+            b.iter(|| Encode::<Code128Buf<Pattern>>::encode(black_box(text)))
+        });
+    }
+
+    c.bench_function("encode all bytes", |b| {
+        b.iter(|| encode_bytes(black_box(&latin1)))
+    });
+    c.bench_function("encoded_len long str", |b| {
+        b.iter(|| encoded_len(black_box(&long)))
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(1000);
+    targets = encode
+}
+criterion_main!(benches);
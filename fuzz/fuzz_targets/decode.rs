@@ -0,0 +1,45 @@
+//  This Source Code Form is subject to the terms of the Mozilla Public
+//  License, v. 2.0. If a copy of the MPL was not distributed with this
+//  file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Feed almost valid barcodes and arbitrary bar widths to the decoders
+//!
+//! Run with `cargo fuzz run decode`.  Inputs that once found bugs belong in
+//! `corpus/decode`, so every run checks them first.
+
+#![no_main]
+
+use feather_code::barcode::code128::{Code128, Pattern, Symbology};
+use feather_code::barcode::format::{Decode, Format};
+use feather_code::barcode::testing::AlmostValid;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: (AlmostValid, Vec<Pattern>, [u8; 6])| {
+    let (AlmostValid(symbols), patterns, widths) = input;
+
+    let code = Code128::new(&symbols[..]);
+    let checksum = code.checksum();
+    let text: Result<String, _> = code.decode();
+    let bytes: Result<Vec<u8>, _> = code.decode();
+
+    // Whatever decodes must have passed the checksum
+    assert!(checksum || (text.is_err() && bytes.is_err()));
+
+    let (partial, error) = code.decode_partial();
+    if let Ok(text) = text {
+        assert_eq!((partial, error), (text, None));
+    }
+
+    let _ = code.decode_lenient();
+    let _ = code.decode_auto_orient();
+    let _ = code.dump(Symbology::B);
+
+    let code = Code128::new(&patterns[..]);
+    let _ = code.checksum();
+    let _: Result<String, _> = code.decode();
+    let _ = code.modules();
+
+    if let Some(p) = Pattern::from_bar_widths(widths) {
+        assert_eq!(p.bar_widths(), widths);
+    }
+});
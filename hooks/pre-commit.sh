@@ -30,6 +30,7 @@ then
 
     cargo doc --no-deps &&
     cargo build &&
+    cargo build --no-default-features --features alloc &&
     cargo test --all
 
     # Capture exit code from tests
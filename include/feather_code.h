@@ -0,0 +1,58 @@
+/*
+ *  This Source Code Form is subject to the terms of the Mozilla Public
+ *  License, v. 2.0. If a copy of the MPL was not distributed with this
+ *  file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+/*
+ * C interface to feather-code, built with the `ffi` feature.
+ *
+ * Maintained by hand alongside src/barcode/ffi.rs.
+ */
+
+#ifndef FEATHER_CODE_H
+#define FEATHER_CODE_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* The call succeeded */
+#define FEATHER_CODE_OK 0
+/* A required pointer was null */
+#define FEATHER_CODE_ERR_NULL (-1)
+/* The symbols are not a valid barcode */
+#define FEATHER_CODE_ERR_DECODE (-2)
+/* The output buffer is too small for the decoded text */
+#define FEATHER_CODE_ERR_BUFFER (-3)
+
+/*
+ * Decode `len` Code 128 symbol values at `data` into the buffer at `out`.
+ *
+ * On entry `*out_len` is the size of the buffer; on return it is the length
+ * in bytes of the decoded text, which is UTF-8.  Characters extended by
+ * FNC4 decode to Latin-1 characters above 127, two bytes each, so query
+ * the length rather than assuming one byte per symbol.  No terminating NUL
+ * is written.  On FEATHER_CODE_ERR_BUFFER, `*out_len` is the length needed
+ * and nothing is written.  A null `out` with `*out_len` of zero queries the
+ * length.
+ */
+int32_t feather_code_decode(const uint8_t *data, size_t len,
+                            uint8_t *out, size_t *out_len);
+
+/*
+ * Check `len` Code 128 symbol values at `data`.
+ *
+ * Returns 1 if the check symbol matches, 0 if not, and
+ * FEATHER_CODE_ERR_NULL if `data` is null.
+ */
+int32_t feather_code_checksum(const uint8_t *data, size_t len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* FEATHER_CODE_H */
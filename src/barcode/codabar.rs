@@ -0,0 +1,457 @@
+//  This Source Code Form is subject to the terms of the Mozilla Public
+//  License, v. 2.0. If a copy of the MPL was not distributed with this
+//  file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Codabar barcodes
+//!
+//! Codabar, found on library books and blood bags, writes digits and
+//! `- $ : / . +` between a start and a stop character, each one of `A` to
+//! `D`.  The pair chosen often means something to the application, so
+//! decoding can report it.  A modulo 16 check character before the stop
+//! character is optional, so the barcode must say whether it has one.
+//!
+//! Symbols are held as values: `0` to `9` for the digits, then `10` to `15`
+//! for `- $ : / . +`, and `16` to `19` for `A` to `D`.
+//!
+//! ```
+//! use feather_code::barcode::codabar::{Codabar, CodabarBuf};
+//! use feather_code::barcode::format::{Decode, Encode, Format};
+//!
+//! let code: CodabarBuf = "40156".encode().unwrap();
+//!
+//! assert_eq!(code.to_string(), "A40156A");
+//! assert!(code.checksum());
+//! assert_eq!(code.decode(), Ok(String::from("40156")));
+//! ```
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use barcode::format::{Decode, Encode, Error, Format, Result, IN_DATA};
+
+/// Character of each value
+static CHARS: &[u8; 20] = b"0123456789-$:/.+ABCD";
+
+/// Wide elements of each value: four bars and three spaces alternating
+/// from the first bar, which is the highest of seven bits, a set bit wide
+static ELEMENTS: [u8; 20] = [
+    0b0000011, 0b0000110, 0b0001001, 0b1100000, 0b0010010,
+    0b1000010, 0b0100001, 0b0100100, 0b0110000, 0b1001000,
+    0b0001100, 0b0011000, 0b1000101, 0b1010001, 0b1010100,
+    0b0010101, 0b0011010, 0b0101001, 0b0001011, 0b0001110,
+];
+
+/// Lowest value of the start and stop characters, `A`
+const FIRST_GUARD: u8 = 16;
+
+/// The value of a character, if Codabar has it; `a` to `d` count as `A` to
+/// `D`
+///
+/// ```
+/// use feather_code::barcode::codabar::char_value;
+///
+/// assert_eq!(char_value('$'), Some(11));
+/// assert_eq!(char_value('b'), Some(17));
+/// assert_eq!(char_value('E'), None);
+/// ```
+pub fn char_value(c: char) -> Option<u8> {
+    let c = c.to_ascii_uppercase();
+
+    CHARS.iter().position(|&b| b as char == c).map(|u| u as u8)
+}
+
+/// The character with the given value, if there is one
+pub fn value_char(value: u8) -> Option<char> {
+    CHARS.get(value as usize).map(|&b| b as char)
+}
+
+/// Whether each element of a value's symbol is wide, bars and spaces
+/// alternating from the first bar
+///
+/// Digits, `-`, and `$` have two wide elements; the rest have three.
+///
+/// ```
+/// use feather_code::barcode::codabar::elements;
+///
+/// assert_eq!(elements(0), Some([false, false, false, false, false, true, true]));
+/// assert_eq!(elements(20), None);
+/// ```
+pub fn elements(value: u8) -> Option<[bool; 7]> {
+    ELEMENTS.get(value as usize).map(|&bits| {
+        let mut wide = [false; 7];
+        for (i, w) in wide.iter_mut().enumerate() {
+            *w = bits >> (6 - i) & 1 == 1;
+        }
+
+        wide
+    })
+}
+
+/// Whether a value is one of the start and stop characters `A` to `D`
+fn is_guard(value: u8) -> bool {
+    (FIRST_GUARD..FIRST_GUARD + 4).contains(&value)
+}
+
+/// The check value for a barcode's values, start and stop included: what
+/// brings their sum up to a multiple of 16
+///
+/// ```
+/// use feather_code::barcode::codabar::check_value;
+///
+/// // A37859B: A=16, 3, 7, 8, 5, 9, B=17 sum to 65, so the check is 15, "+"
+/// assert_eq!(check_value(&[16, 3, 7, 8, 5, 9, 17]), 15);
+/// ```
+pub fn check_value(values: &[u8]) -> u8 {
+    let sum: usize = values.iter().map(|&v| v as usize).sum();
+
+    ((16 - sum % 16) % 16) as u8
+}
+
+/// What a Codabar barcode decodes to, start and stop characters included
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CodabarText {
+    /// The start character, `A` to `D`
+    pub start: char,
+    /// The characters between start and stop, without any check character
+    pub data: String,
+    /// The stop character, `A` to `D`
+    pub stop: char,
+}
+
+/// A Codabar barcode borrowing its values, start and stop included
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Codabar<'a> {
+    values: &'a [u8],
+    check: bool,
+}
+
+impl<'a> Codabar<'a> {
+    /// Wrap values without a check character
+    pub fn new(values: &'a [u8]) -> Codabar<'a> {
+        Codabar { values, check: false }
+    }
+
+    /// Wrap values whose last before the stop character is the check
+    /// character
+    pub fn with_check(values: &'a [u8]) -> Codabar<'a> {
+        Codabar { values, check: true }
+    }
+
+    /// The values, as given
+    pub fn values(&self) -> &'a [u8] {
+        self.values
+    }
+
+    /// Whether the barcode has a check character
+    pub fn has_check(&self) -> bool {
+        self.check
+    }
+
+    /// Split out the data and check character, failing unless the barcode
+    /// has start and stop characters and only data between them
+    fn parts(&self) -> Result<(&'a [u8], Option<u8>)> {
+        let n = self.values.len();
+        let least = if self.check { 3 } else { 2 };
+
+        if n < least || !is_guard(self.values[0]) || !is_guard(self.values[n - 1]) {
+            return Err(Error::BadFormat(String::from("missing start or stop character")));
+        }
+
+        let inner = &self.values[1..n - 1];
+        if let Some(index) = inner.iter().position(|&v| v >= FIRST_GUARD) {
+            return Err(Error::BadSymbolAt { index, value: inner[index], context: IN_DATA });
+        }
+
+        if self.check {
+            Ok((&inner[..inner.len() - 1], inner.last().cloned()))
+        } else {
+            Ok((inner, None))
+        }
+    }
+
+    /// Whether all the values, check character included, sum to a
+    /// multiple of 16
+    fn check_matches(&self) -> bool {
+        check_value(self.values) == 0
+    }
+}
+
+impl<'a> Format for Codabar<'a> {
+    /// Whether the barcode is framed and, if it has a check character,
+    /// whether that matches
+    fn checksum(&self) -> bool {
+        match self.parts() {
+            Ok((_, Some(_))) => self.check_matches(),
+            Ok((_, None)) => true,
+            Err(_) => false,
+        }
+    }
+
+    fn has_checksum(&self) -> bool {
+        self.check
+    }
+}
+
+impl<'a> Decode<CodabarText> for Codabar<'a> {
+    /// The data along with the start and stop characters
+    fn decode(&self) -> Result<CodabarText> {
+        let (data, check) = self.parts()?;
+
+        if check.is_some() && !self.check_matches() {
+            return Err(Error::BadFormat(String::from("check character does not match")));
+        }
+
+        let n = self.values.len();
+        let char_of = |v: u8| CHARS[v as usize] as char;
+
+        Ok(CodabarText {
+            start: char_of(self.values[0]),
+            data: data.iter().map(|&v| char_of(v)).collect(),
+            stop: char_of(self.values[n - 1]),
+        })
+    }
+}
+
+impl<'a> Decode<String> for Codabar<'a> {
+    /// The characters between start and stop, without any check character
+    fn decode(&self) -> Result<String> {
+        Decode::<CodabarText>::decode(self).map(|text| text.data)
+    }
+}
+
+impl<'a> fmt::Display for Codabar<'a> {
+    /// Every character, start, check, and stop included; values out of
+    /// range show as `?`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for &v in self.values {
+            write!(f, "{}", value_char(v).unwrap_or('?'))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Settings for `encode_with`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EncodeOptions {
+    /// Start character, `A` to `D`
+    pub start: char,
+    /// Stop character, `A` to `D`
+    pub stop: char,
+    /// Whether to add a check character
+    pub check: bool,
+}
+
+impl Default for EncodeOptions {
+    /// `A` to start and stop, without a check character
+    fn default() -> EncodeOptions {
+        EncodeOptions { start: 'A', stop: 'A', check: false }
+    }
+}
+
+/// An owned Codabar barcode
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CodabarBuf {
+    values: Vec<u8>,
+    check: bool,
+}
+
+impl CodabarBuf {
+    /// Borrow as a `Codabar`
+    pub fn as_codabar(&self) -> Codabar<'_> {
+        Codabar { values: &self.values, check: self.check }
+    }
+
+    /// The values, start and stop included
+    pub fn values(&self) -> &[u8] {
+        &self.values
+    }
+}
+
+impl Format for CodabarBuf {
+    fn checksum(&self) -> bool {
+        self.as_codabar().checksum()
+    }
+
+    fn has_checksum(&self) -> bool {
+        self.check
+    }
+}
+
+impl<T> Decode<T> for CodabarBuf
+    where for<'a> Codabar<'a>: Decode<T>
+{
+    fn decode(&self) -> Result<T> {
+        self.as_codabar().decode()
+    }
+}
+
+impl fmt::Display for CodabarBuf {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.as_codabar().fmt(f)
+    }
+}
+
+/// Encode `text` between the start and stop characters `options` picks
+///
+/// Fails with `BadFormat` for a character Codabar lacks in `text`,
+/// including `A` to `D`, or a start or stop character outside `A` to `D`.
+///
+/// ```
+/// use feather_code::barcode::codabar::{encode_with, CodabarText, EncodeOptions};
+/// use feather_code::barcode::format::Decode;
+///
+/// let options = EncodeOptions { start: 'B', stop: 'D', check: true };
+/// let code = encode_with("31117013206375", &options).unwrap();
+///
+/// assert_eq!(code.to_string(), "B311170132063754D");
+/// assert_eq!(code.decode(), Ok(CodabarText {
+///     start: 'B',
+///     data: String::from("31117013206375"),
+///     stop: 'D',
+/// }));
+/// ```
+pub fn encode_with(text: &str, options: &EncodeOptions) -> Result<CodabarBuf> {
+    let guard = |c: char| {
+        char_value(c).filter(|&v| is_guard(v)).ok_or_else(|| {
+            Error::BadFormat(format!("{:?} is not a Codabar start or stop character", c))
+        })
+    };
+
+    let mut values = vec![guard(options.start)?];
+    for c in text.chars() {
+        match char_value(c) {
+            Some(v) if !is_guard(v) => values.push(v),
+            _ => return Err(Error::BadFormat(format!("cannot encode {:?} in Codabar", c))),
+        }
+    }
+
+    let stop = guard(options.stop)?;
+    if options.check {
+        let mut framed = values.clone();
+        framed.push(stop);
+        values.push(check_value(&framed));
+    }
+    values.push(stop);
+
+    Ok(CodabarBuf { values, check: options.check })
+}
+
+impl Encode<CodabarBuf> for str {
+    /// Encode between `A` start and stop characters, without a check
+    /// character
+    fn encode(&self) -> Result<CodabarBuf> {
+        encode_with(self, &EncodeOptions::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    fn values(text: &str) -> Vec<u8> {
+        text.chars().map(|c| char_value(c).unwrap()).collect()
+    }
+
+    #[test]
+    fn decode_reports_start_and_stop() {
+        let values = values("C12-34$56:78/90.+D");
+        let code = Codabar::new(&values);
+
+        assert!(code.checksum());
+        assert_eq!(code.decode(), Ok(CodabarText {
+            start: 'C',
+            data: String::from("12-34$56:78/90.+"),
+            stop: 'D',
+        }));
+        assert_eq!(Decode::<String>::decode(&code), Ok(String::from("12-34$56:78/90.+")));
+    }
+
+    #[test]
+    fn check_character() {
+        // A37859B has the check character "+"
+        let values = values("A37859+B");
+        let code = Codabar::with_check(&values);
+
+        assert!(code.checksum());
+        assert_eq!(Decode::<String>::decode(&code), Ok(String::from("37859")));
+        assert_eq!(Decode::<String>::decode(&Codabar::new(&values)),
+                   Ok(String::from("37859+")));
+
+        let mut wrong = values.clone();
+        wrong[6] = 0;
+        assert!(!Codabar::with_check(&wrong).checksum());
+        assert_eq!(Decode::<String>::decode(&Codabar::with_check(&wrong)),
+                   Err(Error::BadFormat(String::from("check character does not match"))));
+    }
+
+    #[test]
+    fn round_trip() {
+        for text in &["", "0", "40156", "31117013206375", "-$:/.+", "123.45"] {
+            for &(start, stop) in &[('A', 'A'), ('B', 'D'), ('c', 'b')] {
+                for &check in &[false, true] {
+                    let options = EncodeOptions { start, stop, check };
+                    let code = encode_with(text, &options).unwrap();
+
+                    assert!(code.checksum(), "{:?} {:?}", text, options);
+                    assert_eq!(code.decode(), Ok(CodabarText {
+                        start: start.to_ascii_uppercase(),
+                        data: text.to_string(),
+                        stop: stop.to_ascii_uppercase(),
+                    }));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_characters() {
+        assert_eq!(Encode::<CodabarBuf>::encode("12A34"),
+                   Err(Error::BadFormat(String::from("cannot encode 'A' in Codabar"))));
+        assert_eq!(Encode::<CodabarBuf>::encode("PJJ"),
+                   Err(Error::BadFormat(String::from("cannot encode 'P' in Codabar"))));
+
+        let options = EncodeOptions { stop: 'E', ..EncodeOptions::default() };
+        assert_eq!(encode_with("12", &options),
+                   Err(Error::BadFormat(String::from("'E' is not a Codabar start or stop \
+                                                      character"))));
+        let options = EncodeOptions { start: '1', ..EncodeOptions::default() };
+        assert!(encode_with("12", &options).is_err());
+    }
+
+    #[test]
+    fn rejects_bad_framing() {
+        let inner_guard = values("A12B34A");
+        assert_eq!(Decode::<String>::decode(&Codabar::new(&inner_guard)),
+                   Err(Error::BadSymbolAt { index: 2, value: 17, context: IN_DATA }));
+
+        let out_of_range = [16, 1, 25, 16];
+        assert!(Decode::<String>::decode(&Codabar::new(&out_of_range)).is_err());
+
+        for text in &["123", "A123", "123B", "A"] {
+            let values = values(text);
+
+            assert!(!Codabar::new(&values).checksum(), "{}", text);
+            assert!(Decode::<String>::decode(&Codabar::new(&values)).is_err(), "{}", text);
+        }
+
+        let empty = values("AB");
+        assert_eq!(Decode::<String>::decode(&Codabar::new(&empty)), Ok(String::new()));
+        assert!(Decode::<String>::decode(&Codabar::with_check(&empty)).is_err());
+    }
+
+    #[test]
+    fn elements_are_distinct() {
+        for v in 0..20u8 {
+            let wide = elements(v).unwrap();
+            let count = wide.iter().filter(|&&w| w).count();
+
+            assert_eq!(count, if v < 12 { 2 } else { 3 }, "{:?}", value_char(v));
+            for w in 0..v {
+                assert_ne!(elements(w), Some(wide));
+            }
+        }
+    }
+}
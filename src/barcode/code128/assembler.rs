@@ -0,0 +1,175 @@
+//  This Source Code Form is subject to the terms of the Mozilla Public
+//  License, v. 2.0. If a copy of the MPL was not distributed with this
+//  file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Joining messages split across barcodes with FNC2
+//!
+//! An FNC2 symbol anywhere in a barcode asks the reader to hold on to its
+//! data and prepend it to the next barcode scanned.  The message is
+//! complete once a barcode without FNC2 is read.
+
+use alloc::string::String;
+use core::fmt::Debug;
+use core::mem;
+
+use super::decoder::Walker;
+use super::{Code128, Encoding};
+use barcode::format::{Error, Result};
+
+/// Progress of a message being assembled
+#[derive(Debug, PartialEq)]
+pub enum AssemblyState {
+    /// The last barcode contained FNC2, so more data follows
+    AwaitingMore,
+    /// The last barcode ended the message, which is the concatenated data
+    /// of every barcode pushed
+    Complete(String),
+}
+
+/// Concatenates the data of barcodes linked by FNC2
+///
+/// ```
+/// use feather_code::barcode::code128::{AssemblyState, Code128, MessageAssembler};
+///
+/// // "Hack" followed by FNC2, then "FSU"
+/// let first: [u8; 8] = [104, 40, 65, 67, 75, 97, 24, 106];
+/// let second: [u8; 6] = [104, 38, 51, 53, 94, 106];
+///
+/// let mut assembler = MessageAssembler::new();
+///
+/// assert_eq!(assembler.push(&Code128::new(&first)), Ok(AssemblyState::AwaitingMore));
+/// assert_eq!(assembler.push(&Code128::new(&second)),
+///            Ok(AssemblyState::Complete(String::from("HackFSU"))));
+/// ```
+#[derive(Debug, Default)]
+pub struct MessageAssembler {
+    message: String,
+    complete: bool,
+}
+
+impl MessageAssembler {
+    /// Start assembling a new message
+    pub fn new() -> MessageAssembler {
+        MessageAssembler::default()
+    }
+
+    /// Decode a barcode and append its data to the message
+    ///
+    /// A barcode that fails to decode leaves the message unchanged.  Fails
+    /// with `BadFormat` once the message is complete, until `reset` is
+    /// called.
+    pub fn push<E: Encoding + Debug>(&mut self, code: &Code128<E>) -> Result<AssemblyState> {
+        if self.complete {
+            return Err(Error::BadFormat(String::from("message is already complete")));
+        }
+
+        let mut walker = Walker::new(code.clone(), true);
+        let data = walker.by_ref()
+            .map(|b| b.map(char::from))
+            .collect::<Result<String>>()?;
+
+        self.message.push_str(&data);
+
+        if walker.fnc2 {
+            Ok(AssemblyState::AwaitingMore)
+        } else {
+            self.complete = true;
+            Ok(AssemblyState::Complete(mem::take(&mut self.message)))
+        }
+    }
+
+    /// Discard any partial message and start over
+    pub fn reset(&mut self) {
+        self.message.clear();
+        self.complete = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+    use barcode::code128::{compute_checksum, Symbology};
+
+    /// Symbology B barcode for printable ASCII `text`, optionally with FNC2
+    fn code_b(text: &str, fnc2: bool) -> Vec<u8> {
+        let mut data: Vec<u8> = text.bytes().map(|b| b - 32).collect();
+        if fnc2 {
+            data.push(97);
+        }
+
+        let mut symbols = vec![104];
+        symbols.extend(&data);
+        symbols.push(compute_checksum(Symbology::B, &data));
+        symbols.push(106);
+        symbols
+    }
+
+    #[test]
+    fn assemble_three_barcodes() {
+        let parts = [code_b("The quick brown ", true),
+                     code_b("fox jumps over ", true),
+                     code_b("the lazy dog", false)];
+        let mut assembler = MessageAssembler::new();
+
+        assert_eq!(assembler.push(&Code128::new(&parts[0])), Ok(AssemblyState::AwaitingMore));
+        assert_eq!(assembler.push(&Code128::new(&parts[1])), Ok(AssemblyState::AwaitingMore));
+        assert_eq!(assembler.push(&Code128::new(&parts[2])),
+                   Ok(AssemblyState::Complete(String::from("The quick brown fox jumps over \
+                                                            the lazy dog"))));
+    }
+
+    #[test]
+    fn single_barcode_completes() {
+        let code = code_b("HackFSU", false);
+
+        assert_eq!(MessageAssembler::new().push(&Code128::new(&code)),
+                   Ok(AssemblyState::Complete(String::from("HackFSU"))));
+    }
+
+    #[test]
+    fn push_after_complete_fails_until_reset() {
+        let code = code_b("done", false);
+        let mut assembler = MessageAssembler::new();
+
+        assert!(assembler.push(&Code128::new(&code)).is_ok());
+        assert_eq!(assembler.push(&Code128::new(&code)),
+                   Err(Error::BadFormat(String::from("message is already complete"))));
+
+        assembler.reset();
+        assert_eq!(assembler.push(&Code128::new(&code)),
+                   Ok(AssemblyState::Complete(String::from("done"))));
+    }
+
+    #[test]
+    fn reset_discards_partial_message() {
+        let mut assembler = MessageAssembler::new();
+
+        assembler.push(&Code128::new(&code_b("stale ", true))).unwrap();
+        assembler.reset();
+
+        assert_eq!(assembler.push(&Code128::new(&code_b("fresh", false))),
+                   Ok(AssemblyState::Complete(String::from("fresh"))));
+    }
+
+    #[test]
+    fn bad_barcode_leaves_message_unchanged() {
+        let mut bad = code_b("oops", false);
+        bad[2] += 1;
+        let mut assembler = MessageAssembler::new();
+
+        assembler.push(&Code128::new(&code_b("keep ", true))).unwrap();
+        assert!(assembler.push(&Code128::new(&bad)).is_err());
+        assert_eq!(assembler.push(&Code128::new(&code_b("going", false))),
+                   Ok(AssemblyState::Complete(String::from("keep going"))));
+    }
+
+    #[test]
+    fn code_c_97_is_not_fnc2() {
+        let data = [97u8];
+        let symbols = [105, 97, compute_checksum(Symbology::C, &data), 106];
+
+        assert_eq!(MessageAssembler::new().push(&Code128::new(&symbols)),
+                   Ok(AssemblyState::Complete(String::from("97"))));
+    }
+}
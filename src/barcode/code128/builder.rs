@@ -0,0 +1,460 @@
+//  This Source Code Form is subject to the terms of the Mozilla Public
+//  License, v. 2.0. If a copy of the MPL was not distributed with this
+//  file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Building Code 128 barcodes with function codes
+//!
+//! Function codes have no character of their own, so the string encoder
+//! can't produce them.  The builder takes text and function codes in order
+//! and plans the shortest symbols for all of them together, as the string
+//! encoder does for text alone.
+//!
+//! Segments can also be pinned to a symbology.  The planner then switches
+//! symbology only where the pinned segments require it, giving full
+//! control over the switches when every segment is pinned.
+
+use alloc::vec::Vec;
+
+use super::encoder::{char_value, finish, plan, planned_len, Input};
+use super::{Code128Buf, EncodeOptions, Pattern, Symbology};
+use barcode::format::{Error, Result};
+
+/// Builds a Code 128 barcode from text, function codes, and raw symbols
+///
+/// A GS1 barcode starts with FNC1:
+///
+/// ```
+/// use feather_code::barcode::code128::Code128Builder;
+/// use feather_code::barcode::format::Decode;
+///
+/// let code = Code128Builder::new().fnc1().text("0112345678901231").build().unwrap();
+///
+/// assert_eq!(code.symbols()[..2], [105u8.into(), 102u8.into()]);
+/// assert_eq!(code.decode(), Ok(String::from("0112345678901231")));
+/// ```
+///
+/// Pinning every segment spells out the symbols exactly:
+///
+/// ```
+/// use feather_code::barcode::code128::{Code128Builder, Encoding};
+///
+/// let code = Code128Builder::new()
+///     .push_a("LOT")
+///     .push_c_digits("1234")
+///     .push_b("x")
+///     .build()
+///     .unwrap();
+/// let values: Vec<u8> = code.symbols().iter().map(Encoding::as_u8).collect();
+///
+/// assert_eq!(values[..9], [103, 44, 47, 52, 99, 12, 34, 100, 88]);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Code128Builder {
+    input: Vec<Input>,
+    error: Option<Error>,
+    options: EncodeOptions,
+}
+
+impl Code128Builder {
+    /// Start an empty barcode
+    ///
+    /// Unpinned input starts in `Symbology::default()` unless another
+    /// symbology gives fewer symbols.
+    pub fn new() -> Code128Builder {
+        Code128Builder::default()
+    }
+
+    /// Start an empty barcode with room for `n` characters and function
+    /// codes before reallocating
+    ///
+    /// Text of `L` characters that stays in one symbology, such as
+    /// Symbology B, builds into `L + 3` symbols counting the start, check,
+    /// and stop symbols, so `L` is the capacity to ask for.  Switches and
+    /// shifts add symbols but not inputs.
+    ///
+    /// ```
+    /// use feather_code::barcode::code128::Code128Builder;
+    ///
+    /// let builder = Code128Builder::with_capacity(7).text("PJJ123C");
+    ///
+    /// assert!(builder.capacity() >= 7);
+    /// assert_eq!(builder.symbol_count(), 10);
+    /// ```
+    pub fn with_capacity(n: usize) -> Code128Builder {
+        Code128Builder { input: Vec::with_capacity(n), ..Code128Builder::default() }
+    }
+
+    /// Characters and function codes the builder can hold before
+    /// reallocating
+    pub fn capacity(&self) -> usize {
+        self.input.capacity()
+    }
+
+    /// Symbols `build` would produce, counting the start, check, and stop
+    /// symbols; three when nothing has been added
+    pub fn symbol_count(&self) -> usize {
+        planned_len(&self.input[..]) + 2
+    }
+
+    /// Build with `options`, such as to allow more than `MAX_SYMBOLS`
+    /// symbols
+    pub fn options(mut self, options: &EncodeOptions) -> Code128Builder {
+        self.options = *options;
+        self
+    }
+
+    /// Append ASCII text
+    ///
+    /// Characters outside ASCII make `build` fail with `BadFormat`.
+    pub fn text(mut self, text: &str) -> Code128Builder {
+        if let Some(c) = text.chars().find(|c| !c.is_ascii()) {
+            let msg = format!("cannot encode non-ASCII character {:?}", c);
+            return self.fail(Error::BadFormat(msg));
+        }
+
+        self.input.extend(text.bytes().map(Input::Byte));
+        self
+    }
+
+    /// Append text written in symbology A
+    ///
+    /// Characters outside symbology A, such as lowercase letters, make
+    /// `build` fail with `BadFormat`.
+    pub fn push_a(self, text: &str) -> Code128Builder {
+        self.pinned(Symbology::A, text)
+    }
+
+    /// Append text written in symbology B
+    ///
+    /// Characters outside symbology B, such as control characters, make
+    /// `build` fail with `BadFormat`.
+    pub fn push_b(self, text: &str) -> Code128Builder {
+        self.pinned(Symbology::B, text)
+    }
+
+    /// Append digits written in pairs in symbology C
+    ///
+    /// Anything but an even number of digits makes `build` fail with
+    /// `BadFormat`.
+    pub fn push_c_digits(mut self, digits: &str) -> Code128Builder {
+        if !digits.len().is_multiple_of(2) || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            let msg = format!("{:?} is not an even number of digits", digits);
+            return self.fail(Error::BadFormat(msg));
+        }
+
+        self.input.extend(digits.bytes().map(|b| Input::In(Symbology::C, b)));
+        self
+    }
+
+    /// Append one character written with a shift from symbology B to A
+    pub fn shift_a(self, c: char) -> Code128Builder {
+        self.shifted(Symbology::A, c)
+    }
+
+    /// Append one character written with a shift from symbology A to B
+    pub fn shift_b(self, c: char) -> Code128Builder {
+        self.shifted(Symbology::B, c)
+    }
+
+    fn pinned(mut self, set: Symbology, text: &str) -> Code128Builder {
+        match text.chars().find(|&c| !in_set(set, c)) {
+            Some(c) => self.fail(cannot_encode(set, c)),
+            None => {
+                self.input.extend(text.bytes().map(|b| Input::In(set, b)));
+                self
+            }
+        }
+    }
+
+    fn shifted(self, set: Symbology, c: char) -> Code128Builder {
+        if in_set(set, c) {
+            self.push(Input::Shifted(set, c as u8))
+        } else {
+            self.fail(cannot_encode(set, c))
+        }
+    }
+
+    /// Append FNC1, which marks GS1 data when it comes first
+    pub fn fnc1(self) -> Code128Builder {
+        self.push(Input::Fnc1)
+    }
+
+    /// Append FNC2, asking the reader to append the next barcode's data
+    pub fn fnc2(self) -> Code128Builder {
+        self.push(Input::Fnc2)
+    }
+
+    /// Append FNC3, which marks reader programming instructions
+    pub fn fnc3(self) -> Code128Builder {
+        self.push(Input::Fnc3)
+    }
+
+    /// Append a single FNC4
+    ///
+    /// In extended decoding this adds 128 to the next character, and two in
+    /// a row latch that behaviour.
+    pub fn fnc4(self) -> Code128Builder {
+        self.push(Input::Fnc4)
+    }
+
+    /// Append a character symbol as is, in whichever symbology is active
+    ///
+    /// Function codes, shifts, and switches have methods of their own, since
+    /// the planner must track them, so values above 95 make `build` fail
+    /// with `BadFormat`.
+    pub fn raw_symbol(self, value: u8) -> Code128Builder {
+        if value > 95 {
+            return self.fail(Error::BadFormat(format!("{} is not a character symbol", value)));
+        }
+
+        self.push(Input::Raw(value))
+    }
+
+    fn push(mut self, input: Input) -> Code128Builder {
+        self.input.push(input);
+        self
+    }
+
+    /// Record `err` unless an earlier error is already waiting for `build`
+    fn fail(mut self, err: Error) -> Code128Builder {
+        if self.error.is_none() {
+            self.error = Some(err);
+        }
+
+        self
+    }
+
+    /// Plan the symbols and append the check and stop symbols
+    ///
+    /// Fails with the first error met while building, or with
+    /// `InvalidLength` for more than `MAX_SYMBOLS` symbols unless the
+    /// options allow it.  With nothing added, the barcode holds empty text.
+    pub fn build(&self) -> Result<Code128Buf<Pattern>> {
+        if let Some(ref err) = self.error {
+            return Err(err.clone());
+        }
+
+        finish(plan(&self.input[..]), &self.options)
+    }
+}
+
+/// Whether `c` is a character of symbology A or B
+fn in_set(set: Symbology, c: char) -> bool {
+    c.is_ascii() && char_value(set, c as u8).is_some()
+}
+
+fn cannot_encode(set: Symbology, c: char) -> Error {
+    Error::BadFormat(format!("cannot encode {:?} in symbology {:?}", c, set))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::String;
+    use barcode::code128::{AssemblyState, Encoding, MessageAssembler, MAX_SYMBOLS};
+    use barcode::format::{Decode, Format};
+
+    fn values(code: &Code128Buf<Pattern>) -> Vec<u8> {
+        code.symbols().iter().map(|p| p.as_u8()).collect()
+    }
+
+    #[test]
+    fn gs1_starts_with_fnc1_in_code_c() {
+        let code = Code128Builder::new().fnc1().text("42184020500").build().unwrap();
+
+        assert_eq!(values(&code)[..7], [105, 102, 42, 18, 40, 20, 50]);
+        assert!(code.checksum());
+        assert_eq!(code.decode(), Ok(String::from("42184020500")));
+
+        let (_, info) = code.decode_with_info().unwrap();
+        assert!(info.fnc1_present);
+    }
+
+    #[test]
+    fn fnc1_separates_variable_length_fields() {
+        let code = Code128Builder::new()
+            .fnc1().text("10ABC123")
+            .fnc1().text("21XYZ")
+            .build()
+            .unwrap();
+        let symbols = values(&code);
+
+        assert_eq!(symbols.iter().filter(|&&v| v == 102).count(), 2);
+        assert_eq!(code.decode(), Ok(String::from("10ABC12321XYZ")));
+    }
+
+    #[test]
+    fn ties_start_in_default_symbology() {
+        let start = Symbology::default().start_value();
+
+        assert_eq!(Symbology::default(), Symbology::B);
+        for code in &[Code128Builder::new().fnc1(), Code128Builder::new().fnc2().fnc4()] {
+            assert_eq!(values(&code.build().unwrap())[0], start);
+        }
+        assert_eq!(values(&Code128Builder::new().text("12").build().unwrap())[0], 105);
+    }
+
+    #[test]
+    fn fnc3_leaves_code_c() {
+        let code = Code128Builder::new().text("1234").fnc3().text("5678").build().unwrap();
+
+        assert!(values(&code).contains(&96));
+        assert_eq!(code.decode(), Ok(String::from("12345678")));
+    }
+
+    #[test]
+    fn fnc2_links_messages() {
+        let first = Code128Builder::new().text("Hack").fnc2().build().unwrap();
+        let second = Code128Builder::new().text("FSU").build().unwrap();
+
+        let mut assembler = MessageAssembler::new();
+
+        assert_eq!(assembler.push(&first.as_code128()), Ok(AssemblyState::AwaitingMore));
+        assert_eq!(assembler.push(&second.as_code128()),
+                   Ok(AssemblyState::Complete(String::from("HackFSU"))));
+    }
+
+    #[test]
+    fn fnc4_extends_next_character() {
+        let code = Code128Builder::new().text("a").fnc4().text("A").build().unwrap();
+
+        assert_eq!(Decode::<Vec<u8>>::decode(&code), Ok(vec![b'a', 0xc1]));
+        assert_eq!(code.decode(), Ok(String::from("a\u{c1}")));
+    }
+
+    #[test]
+    fn raw_symbol_is_written_as_is() {
+        let code = Code128Builder::new().text("a").raw_symbol(33).build().unwrap();
+
+        assert_eq!(values(&code)[..3], [104, 65, 33]);
+        assert_eq!(code.decode(), Ok(String::from("aA")));
+    }
+
+    #[test]
+    fn build_reports_first_error() {
+        assert_eq!(Code128Builder::new().text("é").raw_symbol(106).build(),
+                   Err(Error::BadFormat(String::from("cannot encode non-ASCII character 'é'"))));
+        assert_eq!(Code128Builder::new().raw_symbol(103).build(),
+                   Err(Error::BadFormat(String::from("103 is not a character symbol"))));
+    }
+
+    #[test]
+    fn raw_symbol_rejects_function_codes() {
+        for value in 96..103 {
+            let message = format!("{} is not a character symbol", value);
+
+            assert_eq!(Code128Builder::new().text("a").raw_symbol(value).build(),
+                       Err(Error::BadFormat(message)));
+        }
+    }
+
+    #[test]
+    fn pinned_segments_switch_as_requested() {
+        let code = Code128Builder::new()
+            .push_b("ab")
+            .push_a("CD")
+            .push_b("ef")
+            .build()
+            .unwrap();
+
+        assert_eq!(values(&code)[..9], [104, 65, 66, 101, 35, 36, 100, 69, 70]);
+        assert_eq!(code.decode(), Ok(String::from("abCDef")));
+    }
+
+    #[test]
+    fn pinned_digits_are_not_compressed_outside_code_c() {
+        let code = Code128Builder::new().push_b("123456").build().unwrap();
+
+        assert_eq!(values(&code)[..7], [104, 17, 18, 19, 20, 21, 22]);
+    }
+
+    #[test]
+    fn shifts_are_written_as_requested() {
+        let code = Code128Builder::new()
+            .push_b("ab")
+            .shift_a('\t')
+            .push_b("c")
+            .build()
+            .unwrap();
+
+        assert_eq!(values(&code)[..6], [104, 65, 66, 98, 73, 67]);
+        assert_eq!(code.decode(), Ok(String::from("ab\tc")));
+
+        let (_, info) = code.decode_with_info().unwrap();
+        assert_eq!((info.switches, info.shifts), (0, 1));
+    }
+
+    #[test]
+    fn pinned_and_planned_segments_mix() {
+        let code = Code128Builder::new()
+            .fnc1()
+            .push_c_digits("0112345678901231")
+            .text("10abc")
+            .build()
+            .unwrap();
+
+        assert_eq!(values(&code)[..3], [105, 102, 1]);
+        assert_eq!(code.decode(), Ok(String::from("011234567890123110abc")));
+    }
+
+    #[test]
+    fn pinned_segments_report_bad_characters() {
+        assert_eq!(Code128Builder::new().push_a("abc").build(),
+                   Err(Error::BadFormat(String::from("cannot encode 'a' in symbology A"))));
+        assert_eq!(Code128Builder::new().push_b("\n").build(),
+                   Err(Error::BadFormat(String::from("cannot encode '\\n' in symbology B"))));
+        assert_eq!(Code128Builder::new().shift_b('é').build(),
+                   Err(Error::BadFormat(String::from("cannot encode 'é' in symbology B"))));
+        assert_eq!(Code128Builder::new().push_c_digits("123").build(),
+                   Err(Error::BadFormat(String::from("\"123\" is not an even number of digits"))));
+        assert_eq!(Code128Builder::new().push_c_digits("1a").build(),
+                   Err(Error::BadFormat(String::from("\"1a\" is not an even number of digits"))));
+    }
+
+    #[test]
+    fn matches_string_encoder_without_function_codes() {
+        use barcode::format::Encode;
+
+        for text in &["PJJ123C", "42184020500", "SHiFT!", "a1234b"] {
+            let built = Code128Builder::new().text(text).build().unwrap();
+            let encoded: Code128Buf<Pattern> = text.encode().unwrap();
+
+            assert_eq!(built, encoded);
+        }
+    }
+
+    #[test]
+    fn build_holds_to_max_symbols() {
+        let text: String = (0..MAX_SYMBOLS - 2).map(|_| 'x').collect();
+        let builder = Code128Builder::new().text(&text);
+
+        assert_eq!(builder.build(),
+                   Err(Error::InvalidLength(
+                       String::from("233 symbols, more than the 232 allowed"))));
+
+        let code = builder.options(&EncodeOptions { allow_oversize: true }).build().unwrap();
+        assert_eq!(code.symbols().len(), MAX_SYMBOLS + 1);
+    }
+
+    #[test]
+    fn capacity_and_symbol_count() {
+        let text = "HackFSU feather codes, built into one long Code 128 barcode";
+        let mut builder = Code128Builder::with_capacity(text.len());
+        assert_eq!(builder.symbol_count(), 3);
+        assert_eq!(values(&builder.build().unwrap()), [104, 1, 106]);
+
+        let capacity = builder.capacity();
+        assert!(capacity >= text.len());
+
+        for c in text.chars() {
+            builder = builder.push_b(c.encode_utf8(&mut [0; 4]));
+        }
+
+        assert_eq!(builder.capacity(), capacity);
+        assert_eq!(builder.symbol_count(), text.len() + 3);
+        assert_eq!(builder.symbol_count(), builder.build().unwrap().symbols().len());
+
+        let builder = Code128Builder::new().fnc1().text("42184020500");
+        assert_eq!(builder.symbol_count(), builder.build().unwrap().symbols().len());
+    }
+}
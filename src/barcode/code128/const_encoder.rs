@@ -0,0 +1,363 @@
+//  This Source Code Form is subject to the terms of the Mozilla Public
+//  License, v. 2.0. If a copy of the MPL was not distributed with this
+//  file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Allocation-free encoding, behind the `code128!` macro and `Code128Arr`
+//!
+//! The shortest-sequence planner in `encoder` is a `const fn` working in
+//! buffers supplied by its callers, so the same search runs here on arrays
+//! sized by const generics, and nothing is allocated while planning.  The
+//! crate as a whole still requires `alloc`.
+
+use alloc::string::ToString;
+
+use super::encoder::{search, trace, Items, Step, NONE, NO_STEP};
+use super::{add_to_checksum, Code128, Diagnostic, Pattern, MAX_SYMBOLS};
+use barcode::format::{Decode, Error, Format, Result};
+
+/// A barcode encoded at compile time, with room for `S` symbols
+#[doc(hidden)]
+pub struct ConstPlan<const S: usize> {
+    symbols: [u8; S],
+    len: usize,
+}
+
+impl<const S: usize> ConstPlan<S> {
+    /// Number of symbols, including the start, check, and stop symbols
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether there are no symbols, which never happens for a finished
+    /// plan
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The symbols as patterns, `M` being exactly `len()`
+    pub const fn to_array<const M: usize>(&self) -> [Pattern; M] {
+        assert!(M == self.len, "code128!: array length does not match the plan");
+
+        let mut out = [Pattern::C0; M];
+        let mut i = 0;
+        while i < M {
+            out[i] = Pattern::from_u8(self.symbols[i]);
+            i += 1;
+        }
+
+        out
+    }
+
+    /// The symbols as values
+    pub fn as_slice(&self) -> &[u8] {
+        &self.symbols[..self.len]
+    }
+}
+
+/// Plan the symbols for `text` into `symbols`, with the start, check, and
+/// stop symbols
+///
+/// `cost` and `back` must have room for `text.len() + 1` positions.
+/// Returns the number of symbols, or `NONE` if they don't fit in
+/// `symbols`.
+const fn plan_into(text: &[u8],
+                   cost: &mut [[usize; 6]],
+                   back: &mut [[Step; 6]],
+                   symbols: &mut [u8])
+                   -> usize {
+    let (planned, end) = search(Items::Bytes(text), cost, back);
+    if planned + 2 > symbols.len() {
+        return NONE;
+    }
+
+    trace(back, text.len(), end, planned, symbols);
+
+    // The check symbol, weighing the start symbol once and the data by
+    // position, as `const_checksum` does
+    let mut sum = add_to_checksum(0, 1, symbols[0]);
+    let mut j = 1;
+    while j < planned {
+        sum = add_to_checksum(sum, j, symbols[j]);
+        j += 1;
+    }
+
+    symbols[planned] = sum;
+    symbols[planned + 1] = Pattern::stop().as_u8();
+
+    planned + 2
+}
+
+/// Encode `text` with start, check, and stop symbols
+///
+/// `P` must be at least `text.len() + 1` and `S` at least
+/// `2 * text.len() + 3`.  Panics, failing compilation in const contexts,
+/// for non-ASCII text, or text needing more than `MAX_SYMBOLS` symbols.
+#[doc(hidden)]
+pub const fn const_plan<const P: usize, const S: usize>(text: &str) -> ConstPlan<S> {
+    let text = text.as_bytes();
+    let n = text.len();
+
+    assert!(P > n && S >= 2 * n + 3, "code128!: plan capacity too small");
+
+    let mut c = 0;
+    while c < n {
+        assert!(text[c] < 128, "code128!: only ASCII text can be encoded");
+        c += 1;
+    }
+
+    let mut cost = [[NONE; 6]; P];
+    let mut back = [[NO_STEP; 6]; P];
+    let mut symbols = [0u8; S];
+    let len = plan_into(text, &mut cost, &mut back, &mut symbols);
+    assert!(len <= MAX_SYMBOLS, "code128!: more than MAX_SYMBOLS symbols");
+
+    ConstPlan { symbols, len }
+}
+
+/// A Code 128 barcode held in an array, with room for `N` symbols
+///
+/// For targets where allocating is unwelcome: encoding a barcode that fits
+/// uses the stack alone, about 400 bytes per symbol of room while planning.
+/// The crate still needs an allocator, as every build requires the `alloc`
+/// feature, and errors carry a formatted `String`, so failing does
+/// allocate.
+///
+/// ```
+/// use feather_code::barcode::code128::Code128Arr;
+/// use feather_code::barcode::format::{Decode, Format};
+///
+/// let code = Code128Arr::<16>::encode("PJJ123C").unwrap();
+///
+/// assert_eq!(code.as_slice().len(), 10);
+/// assert!(code.checksum());
+/// assert_eq!(code.decode(), Ok(String::from("PJJ123C")));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Code128Arr<const N: usize>([Pattern; N], usize);
+
+impl<const N: usize> Code128Arr<N> {
+    /// Encode ASCII text into the same symbols as `str::encode`
+    ///
+    /// Fails with `BadFormat` for non-ASCII text, or text needing
+    /// more than `N` symbols, and with `InvalidLength` for text needing more
+    /// than `MAX_SYMBOLS`.
+    pub fn encode(text: &str) -> Result<Code128Arr<N>> {
+        if let Some(c) = text.chars().find(|c| !c.is_ascii()) {
+            return Err(Error::BadFormat(format!("cannot encode non-ASCII character {:?}", c)));
+        }
+
+        let too_long = || Error::BadFormat(format!("{:?} needs more than {} symbols", text, N));
+
+        // No symbol holds more than two characters, so text this long
+        // could never fit, and two positions per symbol are enough
+        if text.len() >= 2 * N {
+            return Err(too_long());
+        }
+
+        let mut cost = [[[NONE; 6]; 2]; N];
+        let mut back = [[[NO_STEP; 6]; 2]; N];
+        let mut symbols = [0; N];
+        let len = plan_into(text.as_bytes(),
+                            cost.as_flattened_mut(),
+                            back.as_flattened_mut(),
+                            &mut symbols);
+
+        if len == NONE {
+            return Err(too_long());
+        }
+
+        if len > MAX_SYMBOLS {
+            let diagnostic = Diagnostic::TooManySymbols { count: len };
+            return Err(Error::InvalidLength(diagnostic.to_string()));
+        }
+
+        let mut patterns = [Pattern::C0; N];
+        for (p, &u) in patterns.iter_mut().zip(&symbols[..len]) {
+            *p = Pattern::from_u8(u);
+        }
+
+        Ok(Code128Arr(patterns, len))
+    }
+
+    /// The symbols, including the start, check, and stop symbols
+    pub fn as_slice(&self) -> &[Pattern] {
+        &self.0[..self.1]
+    }
+
+    /// Borrow as a `Code128`
+    pub fn as_code128(&self) -> Code128<'_, Pattern> {
+        Code128::new(self.as_slice())
+    }
+}
+
+impl<const N: usize> Format for Code128Arr<N> {
+    fn checksum(&self) -> bool {
+        self.as_code128().checksum()
+    }
+}
+
+impl<const N: usize, T> Decode<T> for Code128Arr<N>
+    where for<'a> Code128<'a, Pattern>: Decode<T>
+{
+    fn decode(&self) -> Result<T> {
+        self.as_code128().decode()
+    }
+}
+
+/// Encode ASCII text at compile time
+///
+/// Expands to a `&'static [Pattern; N]` holding the start symbol, the
+/// shortest data symbols, the check symbol, and the stop symbol, the same
+/// symbols `str::encode` produces.  The text must be a constant expression;
+/// non-ASCII text, or text needing more than `MAX_SYMBOLS` symbols, fails
+/// to compile.
+///
+/// ```
+/// #[macro_use]
+/// extern crate feather_code;
+///
+/// use feather_code::barcode::code128::{Code128Buf, Pattern};
+/// use feather_code::barcode::format::Encode;
+///
+/// static CODE: &[Pattern] = code128!("PJJ123C");
+///
+/// fn main() {
+///     let runtime: Code128Buf<Pattern> = "PJJ123C".encode().unwrap();
+///
+///     assert_eq!(CODE, runtime.symbols());
+/// }
+/// ```
+///
+/// ```compile_fail
+/// # #[macro_use] extern crate feather_code;
+/// # fn main() {
+/// let code = code128!("café");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! code128 {
+    ($text:expr) => {{
+        const TEXT: &str = $text;
+        const POSITIONS: usize = TEXT.len() + 1;
+        const CAPACITY: usize = 2 * TEXT.len() + 3;
+        const PLAN: $crate::barcode::code128::ConstPlan<CAPACITY> =
+            $crate::barcode::code128::const_plan::<POSITIONS, CAPACITY>(TEXT);
+        const CODE: [$crate::barcode::code128::Pattern; PLAN.len()] = PLAN.to_array();
+        &CODE
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::String;
+    use alloc::vec::Vec;
+    use barcode::code128::Code128Buf;
+    use barcode::format::Encode;
+
+    fn runtime(text: &str) -> Vec<u8> {
+        let code: Code128Buf<Pattern> = text.encode().unwrap();
+        code.symbols().iter().map(|p| p.as_u8()).collect()
+    }
+
+    #[test]
+    fn macro_matches_runtime_encoder() {
+        let pjj: &[Pattern] = code128!("PJJ123C");
+        let digits: &[Pattern] = code128!("42184020500");
+        let shift: &[Pattern] = code128!("SHiFT\x00!");
+
+        for &(code, text) in &[(pjj, "PJJ123C"), (digits, "42184020500"), (shift, "SHiFT\x00!")] {
+            let symbols: Vec<u8> = code.iter().map(|p| p.as_u8()).collect();
+            assert_eq!(symbols, runtime(text));
+        }
+    }
+
+    #[test]
+    fn macro_output_decodes() {
+        static CODE: &[Pattern] = code128!("HackFSU 2024");
+        let code = Code128::new(CODE);
+
+        assert!(code.checksum());
+        assert_eq!(code.decode(), Ok(String::from("HackFSU 2024")));
+    }
+
+    #[test]
+    #[should_panic(expected = "only ASCII")]
+    fn plan_rejects_non_ascii() {
+        const_plan::<8, 16>("café");
+    }
+
+    #[test]
+    fn plan_encodes_empty_text() {
+        assert_eq!(const_plan::<1, 3>("").as_slice(), &[104, 1, 106]);
+    }
+
+    #[test]
+    #[should_panic(expected = "more than MAX_SYMBOLS")]
+    fn plan_rejects_oversize_text() {
+        let text: String = (0..MAX_SYMBOLS - 2).map(|_| 'x').collect();
+
+        const_plan::<{ MAX_SYMBOLS - 1 }, { 2 * MAX_SYMBOLS - 1 }>(&text);
+    }
+
+    #[test]
+    fn array_holds_short_text() {
+        let code = Code128Arr::<32>::encode("HackFSU 2024").unwrap();
+        let symbols: Vec<u8> = code.as_slice().iter().map(|p| p.as_u8()).collect();
+
+        assert_eq!(symbols, runtime("HackFSU 2024"));
+        assert!(code.checksum());
+        assert_eq!(code.decode(), Ok(String::from("HackFSU 2024")));
+    }
+
+    #[test]
+    fn array_rejects_what_does_not_fit() {
+        // Exactly ten symbols
+        assert!(Code128Arr::<10>::encode("PJJ123C").is_ok());
+        assert_eq!(Code128Arr::<9>::encode("PJJ123C"),
+                   Err(Error::BadFormat(String::from("\"PJJ123C\" needs more than 9 symbols"))));
+        assert!(Code128Arr::<4>::encode("12345678").is_err());
+        assert!(Code128Arr::<3>::encode("").is_ok());
+        assert!(Code128Arr::<32>::encode("caf\u{e9}").is_err());
+
+        let text: String = (0..MAX_SYMBOLS - 2).map(|_| 'x').collect();
+        assert_eq!(Code128Arr::<{ MAX_SYMBOLS + 1 }>::encode(&text),
+                   Err(Error::InvalidLength(
+                       String::from("233 symbols, more than the 232 allowed"))));
+    }
+
+    quickcheck! {
+        fn array_matches_runtime_encoder(bytes: Vec<u8>) -> bool {
+            let text: String = bytes.iter().take(40).map(|&b| (b & 0x7f) as char).collect();
+
+            match Code128Arr::<64>::encode(&text) {
+                Ok(code) => code.as_slice().iter().map(|p| p.as_u8()).eq(runtime(&text)),
+                Err(_) => runtime(&text).len() > 64,
+            }
+        }
+
+        fn plan_matches_runtime_encoder(bytes: Vec<u8>) -> bool {
+            let text: String = bytes.iter().take(60).map(|&b| (b & 0x7f) as char).collect();
+
+            const_plan::<61, 123>(&text).as_slice() == &runtime(&text)[..]
+        }
+    }
+
+    #[test]
+    fn plan_matches_runtime_encoder_exhaustively() {
+        // Digits for Code C, and a character only A, only B, or both have
+        let alphabet = b"01Aa\x01";
+        let mut texts = vec![Vec::new()];
+
+        for _ in 0..6 {
+            texts = texts.iter()
+                .flat_map(|t| alphabet.iter().map(move |&c| [&t[..], &[c]].concat()))
+                .collect();
+
+            for text in &texts {
+                let text = ::core::str::from_utf8(text).unwrap();
+                assert_eq!(const_plan::<7, 15>(text).as_slice(), &runtime(text)[..], "{:?}", text);
+            }
+        }
+    }
+}
@@ -0,0 +1,423 @@
+//  This Source Code Form is subject to the terms of the Mozilla Public
+//  License, v. 2.0. If a copy of the MPL was not distributed with this
+//  file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! The symbology state machine behind the Code 128 decoders
+//!
+//! The walker steps through the data symbols on demand, tracking the active
+//! symbology, pending shifts, and FNC4, and yields one decoded byte at a
+//! time.  Code C symbols decode to two digits, the second of which is held
+//! back until the next step; in Code C, values above 99 other than the
+//! switches and FNC1 are errors, as in the other symbologies.
+
+use core::fmt::Debug;
+use core::iter::FusedIterator;
+
+use super::{Code128, DecodeInfo, Encoding, Symbology};
+use barcode::format::{Error, Result, AFTER_SHIFT, IN_DATA};
+
+/// One step of a walk: a decoded byte, or a function code that carries no
+/// character
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Item {
+    /// A decoded byte
+    Byte(u8),
+    /// FNC1 to FNC4, with the index and value of its symbol; FNC4 is only
+    /// reported when it isn't extending characters
+    Function {
+        /// Position among the data symbols
+        index: usize,
+        /// Value of the symbol
+        value: u8,
+    },
+}
+
+/// Lazily decoded bytes of a Code 128 barcode
+pub struct Walker<'a, E: 'a + Encoding> {
+    code: Code128<'a, E>,
+    checked: bool,
+    reversed: bool,
+    data: &'a [E],
+    index: usize,
+    extended: bool,
+    current: Symbology,
+    shift: Option<Symbology>,
+    latched: bool,
+    fnc4: bool,
+    pending: Option<u8>,
+    /// Whether an FNC2 has been read, asking for the next barcode's data
+    /// to be appended
+    pub fnc2: bool,
+    /// What the walk has found so far about how the barcode is encoded,
+    /// when the walker was built by `with_info`
+    pub info: Option<DecodeInfo>,
+    /// Symbology selected by the start symbol
+    pub start: Symbology,
+    /// Symbology the last byte was decoded in, counting shifts
+    pub set: Symbology,
+}
+
+impl<'a, E: 'a + Encoding> Walker<'a, E> {
+    /// Walk the data symbols of `code`
+    ///
+    /// When `extended` is set, FNC4 adds 128 to the following data
+    /// character, and two consecutive FNC4 symbols latch that behaviour
+    /// until the next pair; otherwise FNC4 is skipped like the other
+    /// function codes.  Code C digits are never extended.
+    pub fn new(code: Code128<'a, E>, extended: bool) -> Walker<'a, E> {
+        Walker {
+            code,
+            checked: false,
+            reversed: false,
+            data: &[],
+            index: 0,
+            extended,
+            current: Symbology::A,
+            shift: None,
+            latched: false,
+            fnc4: false,
+            pending: None,
+            fnc2: false,
+            info: None,
+            start: Symbology::A,
+            set: Symbology::A,
+        }
+    }
+
+    /// Walk the data symbols of `code` like `new`, also gathering a
+    /// `DecodeInfo` as the walk goes
+    pub fn with_info(code: Code128<'a, E>, extended: bool) -> Walker<'a, E> {
+        let info = DecodeInfo {
+            start_symbology: Symbology::A,
+            switches: 0,
+            shifts: 0,
+            fnc1_present: false,
+            fnc4_present: false,
+            symbol_count: 0,
+        };
+
+        Walker { info: Some(info), ..Walker::new(code, extended) }
+    }
+
+    /// Walk the data symbols of `code` stored back to front, stop symbol
+    /// first, reading them in their logical order
+    pub fn reversed(code: Code128<'a, E>, extended: bool) -> Walker<'a, E> {
+        Walker { reversed: true, ..Walker::new(code, extended) }
+    }
+}
+
+impl<'a, E: 'a + Encoding> Walker<'a, E> {
+    /// End the walk at the symbol just read, building its error from its
+    /// index
+    fn fail<F: FnOnce(usize) -> Error>(&mut self, err: F) -> Error {
+        let index = self.index - 1;
+        self.index = self.data.len();
+
+        err(index)
+    }
+
+    /// The data symbol at `index` in reading order
+    fn symbol(&self, index: usize) -> Option<&'a E> {
+        if self.reversed {
+            self.data.len().checked_sub(index + 1).and_then(|i| self.data.get(i))
+        } else {
+            self.data.get(index)
+        }
+    }
+
+    /// Update the `DecodeInfo`, if one is being gathered
+    fn track<F: FnOnce(&mut DecodeInfo)>(&mut self, update: F) {
+        if let Some(ref mut info) = self.info {
+            update(info);
+        }
+    }
+
+    fn shift_to(&mut self, set: Symbology) {
+        self.track(|info| info.shifts += 1);
+        self.shift = Some(set);
+    }
+
+    fn switch_to(&mut self, set: Symbology) {
+        self.track(|info| info.switches += 1);
+        self.current = set;
+    }
+}
+
+impl<'a, E: 'a + Encoding + Debug> Walker<'a, E> {
+    /// Step to the next byte or function code
+    pub fn next_item(&mut self) -> Option<Result<Item>> {
+        if let Some(b) = self.pending.take() {
+            return Some(Ok(Item::Byte(b)));
+        }
+
+        if !self.checked {
+            self.checked = true;
+
+            match self.code.check(self.reversed) {
+                Ok((start, data)) => {
+                    self.current = start;
+                    self.start = start;
+                    self.data = data;
+                    self.track(|info| {
+                        info.start_symbology = start;
+                        info.symbol_count = data.len() + 3;
+                    });
+                }
+                Err(e) => return Some(Err(e)),
+            }
+        }
+
+        while let Some(e) = self.symbol(self.index) {
+            self.index += 1;
+
+            let shifted = self.shift.is_some();
+            let active = self.shift.take().unwrap_or(self.current);
+            let high = if self.extended && (self.latched != self.fnc4) { 128 } else { 0 };
+
+            match (active, e.as_u8()) {
+                // A shift covers exactly one character, and start symbols
+                // only begin a barcode
+                (_, to @ 98..=101) if shifted => return Some(Err(self.fail(|index| {
+                    Error::BadTransitionAt { index, from: active, to }
+                }))),
+                (_, to @ 103..=105) => return Some(Err(self.fail(|index| {
+                    Error::BadTransitionAt { index, from: active, to }
+                }))),
+                (Symbology::C, u @ 0..=99) => {
+                    self.set = Symbology::C;
+                    self.pending = Some(b'0' + u % 10);
+                    return Some(Ok(Item::Byte(b'0' + u / 10)));
+                }
+                (Symbology::A, u @ 0..=63) | (Symbology::B, u @ 0..=95) => {
+                    self.set = active;
+                    self.fnc4 = false;
+                    return Some(Ok(Item::Byte(u + 32 + high)));
+                }
+                (Symbology::A, u @ 64..=95) => {
+                    self.set = active;
+                    self.fnc4 = false;
+                    return Some(Ok(Item::Byte(u - 64 + high)));
+                }
+                (Symbology::A, value @ 101) | (Symbology::B, value @ 100) => {
+                    self.track(|info| info.fnc4_present = true);
+                    if self.fnc4 {
+                        self.latched = !self.latched;
+                    }
+                    self.fnc4 = !self.fnc4;
+
+                    if !self.extended {
+                        return Some(Ok(Item::Function { index: self.index - 1, value }));
+                    }
+                }
+                // FNC1, FNC2, and FNC3 carry no characters
+                (_, value @ 96..=97) | (_, value @ 102) => {
+                    match value {
+                        97 => self.fnc2 = true,
+                        102 => self.track(|info| info.fnc1_present = true),
+                        _ => {}
+                    }
+
+                    return Some(Ok(Item::Function { index: self.index - 1, value }));
+                }
+                (Symbology::A, 98) => self.shift_to(Symbology::B),
+                (Symbology::B, 98) => self.shift_to(Symbology::A),
+                (_, 99) => self.switch_to(Symbology::C),
+                (_, 100) => self.switch_to(Symbology::B),
+                (_, 101) => self.switch_to(Symbology::A),
+                (_, value) => return Some(Err(self.fail(|index| {
+                    let context = if shifted { AFTER_SHIFT } else { IN_DATA };
+                    Error::BadSymbolAt { index, value, context }
+                }))),
+            }
+        }
+
+        None
+    }
+}
+
+impl<'a, E: 'a + Encoding + Debug> Iterator for Walker<'a, E> {
+    type Item = Result<u8>;
+
+    /// Step to the next byte, skipping function codes
+    fn next(&mut self) -> Option<Result<u8>> {
+        loop {
+            match self.next_item()? {
+                Ok(Item::Byte(b)) => return Some(Ok(b)),
+                Ok(Item::Function { .. }) => {}
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+impl<'a, E: 'a + Encoding + Debug> FusedIterator for Walker<'a, E> {}
+
+/// Iterator over the characters of a Code 128 barcode
+///
+/// Returned by [`Code128::chars`](struct.Code128.html#method.chars).
+pub struct DecodeChars<'a, E: 'a + Encoding>(Walker<'a, E>);
+
+impl<'a, E: 'a + Encoding> DecodeChars<'a, E> {
+    pub(super) fn new(code: Code128<'a, E>) -> DecodeChars<'a, E> {
+        DecodeChars(Walker::new(code, true))
+    }
+}
+
+impl<'a, E: 'a + Encoding + Debug> Iterator for DecodeChars<'a, E> {
+    type Item = Result<char>;
+
+    fn next(&mut self) -> Option<Result<char>> {
+        self.0.next().map(|r| r.map(char::from))
+    }
+}
+
+impl<'a, E: 'a + Encoding + Debug> FusedIterator for DecodeChars<'a, E> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::String;
+    use alloc::vec::Vec;
+    use barcode::code128::compute_checksum;
+    use barcode::format::Decode;
+
+    const PJJ123C: [u8; 10] = [103, 48, 42, 42, 17, 18, 19, 35, 54, 106];
+
+    #[test]
+    fn chars_stop_early() {
+        let mut chars = Code128::new(&PJJ123C).chars();
+
+        assert_eq!(chars.next(), Some(Ok('P')));
+        assert_eq!(chars.next(), Some(Ok('J')));
+    }
+
+    #[test]
+    fn chars_split_code_c_pairs() {
+        let symbols = [105u8, 12, 34, 56, 44, 106];
+        let chars: Vec<Result<char>> = Code128::new(&symbols).chars().collect();
+
+        assert_eq!(chars, vec![Ok('1'), Ok('2'), Ok('3'), Ok('4'), Ok('5'), Ok('6')]);
+    }
+
+    #[test]
+    fn chars_error_at_bad_symbol() {
+        let data = [33u8, 34, 105, 35];
+        let mut symbols = vec![104u8];
+        symbols.extend(&data);
+        symbols.push(compute_checksum(Symbology::B, &data));
+        symbols.push(106);
+
+        let chars: Vec<Result<char>> = Code128::new(&symbols).chars().collect();
+
+        assert_eq!(chars, vec![Ok('A'), Ok('B'), Err(Error::BadTransitionAt {
+            index: 2,
+            from: Symbology::B,
+            to: 105,
+        })]);
+    }
+
+    #[test]
+    fn error_index_after_shift() {
+        let data = [12u8, 34, 101, 48, 98, 104, 35];
+        let mut symbols = vec![105u8];
+        symbols.extend(&data);
+        symbols.push(compute_checksum(Symbology::C, &data));
+        symbols.push(106);
+
+        let code = Code128::new(&symbols);
+
+        let err = || Error::BadTransitionAt { index: 5, from: Symbology::B, to: 104 };
+
+        assert_eq!(Decode::<String>::decode(&code), Err(err()));
+        assert_eq!(Decode::<Vec<u8>>::decode(&code), Err(err()));
+    }
+
+    fn with_check(start: Symbology, data: &[u8]) -> Vec<u8> {
+        let mut symbols = vec![start.start_value()];
+        symbols.extend(data);
+        symbols.push(compute_checksum(start, data));
+        symbols.push(106);
+        symbols
+    }
+
+    #[test]
+    fn switch_after_shift_is_bad_transition() {
+        let symbols = with_check(Symbology::A, &[33, 98, 99, 12]);
+
+        assert_eq!(Decode::<String>::decode(&Code128::new(&symbols)),
+                   Err(Error::BadTransitionAt { index: 2, from: Symbology::B, to: 99 }));
+    }
+
+    #[test]
+    fn interior_stop_is_bad_format() {
+        let symbols = with_check(Symbology::B, &[33, 106, 34]);
+
+        assert_eq!(Decode::<String>::decode(&Code128::new(&symbols)),
+                   Err(Error::BadFormat(String::from("stop symbol at 2 before the end"))));
+    }
+
+    #[test]
+    fn stop_after_shift_is_bad_format() {
+        let symbols = with_check(Symbology::B, &[33, 98, 106, 34]);
+
+        assert_eq!(Decode::<String>::decode(&Code128::new(&symbols)),
+                   Err(Error::BadFormat(String::from("stop symbol at 3 before the end"))));
+    }
+
+    #[test]
+    fn code_c_rejects_values_above_digit_pairs() {
+        let start = with_check(Symbology::C, &[12, 104, 34]);
+        let stop = with_check(Symbology::C, &[12, 106, 34]);
+        let out_of_range = with_check(Symbology::C, &[12, 150, 34]);
+
+        let cases = [
+            (&start, Error::BadTransitionAt { index: 1, from: Symbology::C, to: 104 }),
+            (&stop, Error::BadFormat(String::from("stop symbol at 2 before the end"))),
+            (&out_of_range, Error::BadFormat(String::from("symbol 2 has value 150, above 106"))),
+        ];
+
+        for &(symbols, ref err) in &cases {
+            let code = Code128::new(&symbols[..]);
+
+            assert_eq!(Decode::<String>::decode(&code).as_ref(), Err(err));
+            assert_eq!(Decode::<Vec<u8>>::decode(&code).as_ref(), Err(err));
+        }
+    }
+
+    #[test]
+    fn chars_malformed_yields_one_error() {
+        let chars: Vec<Result<char>> = Code128::new(&PJJ123C[..9]).chars().collect();
+
+        assert_eq!(chars,
+                   vec![Err(Error::BadFormat(String::from("missing start or stop symbol")))]);
+    }
+
+    #[test]
+    fn walker_applies_fnc4() {
+        let data = [33u8, 100, 33];
+        let symbols = [104u8, 33, 100, 33, compute_checksum(Symbology::B, &data), 106];
+
+        let bytes: Result<Vec<u8>> = Walker::new(Code128::new(&symbols), true).collect();
+        let chars: Result<String> = Code128::new(&symbols).chars().collect();
+
+        assert_eq!(bytes, Ok(vec![b'A', 0xc1]));
+        assert_eq!(chars, Ok(String::from("A\u{c1}")));
+    }
+
+    #[test]
+    fn walker_gathers_info_only_when_asked() {
+        let data = [33u8, 100, 33];
+        let symbols = [104u8, 33, 100, 33, compute_checksum(Symbology::B, &data), 106];
+
+        let mut plain = Walker::new(Code128::new(&symbols), true);
+        let mut tracked = Walker::with_info(Code128::new(&symbols), true);
+        plain.by_ref().count();
+        tracked.by_ref().count();
+
+        assert_eq!(plain.info, None);
+        assert_eq!(plain.start, Symbology::B);
+        let info = tracked.info.unwrap();
+        assert_eq!((info.start_symbology, info.symbol_count), (Symbology::B, 6));
+        assert!(info.fnc4_present);
+    }
+}
@@ -0,0 +1,740 @@
+//  This Source Code Form is subject to the terms of the Mozilla Public
+//  License, v. 2.0. If a copy of the MPL was not distributed with this
+//  file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Shortest-sequence planning for the Code 128 encoders
+//!
+//! The planner walks the input once, tracking the cheapest way to reach each
+//! combination of active symbology and FNC4 latch after every byte.  Bytes in
+//! `128..=255` are written as the byte less 128, either prefixed with a
+//! single FNC4 or inside a run latched by a pair of FNC4 symbols, whichever
+//! is shorter.  Code C is only entered while unlatched.
+//!
+//! The input may also carry function codes and raw symbols, which
+//! `Code128Builder` uses to place them among the characters.  The search
+//! is a `const fn` over buffers its callers supply, so `code128!` and
+//! `Code128Arr` run it too, without allocating.
+
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use super::{encode_with, weighted_checksum, Code128Buf, Diagnostic, EncodeOptions, Encoding,
+            MixedEncodeStats, Pattern, Symbology, MAX_SYMBOLS};
+use barcode::format::{Encode, Error, Result};
+
+const SHIFT: u8 = 98;
+
+/// No value yet: a cost not reached, or a step with no predecessor
+pub const NONE: usize = usize::MAX;
+
+/// One unit of planner input
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Input {
+    /// A data byte
+    Byte(u8),
+    Fnc1,
+    Fnc2,
+    Fnc3,
+    Fnc4,
+    /// A symbol written as is, which must leave the symbology and latch
+    /// alone
+    Raw(u8),
+    /// A character that must be written in the given symbology, unlatched;
+    /// in Code C, one of a pair of digits
+    In(Symbology, u8),
+    /// A character that must be written with a shift to the given
+    /// symbology
+    Shifted(Symbology, u8),
+}
+
+/// What the planner reads: plain bytes, or `Input`
+#[derive(Clone, Copy)]
+pub enum Items<'a> {
+    Bytes(&'a [u8]),
+    Inputs(&'a [Input]),
+}
+
+impl<'a> From<&'a [u8]> for Items<'a> {
+    fn from(bytes: &'a [u8]) -> Items<'a> {
+        Items::Bytes(bytes)
+    }
+}
+
+impl<'a> From<&'a [Input]> for Items<'a> {
+    fn from(inputs: &'a [Input]) -> Items<'a> {
+        Items::Inputs(inputs)
+    }
+}
+
+impl<'a> Items<'a> {
+    const fn len(self) -> usize {
+        match self {
+            Items::Bytes(bytes) => bytes.len(),
+            Items::Inputs(inputs) => inputs.len(),
+        }
+    }
+
+    const fn get(self, i: usize) -> Option<Input> {
+        match self {
+            Items::Bytes(bytes) if i < bytes.len() => Some(Input::Byte(bytes[i])),
+            Items::Inputs(inputs) if i < inputs.len() => Some(inputs[i]),
+            _ => None,
+        }
+    }
+}
+
+/// Planner state: an active symbology and whether FNC4 is latched
+#[derive(Clone, Copy, PartialEq)]
+pub struct State {
+    set: Symbology,
+    latched: bool,
+}
+
+/// Every planner state, in order of preference, so that ties start in
+/// `Symbology::default()`; Code C is never latched
+const STATES: [State; 5] = [
+    State { set: Symbology::B, latched: false },
+    State { set: Symbology::A, latched: false },
+    State { set: Symbology::C, latched: false },
+    State { set: Symbology::B, latched: true },
+    State { set: Symbology::A, latched: true },
+];
+
+impl State {
+    const fn index(self) -> usize {
+        self.set.index() + if self.latched { 3 } else { 0 }
+    }
+}
+
+/// How a state was reached: the previous position and state, and the
+/// symbols emitted on the way
+#[derive(Clone, Copy)]
+pub struct Step {
+    pos: usize,
+    state: State,
+    symbols: [u8; 2],
+    len: usize,
+}
+
+/// The step recorded for a state not reached yet
+pub const NO_STEP: Step = Step { pos: NONE, state: STATES[0], symbols: [0; 2], len: 0 };
+
+/// Symbol for a 7-bit character in symbology A or B, if it has one
+pub const fn char_value(set: Symbology, c: u8) -> Option<u8> {
+    match (set, c) {
+        (Symbology::A, 0..=31) => Some(c + 64),
+        (Symbology::A, 32..=95) | (Symbology::B, 32..=127) => Some(c - 32),
+        _ => None,
+    }
+}
+
+const fn fnc4(set: Symbology) -> u8 {
+    match set {
+        Symbology::A => 101,
+        _ => 100,
+    }
+}
+
+const fn other(set: Symbology) -> Symbology {
+    match set {
+        Symbology::A => Symbology::B,
+        _ => Symbology::A,
+    }
+}
+
+const fn digit(input: Option<Input>) -> Option<u8> {
+    match input {
+        Some(Input::Byte(b)) if b.is_ascii_digit() => Some(b - b'0'),
+        _ => None,
+    }
+}
+
+/// A step writing `symbols` from state `s` at `pos`
+const fn step(pos: usize, s: State, symbols: &[u8]) -> Step {
+    let mut step = Step { pos, state: s, symbols: [0; 2], len: symbols.len() };
+    step.symbols[0] = symbols[0];
+    if symbols.len() > 1 {
+        step.symbols[1] = symbols[1];
+    }
+
+    step
+}
+
+/// The way of writing the input at `items[i]` in state `s`, if there is
+/// one, with the number of items consumed
+const fn advance(items: Items, i: usize, s: State) -> Option<(usize, Step)> {
+    let b = match items.get(i) {
+        Some(Input::Byte(b)) => b,
+        // FNC1 is the only function code Code C has
+        Some(Input::Fnc1) => return Some((1, step(i, s, &[102]))),
+        Some(Input::Fnc2) | Some(Input::Fnc3) | Some(Input::Fnc4)
+            if matches!(s.set, Symbology::C) => return None,
+        Some(Input::Fnc2) => return Some((1, step(i, s, &[97]))),
+        Some(Input::Fnc3) => return Some((1, step(i, s, &[96]))),
+        Some(Input::Fnc4) => return Some((1, step(i, s, &[fnc4(s.set)]))),
+        Some(Input::Raw(v)) => return Some((1, step(i, s, &[v]))),
+        Some(Input::In(set, c)) => {
+            if set as u8 != s.set as u8 || s.latched {
+                return None;
+            }
+
+            if let Symbology::C = set {
+                // Code C digits come in pairs, both marked for Code C
+                return match items.get(i + 1) {
+                    Some(Input::In(Symbology::C, lo)) => {
+                        Some((2, step(i, s, &[(c - b'0') * 10 + lo - b'0'])))
+                    }
+                    _ => None,
+                };
+            }
+
+            return match char_value(set, c) {
+                Some(v) => Some((1, step(i, s, &[v]))),
+                None => None,
+            };
+        }
+        Some(Input::Shifted(set, c)) => {
+            if other(set) as u8 != s.set as u8 || s.latched {
+                return None;
+            }
+
+            return match char_value(set, c) {
+                Some(v) => Some((1, step(i, s, &[SHIFT, v]))),
+                None => None,
+            };
+        }
+        None => return None,
+    };
+
+    if let Symbology::C = s.set {
+        return match (digit(items.get(i)), digit(items.get(i + 1))) {
+            (Some(hi), Some(lo)) => Some((2, step(i, s, &[hi * 10 + lo]))),
+            _ => None,
+        };
+    }
+
+    let c = b & 0x7f;
+    let extended = b >= 128;
+
+    if let Some(v) = char_value(s.set, c) {
+        if extended == s.latched {
+            Some((1, step(i, s, &[v])))
+        } else {
+            Some((1, step(i, s, &[fnc4(s.set), v])))
+        }
+    } else if extended == s.latched {
+        match char_value(other(s.set), c) {
+            Some(v) => Some((1, step(i, s, &[SHIFT, v]))),
+            None => None,
+        }
+    } else {
+        None
+    }
+}
+
+/// Replace the step into `to` at `pos` if `new` is cheaper, recording it
+/// only if `back` reaches that far
+const fn update(cost: &mut [[usize; 6]],
+                back: &mut [[Step; 6]],
+                pos: usize,
+                to: State,
+                new: usize,
+                step: Step)
+                -> bool {
+    let row = pos % cost.len();
+    if new < cost[row][to.index()] {
+        cost[row][to.index()] = new;
+        if pos < back.len() {
+            back[pos][to.index()] = step;
+        }
+        true
+    } else {
+        false
+    }
+}
+
+/// Find the fewest start and data symbols for `items`, returning their
+/// number and the state they end in
+///
+/// This is the one planner behind every Code 128 encoder, at runtime and
+/// at compile time.  `cost` holds the cheapest way into each state by
+/// position modulo its length: at least three rows, or one per position
+/// from `0` to `items.len()`.  `back` records how each state was reached,
+/// one row per position, or nothing if it is empty.  Neither needs
+/// clearing beforehand.
+pub const fn search(items: Items,
+                    cost: &mut [[usize; 6]],
+                    back: &mut [[Step; 6]])
+                    -> (usize, State) {
+    let n = items.len();
+
+    let mut r = 0;
+    while r < cost.len() {
+        cost[r] = [NONE; 6];
+        r += 1;
+    }
+
+    let mut k = 0;
+    while k < 3 {
+        let s = STATES[k];
+        let start = Step { symbols: [s.set.start_value(), 0], len: 1, ..NO_STEP };
+        update(cost, back, 0, s, 1, start);
+        k += 1;
+    }
+
+    let mut i = 0;
+    while i <= n {
+        let row = i % cost.len();
+
+        // Switching symbologies and toggling the latch don't consume input,
+        // so settle those before moving forward
+        let mut changed = true;
+        while changed {
+            changed = false;
+
+            let mut f = 0;
+            while f < STATES.len() {
+                let from = STATES[f];
+                let c = cost[row][from.index()];
+                f += 1;
+                if c == NONE {
+                    continue;
+                }
+
+                let mut t = 0;
+                while t < STATES.len() {
+                    let to = STATES[t];
+                    t += 1;
+                    if to.latched == from.latched && to.set as u8 != from.set as u8 {
+                        let step = step(i, from, &[to.set.code_value()]);
+                        changed |= update(cost, back, i, to, c + 1, step);
+                    }
+                }
+
+                if !matches!(from.set, Symbology::C) {
+                    let fnc = fnc4(from.set);
+                    let to = State { set: from.set, latched: !from.latched };
+                    changed |= update(cost, back, i, to, c + 2, step(i, from, &[fnc, fnc]));
+                }
+            }
+        }
+
+        if i == n {
+            break;
+        }
+
+        // Two ahead may hold a stale row when `cost` is a ring of three
+        if i + 2 <= n {
+            cost[(i + 2) % cost.len()] = [NONE; 6];
+        }
+
+        let mut k = 0;
+        while k < STATES.len() {
+            let s = STATES[k];
+            let c = cost[row][s.index()];
+            k += 1;
+            if c == NONE {
+                continue;
+            }
+
+            if let Some((consumed, step)) = advance(items, i, s) {
+                update(cost, back, i + consumed, s, c + step.len, step);
+            }
+        }
+
+        i += 1;
+    }
+
+    // The first cheapest state, in order of preference
+    let row = n % cost.len();
+    let mut best = STATES[0];
+    let mut k = 1;
+    while k < STATES.len() {
+        if cost[row][STATES[k].index()] < cost[row][best.index()] {
+            best = STATES[k];
+        }
+        k += 1;
+    }
+
+    (cost[row][best.index()], best)
+}
+
+/// Write the symbols `search` found, ending in state `end` after `n`
+/// items, to the front of `symbols`, returning the number of switches and
+/// shifts among them
+pub const fn trace(back: &[[Step; 6]],
+                   n: usize,
+                   end: State,
+                   len: usize,
+                   symbols: &mut [u8])
+                   -> (usize, usize) {
+    let (mut switches, mut shifts) = (0, 0);
+    let mut filled = len;
+    let (mut pos, mut state) = (n, end);
+
+    while pos != NONE {
+        let step = back[pos][state.index()];
+
+        if step.pos == pos && step.state.set as u8 != state.set as u8 {
+            // Settling stays put; only switches change the symbology there
+            switches += 1;
+        } else if step.pos != NONE && step.pos < pos && step.symbols[0] == SHIFT {
+            shifts += 1;
+        }
+
+        let mut j = step.len;
+        while j > 0 {
+            j -= 1;
+            filled -= 1;
+            symbols[filled] = step.symbols[j];
+        }
+
+        pos = step.pos;
+        state = step.state;
+    }
+
+    (switches, shifts)
+}
+
+/// Plan the start and data symbols for `items`
+pub fn plan<'a, I: Into<Items<'a>>>(items: I) -> Vec<u8> {
+    plan_with_stats(items).0
+}
+
+/// Plan like `plan`, also counting the switches and shifts chosen
+pub fn plan_with_stats<'a, I: Into<Items<'a>>>(items: I) -> (Vec<u8>, MixedEncodeStats) {
+    let items = items.into();
+    let n = items.len();
+    let mut cost = vec![[NONE; 6]; n + 1];
+    let mut back = vec![[NO_STEP; 6]; n + 1];
+
+    let (len, end) = search(items, &mut cost, &mut back);
+    let mut planned = vec![0; len];
+    let (switches, shifts) = trace(&back, n, end, len, &mut planned);
+
+    let stats = MixedEncodeStats {
+        start: Symbology::from_start_value(planned[0]).expect("plans begin with a start symbol"),
+        switches,
+        shifts,
+        symbols: len + 2,
+    };
+
+    (planned, stats)
+}
+
+/// Number of start and data symbols `plan` produces for `items`
+///
+/// Runs the same search as `plan` but keeps only the costs of the three
+/// positions it can reach at once, so it doesn't allocate.
+pub fn planned_len<'a, I: Into<Items<'a>>>(items: I) -> usize {
+    let mut cost = [[NONE; 6]; 3];
+
+    search(items.into(), &mut cost, &mut []).0
+}
+
+/// Append the check and stop symbols to a plan and convert it to patterns
+///
+/// Fails with `InvalidLength` if that makes more than `MAX_SYMBOLS`
+/// symbols, unless `options` allows oversize barcodes.
+pub fn finish(planned: Vec<u8>, options: &EncodeOptions) -> Result<Code128Buf<Pattern>> {
+    let count = planned.len() + 2;
+    if count > MAX_SYMBOLS && !options.allow_oversize {
+        return Err(Error::InvalidLength(Diagnostic::TooManySymbols { count }.to_string()));
+    }
+
+    let start = Symbology::from_start_value(planned[0]).expect("plans begin with a start symbol");
+    let check = weighted_checksum(start, &planned[1..]);
+
+    Ok(Code128Buf::new(planned.into_iter()
+                           .chain(vec![check, u8::stop()])
+                           .map(Pattern::from)
+                           .collect()))
+}
+
+impl Encode<Code128Buf<Pattern>> for [u8] {
+    /// Encode arbitrary bytes, using FNC4 for values in `128..=255`
+    ///
+    /// The result decodes back to the same bytes through
+    /// `Decode<Vec<u8>>`.
+    ///
+    /// Fails with `InvalidLength` for input needing more than `MAX_SYMBOLS`
+    /// symbols.
+    fn encode(&self) -> Result<Code128Buf<Pattern>> {
+        encode_with(self, &EncodeOptions::default())
+    }
+}
+
+impl Encode<Code128Buf<Pattern>> for str {
+    /// Encode ASCII text
+    ///
+    /// Fails with `BadFormat` for characters outside ASCII, since
+    /// `Decode<String>` has no way to read them back.
+    fn encode(&self) -> Result<Code128Buf<Pattern>> {
+        match self.chars().find(|c| !c.is_ascii()) {
+            Some(c) => Err(Error::BadFormat(format!("cannot encode non-ASCII character {:?}", c))),
+            None => self.as_bytes().encode(),
+        }
+    }
+}
+
+/// Plan a number as Code C digit pairs, most significant first
+///
+/// An odd number of digits gets a leading zero, so the whole barcode stays
+/// in Code C; that costs one symbol less than switching out for the last
+/// digit.
+fn plan_number(mut n: u128) -> Vec<u8> {
+    let mut planned = Vec::with_capacity(21);
+
+    loop {
+        planned.push((n % 100) as u8);
+        n /= 100;
+        if n == 0 {
+            break;
+        }
+    }
+
+    planned.push(Symbology::C.start_value());
+    planned.reverse();
+    planned
+}
+
+impl Encode<Code128Buf<Pattern>> for u64 {
+    /// Encode the number's decimal digits in Code C, without formatting it
+    ///
+    /// A number with an odd number of digits is written with a leading
+    /// zero: `12345` decodes as `"012345"`, which `decode_as` parses back
+    /// to `12345`.
+    ///
+    /// ```
+    /// use feather_code::barcode::code128::{Code128Buf, Pattern};
+    /// use feather_code::barcode::format::{DecodeAs, Encode};
+    ///
+    /// let code: Code128Buf<Pattern> = 12345u64.encode().unwrap();
+    ///
+    /// assert_eq!(code.to_bytes(), [105, 1, 23, 45, 81, 106]);
+    /// assert_eq!(code.decode_as::<u64>(), Ok(12345));
+    /// ```
+    fn encode(&self) -> Result<Code128Buf<Pattern>> {
+        finish(plan_number(u128::from(*self)), &EncodeOptions::default())
+    }
+}
+
+impl Encode<Code128Buf<Pattern>> for u128 {
+    /// Encode the number's decimal digits in Code C, as for `u64`
+    fn encode(&self) -> Result<Code128Buf<Pattern>> {
+        finish(plan_number(*self), &EncodeOptions::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::String;
+    use barcode::code128::{encoded_len, scan_modules, Diagnostic, ScanOptions, MAX_SYMBOLS};
+    use barcode::format::{Decode, DecodeAs, Format};
+
+    fn symbols(code: &Code128Buf<Pattern>) -> Vec<u8> {
+        code.0.iter().map(Encoding::as_u8).collect()
+    }
+
+    #[test]
+    fn encode_ascii_bytes() {
+        let code: Code128Buf<Pattern> = b"PJJ123C"[..].encode().unwrap();
+
+        assert!(code.checksum());
+        assert_eq!(Decode::<Vec<u8>>::decode(&code), Ok(b"PJJ123C".to_vec()));
+        assert_eq!(symbols(&code).len(), 10);
+    }
+
+    #[test]
+    fn encode_digits_with_code_c() {
+        let code: Code128Buf<Pattern> = b"123456"[..].encode().unwrap();
+
+        assert_eq!(symbols(&code), vec![105, 12, 34, 56, 44, 106]);
+    }
+
+    #[test]
+    fn encode_single_high_byte() {
+        let code: Code128Buf<Pattern> = [b'A', 0xc1][..].encode().unwrap();
+
+        assert_eq!(symbols(&code), vec![104, 33, 100, 33, 24, 106]);
+    }
+
+    #[test]
+    fn encode_high_bytes_round_trip() {
+        let bytes: Vec<u8> = b"Gr\xfc\xdfe \x01\x02 \xc0\xc1\xc2\xc3\xc4\xc5 m\xfcnchen 2024\xff\x80"
+            .to_vec();
+        let code: Code128Buf<Pattern> = bytes[..].encode().unwrap();
+
+        assert!(code.checksum());
+        assert_eq!(Decode::<Vec<u8>>::decode(&code), Ok(bytes));
+    }
+
+    #[test]
+    fn encode_latches_long_high_runs() {
+        let bytes = [0xc1u8; 8];
+        let code: Code128Buf<Pattern> = bytes[..].encode().unwrap();
+        let fnc4s = symbols(&code).iter().filter(|&&u| u == 100).count();
+
+        assert_eq!(fnc4s, 2);
+        assert_eq!(Decode::<Vec<u8>>::decode(&code), Ok(bytes.to_vec()));
+    }
+
+    #[test]
+    fn encode_every_byte() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        let code = encode_with(&bytes, &EncodeOptions { allow_oversize: true }).unwrap();
+
+        assert!(code.checksum());
+        assert_eq!(Decode::<Vec<u8>>::decode(&code), Ok(bytes));
+    }
+
+    #[test]
+    fn encode_up_to_max_symbols() {
+        // One symbol per byte in Code B, plus start, check, and stop
+        let fits = [b'x'; MAX_SYMBOLS - 3];
+        let code: Code128Buf<Pattern> = fits[..].encode().unwrap();
+        assert_eq!(symbols(&code).len(), MAX_SYMBOLS);
+        assert_eq!(code.validate(), Ok(()));
+
+        let over = [b'x'; MAX_SYMBOLS - 2];
+        let msg = "233 symbols, more than the 232 allowed";
+        assert_eq!(Encode::<Code128Buf<Pattern>>::encode(&over[..]),
+                   Err(Error::InvalidLength(String::from(msg))));
+
+        // Allowed, and still read, but reported by validate
+        let code = encode_with(&over, &EncodeOptions { allow_oversize: true }).unwrap();
+        assert_eq!(code.validate(),
+                   Err(vec![Diagnostic::TooManySymbols { count: MAX_SYMBOLS + 1 }]));
+        assert!(code.checksum());
+        assert_eq!(Decode::<Vec<u8>>::decode(&code), Ok(over.to_vec()));
+    }
+
+    #[test]
+    fn encode_str() {
+        let code: Code128Buf<Pattern> = "PJJ123C".encode().unwrap();
+
+        assert_eq!(code.decode(), Ok(String::from("PJJ123C")));
+        assert_eq!(Encode::<Code128Buf<Pattern>>::encode("naïve"),
+                   Err(Error::BadFormat(String::from("cannot encode non-ASCII character 'ï'"))));
+    }
+
+    #[test]
+    fn encode_empty() {
+        let code: Code128Buf<Pattern> = b""[..].encode().unwrap();
+
+        assert_eq!(symbols(&code), [104, 1, 106]);
+        assert_eq!(Decode::<Vec<u8>>::decode(&code), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn encode_empty_text_round_trips() {
+        // Start B, its own value as the check, and stop
+        let code: Code128Buf<Pattern> = "".encode().unwrap();
+        assert_eq!(symbols(&code), [104, 1, 106]);
+        assert!(code.checksum());
+        assert_eq!(code.validate(), Err(vec![Diagnostic::EmptyPayload]));
+
+        let read = scan_modules(&code.modules(), &ScanOptions::default()).unwrap().code;
+        assert_eq!(read, code);
+        assert_eq!(read.decode(), Ok(String::new()));
+        assert_eq!(encoded_len(""), Some(3));
+    }
+
+    #[test]
+    fn encoded_len_stops_at_max_symbols() {
+        let fits = "x".repeat(MAX_SYMBOLS - 3);
+        let over = "x".repeat(MAX_SYMBOLS - 2);
+
+        assert_eq!(encoded_len(&fits), Some(MAX_SYMBOLS));
+        assert_eq!(encoded_len(&over), None);
+        assert!(Encode::<Code128Buf<Pattern>>::encode(&over[..]).is_err());
+    }
+
+    #[test]
+    fn planned_len_matches_plan() {
+        for &bytes in &[&b""[..], b"1", b"12", b"a1234b", b"\xc1\xc1\xc1\xc1", b"\x00a\x00a"] {
+            assert_eq!(planned_len(bytes), plan(bytes).len());
+        }
+
+        let inputs = [Input::Fnc1, Input::Byte(b'a'), Input::Shifted(Symbology::A, 1), Input::Fnc4,
+                      Input::In(Symbology::C, b'1'), Input::In(Symbology::C, b'2')];
+        assert_eq!(planned_len(&inputs[..]), plan(&inputs[..]).len());
+    }
+
+    #[test]
+    fn encode_numbers_in_code_c() {
+        let code: Code128Buf<Pattern> = 123456u64.encode().unwrap();
+        assert_eq!(symbols(&code), vec![105, 12, 34, 56, 44, 106]);
+        assert_eq!(code.decode_as::<u64>(), Ok(123456));
+
+        // Odd digit counts get a leading zero
+        for &(n, text) in &[(0u64, "00"), (7, "07"), (12345, "012345")] {
+            let code: Code128Buf<Pattern> = n.encode().unwrap();
+
+            assert!(code.checksum());
+            assert_eq!(code.decode(), Ok(String::from(text)));
+            assert_eq!(code.decode_as::<u64>(), Ok(n));
+        }
+    }
+
+    #[test]
+    fn encode_largest_numbers() {
+        let code: Code128Buf<Pattern> = u64::MAX.encode().unwrap();
+        assert_eq!(symbols(&code).len(), 10 + 3);
+        assert_eq!(code.decode_as::<u64>(), Ok(u64::MAX));
+
+        // 39 digits, so led by a zero
+        let code: Code128Buf<Pattern> = u128::MAX.encode().unwrap();
+        assert_eq!(symbols(&code).len(), 20 + 3);
+        assert_eq!(symbols(&code)[1], 3);
+        assert_eq!(code.decode_as::<u128>(), Ok(u128::MAX));
+    }
+
+    quickcheck! {
+        fn numbers_round_trip(n: u64) -> bool {
+            let code: Code128Buf<Pattern> = n.encode().unwrap();
+
+            code.decode_as::<u64>() == Ok(n)
+        }
+
+        fn numbers_are_no_longer_than_their_text(n: u64) -> bool {
+            let code: Code128Buf<Pattern> = n.encode().unwrap();
+
+            Some(symbols(&code).len()) <= encoded_len(&format!("{}", n))
+        }
+
+        fn encoded_len_agrees_with_encode(s: String, copies: u8) -> bool {
+            // Repeated up to eight times, to cross MAX_SYMBOLS now and then
+            let ascii: String = s.chars().map(|c| (c as u32 % 128) as u8 as char).collect();
+            let ascii = ascii.repeat(copies as usize % 8 + 1);
+
+            let code: Result<Code128Buf<Pattern>> = ascii.encode();
+
+            encoded_len(&ascii) == code.ok().map(|code| symbols(&code).len())
+        }
+
+        fn encoded_len_agrees_on_digits(digits: Vec<u8>, copies: u8) -> bool {
+            let ascii: String = digits.iter().map(|d| (b'0' + d % 10) as char).collect();
+            let ascii = ascii.repeat(copies as usize % 8 + 1);
+
+            let code: Result<Code128Buf<Pattern>> = ascii.encode();
+
+            encoded_len(&ascii) == code.ok().map(|code| symbols(&code).len())
+        }
+
+        fn encoded_len_rejects_what_encode_rejects(s: String) -> bool {
+            encoded_len(&s).is_some() == Encode::<Code128Buf<Pattern>>::encode(&s[..]).is_ok()
+        }
+
+        fn ascii_bytes_encode_like_str(bytes: Vec<u8>) -> bool {
+            let ascii: Vec<u8> = bytes.iter().map(|b| b & 0x7f).collect();
+            let text = String::from_utf8(ascii.clone()).unwrap();
+
+            let from_bytes: Result<Code128Buf<Pattern>> = ascii[..].encode();
+            let from_str: Result<Code128Buf<Pattern>> = text.encode();
+
+            from_bytes == from_str
+        }
+    }
+}
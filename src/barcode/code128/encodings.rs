@@ -0,0 +1,795 @@
+//  This Source Code Form is subject to the terms of the Mozilla Public
+//  License, v. 2.0. If a copy of the MPL was not distributed with this
+//  file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Symbol representations usable in a [`Code128`](../struct.Code128.html)
+
+use core::fmt;
+use core::str::FromStr;
+
+use super::Symbology;
+use barcode::format::Error;
+
+#[cfg(feature = "arbitrary")]
+use arbitrary::Arbitrary;
+#[cfg(feature = "serde")]
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// A value that can stand in for a single Code 128 symbol
+///
+/// Every symbol has a numeric value in `0..=106`; implementors only need to
+/// expose that value and construct the special start, switch, and stop
+/// symbols.
+pub trait Encoding: PartialEq + Sized {
+    /// Numeric value of the symbol
+    fn as_u8(&self) -> u8;
+
+    /// Start symbol for the given symbology
+    fn start(s: Symbology) -> Self;
+
+    /// Symbol switching to the given symbology from any other
+    fn switch(s: Symbology) -> Self;
+
+    /// Stop symbol
+    fn stop() -> Self;
+}
+
+impl Encoding for u8 {
+    fn as_u8(&self) -> u8 {
+        *self
+    }
+
+    fn start(s: Symbology) -> u8 {
+        s.start_value()
+    }
+
+    fn switch(s: Symbology) -> u8 {
+        s.code_value()
+    }
+
+    fn stop() -> u8 {
+        106
+    }
+}
+
+/// The 107 symbols of Code 128, named by their value
+///
+/// With the `serde` feature, a pattern serializes as its value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(Arbitrary))]
+pub enum Pattern {
+    C0,
+    C1,
+    C2,
+    C3,
+    C4,
+    C5,
+    C6,
+    C7,
+    C8,
+    C9,
+    C10,
+    C11,
+    C12,
+    C13,
+    C14,
+    C15,
+    C16,
+    C17,
+    C18,
+    C19,
+    C20,
+    C21,
+    C22,
+    C23,
+    C24,
+    C25,
+    C26,
+    C27,
+    C28,
+    C29,
+    C30,
+    C31,
+    C32,
+    C33,
+    C34,
+    C35,
+    C36,
+    C37,
+    C38,
+    C39,
+    C40,
+    C41,
+    C42,
+    C43,
+    C44,
+    C45,
+    C46,
+    C47,
+    C48,
+    C49,
+    C50,
+    C51,
+    C52,
+    C53,
+    C54,
+    C55,
+    C56,
+    C57,
+    C58,
+    C59,
+    C60,
+    C61,
+    C62,
+    C63,
+    C64,
+    C65,
+    C66,
+    C67,
+    C68,
+    C69,
+    C70,
+    C71,
+    C72,
+    C73,
+    C74,
+    C75,
+    C76,
+    C77,
+    C78,
+    C79,
+    C80,
+    C81,
+    C82,
+    C83,
+    C84,
+    C85,
+    C86,
+    C87,
+    C88,
+    C89,
+    C90,
+    C91,
+    C92,
+    C93,
+    C94,
+    C95,
+    C96,
+    C97,
+    C98,
+    C99,
+    C100,
+    C101,
+    C102,
+    C103,
+    C104,
+    C105,
+    C106,
+}
+
+/// Every pattern, indexed by its value
+pub const PATTERNS: [Pattern; 107] = [
+    Pattern::C0, Pattern::C1, Pattern::C2, Pattern::C3, Pattern::C4, Pattern::C5,
+    Pattern::C6, Pattern::C7, Pattern::C8, Pattern::C9, Pattern::C10, Pattern::C11,
+    Pattern::C12, Pattern::C13, Pattern::C14, Pattern::C15, Pattern::C16, Pattern::C17,
+    Pattern::C18, Pattern::C19, Pattern::C20, Pattern::C21, Pattern::C22, Pattern::C23,
+    Pattern::C24, Pattern::C25, Pattern::C26, Pattern::C27, Pattern::C28, Pattern::C29,
+    Pattern::C30, Pattern::C31, Pattern::C32, Pattern::C33, Pattern::C34, Pattern::C35,
+    Pattern::C36, Pattern::C37, Pattern::C38, Pattern::C39, Pattern::C40, Pattern::C41,
+    Pattern::C42, Pattern::C43, Pattern::C44, Pattern::C45, Pattern::C46, Pattern::C47,
+    Pattern::C48, Pattern::C49, Pattern::C50, Pattern::C51, Pattern::C52, Pattern::C53,
+    Pattern::C54, Pattern::C55, Pattern::C56, Pattern::C57, Pattern::C58, Pattern::C59,
+    Pattern::C60, Pattern::C61, Pattern::C62, Pattern::C63, Pattern::C64, Pattern::C65,
+    Pattern::C66, Pattern::C67, Pattern::C68, Pattern::C69, Pattern::C70, Pattern::C71,
+    Pattern::C72, Pattern::C73, Pattern::C74, Pattern::C75, Pattern::C76, Pattern::C77,
+    Pattern::C78, Pattern::C79, Pattern::C80, Pattern::C81, Pattern::C82, Pattern::C83,
+    Pattern::C84, Pattern::C85, Pattern::C86, Pattern::C87, Pattern::C88, Pattern::C89,
+    Pattern::C90, Pattern::C91, Pattern::C92, Pattern::C93, Pattern::C94, Pattern::C95,
+    Pattern::C96, Pattern::C97, Pattern::C98, Pattern::C99, Pattern::C100, Pattern::C101,
+    Pattern::C102, Pattern::C103, Pattern::C104, Pattern::C105, Pattern::C106,
+];
+
+/// Widths in modules of the bars and spaces of each symbol, indexed by value
+///
+/// Each symbol alternates bar, space, bar, space, bar, space across 11
+/// modules.  The stop symbol is followed by a final 2 module bar, which is
+/// not included here.
+pub const BAR_WIDTHS: [[u8; 6]; 107] = [
+    [2, 1, 2, 2, 2, 2], [2, 2, 2, 1, 2, 2], [2, 2, 2, 2, 2, 1], [1, 2, 1, 2, 2, 3],
+    [1, 2, 1, 3, 2, 2], [1, 3, 1, 2, 2, 2], [1, 2, 2, 2, 1, 3], [1, 2, 2, 3, 1, 2],
+    [1, 3, 2, 2, 1, 2], [2, 2, 1, 2, 1, 3], [2, 2, 1, 3, 1, 2], [2, 3, 1, 2, 1, 2],
+    [1, 1, 2, 2, 3, 2], [1, 2, 2, 1, 3, 2], [1, 2, 2, 2, 3, 1], [1, 1, 3, 2, 2, 2],
+    [1, 2, 3, 1, 2, 2], [1, 2, 3, 2, 2, 1], [2, 2, 3, 2, 1, 1], [2, 2, 1, 1, 3, 2],
+    [2, 2, 1, 2, 3, 1], [2, 1, 3, 2, 1, 2], [2, 2, 3, 1, 1, 2], [3, 1, 2, 1, 3, 1],
+    [3, 1, 1, 2, 2, 2], [3, 2, 1, 1, 2, 2], [3, 2, 1, 2, 2, 1], [3, 1, 2, 2, 1, 2],
+    [3, 2, 2, 1, 1, 2], [3, 2, 2, 2, 1, 1], [2, 1, 2, 1, 2, 3], [2, 1, 2, 3, 2, 1],
+    [2, 3, 2, 1, 2, 1], [1, 1, 1, 3, 2, 3], [1, 3, 1, 1, 2, 3], [1, 3, 1, 3, 2, 1],
+    [1, 1, 2, 3, 1, 3], [1, 3, 2, 1, 1, 3], [1, 3, 2, 3, 1, 1], [2, 1, 1, 3, 1, 3],
+    [2, 3, 1, 1, 1, 3], [2, 3, 1, 3, 1, 1], [1, 1, 2, 1, 3, 3], [1, 1, 2, 3, 3, 1],
+    [1, 3, 2, 1, 3, 1], [1, 1, 3, 1, 2, 3], [1, 1, 3, 3, 2, 1], [1, 3, 3, 1, 2, 1],
+    [3, 1, 3, 1, 2, 1], [2, 1, 1, 3, 3, 1], [2, 3, 1, 1, 3, 1], [2, 1, 3, 1, 1, 3],
+    [2, 1, 3, 3, 1, 1], [2, 1, 3, 1, 3, 1], [3, 1, 1, 1, 2, 3], [3, 1, 1, 3, 2, 1],
+    [3, 3, 1, 1, 2, 1], [3, 1, 2, 1, 1, 3], [3, 1, 2, 3, 1, 1], [3, 3, 2, 1, 1, 1],
+    [3, 1, 4, 1, 1, 1], [2, 2, 1, 4, 1, 1], [4, 3, 1, 1, 1, 1], [1, 1, 1, 2, 2, 4],
+    [1, 1, 1, 4, 2, 2], [1, 2, 1, 1, 2, 4], [1, 2, 1, 4, 2, 1], [1, 4, 1, 1, 2, 2],
+    [1, 4, 1, 2, 2, 1], [1, 1, 2, 2, 1, 4], [1, 1, 2, 4, 1, 2], [1, 2, 2, 1, 1, 4],
+    [1, 2, 2, 4, 1, 1], [1, 4, 2, 1, 1, 2], [1, 4, 2, 2, 1, 1], [2, 4, 1, 2, 1, 1],
+    [2, 2, 1, 1, 1, 4], [4, 1, 3, 1, 1, 1], [2, 4, 1, 1, 1, 2], [1, 3, 4, 1, 1, 1],
+    [1, 1, 1, 2, 4, 2], [1, 2, 1, 1, 4, 2], [1, 2, 1, 2, 4, 1], [1, 1, 4, 2, 1, 2],
+    [1, 2, 4, 1, 1, 2], [1, 2, 4, 2, 1, 1], [4, 1, 1, 2, 1, 2], [4, 2, 1, 1, 1, 2],
+    [4, 2, 1, 2, 1, 1], [2, 1, 2, 1, 4, 1], [2, 1, 4, 1, 2, 1], [4, 1, 2, 1, 2, 1],
+    [1, 1, 1, 1, 4, 3], [1, 1, 1, 3, 4, 1], [1, 3, 1, 1, 4, 1], [1, 1, 4, 1, 1, 3],
+    [1, 1, 4, 3, 1, 1], [4, 1, 1, 1, 1, 3], [4, 1, 1, 3, 1, 1], [1, 1, 3, 1, 4, 1],
+    [1, 1, 4, 1, 3, 1], [3, 1, 1, 1, 4, 1], [4, 1, 1, 1, 3, 1], [2, 1, 1, 4, 1, 2],
+    [2, 1, 1, 2, 1, 4], [2, 1, 1, 2, 3, 2], [2, 3, 3, 1, 1, 1],
+];
+
+/// Representation of each symbol in symbology A, indexed by value
+///
+/// Control characters use their ASCII names, such as `"NUL"` and `"SOH"`.
+pub static REPR_TABLE_A: [&str; 107] = [
+    " ", "!", "\"", "#", "$", "%", "&", "'", "(", ")", "*", "+", ",", "-", ".", "/",
+    "0", "1", "2", "3", "4", "5", "6", "7", "8", "9", ":", ";", "<", "=", ">", "?", "@",
+    "A", "B", "C", "D", "E", "F", "G", "H", "I", "J", "K", "L", "M", "N", "O", "P", "Q",
+    "R", "S", "T", "U", "V", "W", "X", "Y", "Z", "[", "\\", "]", "^", "_", "NUL", "SOH",
+    "STX", "ETX", "EOT", "ENQ", "ACK", "BEL", "BS", "HT", "LF", "VT", "FF", "CR", "SO",
+    "SI", "DLE", "DC1", "DC2", "DC3", "DC4", "NAK", "SYN", "ETB", "CAN", "EM", "SUB",
+    "ESC", "FS", "GS", "RS", "US", "FNC3", "FNC2", "Shift B", "Code C", "Code B",
+    "FNC4", "FNC1", "Start A", "Start B", "Start C", "Stop",
+];
+
+/// Representation of each symbol in symbology B, indexed by value
+///
+/// `DEL` is named like the control characters of symbology A.
+pub static REPR_TABLE_B: [&str; 107] = [
+    " ", "!", "\"", "#", "$", "%", "&", "'", "(", ")", "*", "+", ",", "-", ".", "/",
+    "0", "1", "2", "3", "4", "5", "6", "7", "8", "9", ":", ";", "<", "=", ">", "?", "@",
+    "A", "B", "C", "D", "E", "F", "G", "H", "I", "J", "K", "L", "M", "N", "O", "P", "Q",
+    "R", "S", "T", "U", "V", "W", "X", "Y", "Z", "[", "\\", "]", "^", "_", "`", "a",
+    "b", "c", "d", "e", "f", "g", "h", "i", "j", "k", "l", "m", "n", "o", "p", "q", "r",
+    "s", "t", "u", "v", "w", "x", "y", "z", "{", "|", "}", "~", "DEL", "FNC3", "FNC2",
+    "Shift A", "Code C", "FNC4", "Code A", "FNC1", "Start A", "Start B", "Start C",
+    "Stop",
+];
+
+/// Representation of each symbol in symbology C, indexed by value
+pub static REPR_TABLE_C: [&str; 107] = [
+    "00", "01", "02", "03", "04", "05", "06", "07", "08", "09", "10", "11", "12", "13",
+    "14", "15", "16", "17", "18", "19", "20", "21", "22", "23", "24", "25", "26", "27",
+    "28", "29", "30", "31", "32", "33", "34", "35", "36", "37", "38", "39", "40", "41",
+    "42", "43", "44", "45", "46", "47", "48", "49", "50", "51", "52", "53", "54", "55",
+    "56", "57", "58", "59", "60", "61", "62", "63", "64", "65", "66", "67", "68", "69",
+    "70", "71", "72", "73", "74", "75", "76", "77", "78", "79", "80", "81", "82", "83",
+    "84", "85", "86", "87", "88", "89", "90", "91", "92", "93", "94", "95", "96", "97",
+    "98", "99", "Code B", "Code A", "FNC1", "Start A", "Start B", "Start C", "Stop",
+];
+
+// Every value converts to its own pattern and back, checked when the crate
+// builds, and `try_from_u8` rejects every value above 106
+const _: () = {
+    let mut u = 0;
+    while u < Pattern::count() {
+        assert!(Pattern::from_u8(u as u8).as_u8() as usize == u);
+        assert!(Pattern::checked_from_u8(u as u8).is_some());
+        u += 1;
+    }
+
+    let mut u = Pattern::count();
+    while u <= u8::MAX as usize {
+        assert!(Pattern::checked_from_u8(u as u8).is_none());
+        u += 1;
+    }
+};
+
+impl Pattern {
+    /// Number of patterns, from `C0` through the stop pattern `C106`
+    pub const fn count() -> usize {
+        PATTERNS.len()
+    }
+
+    /// Highest pattern that can appear among the data symbols
+    ///
+    /// `C102` is FNC1 in every symbology; the start and stop patterns
+    /// follow it.
+    pub const fn max_data() -> Pattern {
+        Pattern::C102
+    }
+
+    /// Every pattern in order, `C0` through `C106`
+    ///
+    /// ```
+    /// use feather_code::barcode::code128::Pattern;
+    ///
+    /// assert_eq!(Pattern::all().count(), Pattern::count());
+    /// ```
+    pub fn all() -> impl Iterator<Item = Pattern> {
+        PATTERNS.iter().cloned()
+    }
+
+    /// The patterns that can appear among the data symbols, `C0` through
+    /// `max_data()`
+    pub fn all_data() -> impl Iterator<Item = Pattern> {
+        PATTERNS[..=Pattern::max_data() as usize].iter().cloned()
+    }
+
+    /// The function, shift, and switch patterns, `C96` through `C102`
+    ///
+    /// What each means depends on the symbology; in Code C, `C96` to `C98`
+    /// are digit pairs instead.
+    pub fn all_control() -> impl Iterator<Item = Pattern> {
+        PATTERNS[96..=Pattern::max_data() as usize].iter().cloned()
+    }
+
+    /// Whether the pattern is one of the start patterns, `C103` to `C105`
+    ///
+    /// ```
+    /// use feather_code::barcode::code128::Pattern;
+    ///
+    /// assert!(Pattern::C104.is_start());
+    /// assert!(!Pattern::max_data().is_start());
+    /// ```
+    pub const fn is_start(self) -> bool {
+        matches!(self, Pattern::C103 | Pattern::C104 | Pattern::C105)
+    }
+
+    /// Convert a symbol value to its pattern, saturating at `C106`
+    ///
+    /// Usable in const contexts:
+    ///
+    /// ```
+    /// use feather_code::barcode::code128::Pattern;
+    ///
+    /// const STOP: Pattern = Pattern::from_u8(106);
+    /// assert_eq!(STOP, Pattern::C106);
+    /// ```
+    pub const fn from_u8(u: u8) -> Pattern {
+        if (u as usize) < Pattern::count() {
+            PATTERNS[u as usize]
+        } else {
+            Pattern::C106
+        }
+    }
+
+    /// Numeric value of the pattern, usable in const contexts
+    pub const fn as_u8(self) -> u8 {
+        self as u8
+    }
+
+    /// Start pattern for the given symbology, usable in const contexts
+    pub const fn start(s: Symbology) -> Pattern {
+        Pattern::from_u8(s.start_value())
+    }
+
+    /// Pattern switching to the given symbology, usable in const contexts
+    pub const fn switch(s: Symbology) -> Pattern {
+        Pattern::from_u8(s.code_value())
+    }
+
+    /// Stop pattern, usable in const contexts
+    pub const fn stop() -> Pattern {
+        Pattern::C106
+    }
+
+    /// Human-readable name of the symbol in the given symbology
+    ///
+    /// Characters are represented by themselves, control characters and
+    /// special symbols by their names:
+    ///
+    /// ```
+    /// use feather_code::barcode::code128::{Pattern, Symbology};
+    ///
+    /// assert_eq!(Pattern::C33.repr_static(Symbology::B), "A");
+    /// assert_eq!(Pattern::C64.repr_static(Symbology::A), "NUL");
+    /// assert_eq!(Pattern::C7.repr_static(Symbology::C), "07");
+    /// ```
+    pub fn repr_static(self, sym: Symbology) -> &'static str {
+        let table = match sym {
+            Symbology::A => &REPR_TABLE_A,
+            Symbology::B => &REPR_TABLE_B,
+            Symbology::C => &REPR_TABLE_C,
+        };
+
+        table[self as usize]
+    }
+
+    /// Widths of the bars and spaces making up the symbol
+    ///
+    /// ```
+    /// use feather_code::barcode::code128::Pattern;
+    ///
+    /// assert_eq!(Pattern::C104.bar_widths(), [2, 1, 1, 2, 1, 4]);
+    /// ```
+    pub const fn bar_widths(self) -> [u8; 6] {
+        BAR_WIDTHS[self as usize]
+    }
+
+    /// Find the pattern drawn with the given bar and space widths
+    pub fn from_bar_widths(widths: [u8; 6]) -> Option<Pattern> {
+        BAR_WIDTHS.iter()
+            .position(|&w| w == widths)
+            .map(|u| PATTERNS[u])
+    }
+
+    /// Convert a symbol value to its pattern, failing with `BadFormat` for
+    /// values above 106
+    ///
+    /// Prefer this over `From<u8>` for untrusted input such as scanner
+    /// output, where an out-of-range value indicates corruption.
+    pub fn try_from_u8(u: u8) -> Result<Pattern, Error> {
+        Pattern::checked_from_u8(u).ok_or_else(|| {
+            Error::BadFormat(format!("pattern value {} out of range", u))
+        })
+    }
+
+    /// The pattern behind `try_from_u8`, with `None` for values above 106
+    const fn checked_from_u8(u: u8) -> Option<Pattern> {
+        if (u as usize) < Pattern::count() {
+            Some(PATTERNS[u as usize])
+        } else {
+            None
+        }
+    }
+
+    /// The pattern with the given name, exactly as `Display` writes it
+    ///
+    /// Unlike parsing, bare values and leading zeros are not accepted.
+    ///
+    /// ```
+    /// use feather_code::barcode::code128::Pattern;
+    ///
+    /// assert_eq!(Pattern::from_name("C42"), Some(Pattern::C42));
+    /// assert_eq!(Pattern::from_name("42"), None);
+    /// assert_eq!(Pattern::from_name("C042"), None);
+    /// assert_eq!(Pattern::from_name("C107"), None);
+    /// ```
+    pub fn from_name(s: &str) -> Option<Pattern> {
+        let digits = s.strip_prefix('C')?;
+
+        if digits.is_empty() || digits.len() > 1 && digits.starts_with('0') ||
+           !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+
+        digits.parse().ok().and_then(|u| Pattern::try_from_u8(u).ok())
+    }
+
+    /// The symbol for a character, with the symbology it belongs to
+    ///
+    /// Printable characters and DEL use symbology B, which also covers
+    /// lowercase; control characters use symbology A.  Fails for characters
+    /// outside ASCII, which need FNC4.
+    ///
+    /// ```
+    /// use feather_code::barcode::code128::{Pattern, Symbology};
+    ///
+    /// assert_eq!(Pattern::try_from_char('a'), Ok((Pattern::C65, Symbology::B)));
+    /// assert_eq!(Pattern::try_from_char('\t'), Ok((Pattern::C73, Symbology::A)));
+    /// assert!(Pattern::try_from_char('\u{e9}').is_err());
+    /// ```
+    // Not `TryFrom<char> for (Pattern, Symbology)`: a tuple is never local, so
+    // the orphan rule forbids that impl
+    pub fn try_from_char(c: char) -> Result<(Pattern, Symbology), CharEncodeError> {
+        match c as u32 {
+            u @ 0..=31 => Ok((Pattern::from_u8(u as u8 + 64), Symbology::A)),
+            u @ 32..=127 => Ok((Pattern::from_u8(u as u8 - 32), Symbology::B)),
+            _ => Err(CharEncodeError(c)),
+        }
+    }
+}
+
+/// A character with no Code 128 symbol outside the FNC4 extended mode
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CharEncodeError(pub char);
+
+impl fmt::Display for CharEncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "cannot encode non-ASCII character {:?}", self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl ::std::error::Error for CharEncodeError {}
+
+impl From<u8> for Pattern {
+    /// Convert a symbol value to its pattern, saturating at `C106`
+    ///
+    /// Use `Pattern::try_from_u8` to reject values above 106 instead.
+    fn from(u: u8) -> Pattern {
+        Pattern::from_u8(u)
+    }
+}
+
+impl fmt::Display for Pattern {
+    /// Write the pattern's name, such as `C42`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "C{}", self.as_u8())
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = Error;
+
+    /// Parse a name such as `C42`, or a bare value such as `42`
+    ///
+    /// ```
+    /// use feather_code::barcode::code128::Pattern;
+    ///
+    /// assert_eq!("C42".parse(), Ok(Pattern::C42));
+    /// assert_eq!("106".parse(), Ok(Pattern::C106));
+    /// assert!("C107".parse::<Pattern>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Pattern, Error> {
+        let digits = s.strip_prefix('C').unwrap_or(s);
+
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(Error::BadFormat(format!("invalid pattern name {:?}", s)));
+        }
+
+        match digits.parse() {
+            Ok(u) => Pattern::try_from_u8(u),
+            Err(_) => Err(Error::BadFormat(format!("pattern value {} out of range", digits))),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for Pattern {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(self.as_u8())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Pattern {
+    /// Deserialize from a value, rejecting values above 106
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Pattern, D::Error> {
+        let u = u8::deserialize(deserializer)?;
+
+        Pattern::try_from_u8(u).map_err(|_| {
+            de::Error::custom(format!("pattern value {} out of range", u))
+        })
+    }
+}
+
+impl Encoding for Pattern {
+    fn as_u8(&self) -> u8 {
+        Pattern::as_u8(*self)
+    }
+
+    fn start(s: Symbology) -> Pattern {
+        Pattern::start(s)
+    }
+
+    fn switch(s: Symbology) -> Pattern {
+        Pattern::switch(s)
+    }
+
+    fn stop() -> Pattern {
+        Pattern::stop()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::String;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn from_name_reverses_debug_and_display() {
+        assert!(Pattern::all().all(|p| Pattern::from_name(&format!("{:?}", p)) == Some(p)));
+        assert!(Pattern::all().all(|p| Pattern::from_name(&format!("{}", p)) == Some(p)));
+
+        for name in &["", "C", "c42", "C-1", "C+1", "C 1", "C1 ", "C00", "C256", "Stop"] {
+            assert_eq!(Pattern::from_name(name), None, "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn pattern_from_u8_to_u8() {
+        for u in 0..107u8 {
+            assert_eq!(Pattern::from(u).as_u8(), u);
+        }
+    }
+
+    #[test]
+    fn patterns_are_indexed_by_value() {
+        for (u, p) in PATTERNS.iter().enumerate() {
+            assert_eq!(p.as_u8() as usize, u);
+        }
+    }
+
+    #[test]
+    fn from_u8_in_const_context() {
+        const START_C: Pattern = Pattern::from_u8(105);
+        const SATURATED: Pattern = Pattern::from_u8(200);
+
+        assert_eq!(START_C, Pattern::C105);
+        assert_eq!(SATURATED, Pattern::C106);
+    }
+
+    #[test]
+    fn pattern_from_u8_saturates() {
+        assert_eq!(Pattern::from(107), Pattern::C106);
+        assert_eq!(Pattern::from(255), Pattern::C106);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn pattern_serde_round_trip() {
+        for &p in PATTERNS.iter() {
+            let json = ::serde_json::to_string(&p).unwrap();
+            assert_eq!(json, format!("{}", p.as_u8()));
+            assert_eq!(::serde_json::from_str::<Pattern>(&json).unwrap(), p);
+
+            let bytes = ::bincode::serialize(&p).unwrap();
+            assert_eq!(bytes, vec![p.as_u8()]);
+            assert_eq!(::bincode::deserialize::<Pattern>(&bytes).unwrap(), p);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn pattern_deserialize_rejects_out_of_range() {
+        assert!(::serde_json::from_str::<Pattern>("107").is_err());
+        assert!(::serde_json::from_str::<Pattern>("255").is_err());
+        assert!(::bincode::deserialize::<Pattern>(&[200]).is_err());
+    }
+
+    #[test]
+    fn pattern_space_boundaries() {
+        assert_eq!(Pattern::count(), 107);
+        assert_eq!(Pattern::max_data(), Pattern::C102);
+
+        for &p in PATTERNS.iter() {
+            assert_eq!(p.is_start(), Symbology::from_start_value(p.as_u8()).is_some());
+            assert_eq!(p.as_u8() <= Pattern::max_data().as_u8(),
+                       !p.is_start() && p != Pattern::C106);
+        }
+    }
+
+    #[test]
+    fn all_in_order() {
+        let all: Vec<Pattern> = Pattern::all().collect();
+
+        assert_eq!(all, PATTERNS);
+        assert!(all.iter().enumerate().all(|(i, p)| p.as_u8() as usize == i));
+    }
+
+    #[test]
+    fn all_data_excludes_start_and_stop() {
+        let data: Vec<Pattern> = Pattern::all_data().collect();
+
+        assert_eq!(data.len(), 103);
+        assert_eq!(data.last(), Some(&Pattern::max_data()));
+        assert!(data.iter().all(|p| !p.is_start() && *p != Pattern::stop()));
+
+        for p in Pattern::all_data() {
+            for &s in &Symbology::ALL {
+                assert!(!p.repr_static(s).is_empty());
+            }
+        }
+    }
+
+    #[test]
+    fn all_control_patterns() {
+        let control: Vec<u8> = Pattern::all_control().map(Pattern::as_u8).collect();
+
+        assert_eq!(control, [96, 97, 98, 99, 100, 101, 102]);
+        assert!(Pattern::all_control().all(|p| p.repr_static(Symbology::B).len() > 1));
+    }
+
+    #[test]
+    fn display_names() {
+        assert_eq!(format!("{}", Pattern::C0), "C0");
+        assert_eq!(format!("{}", Pattern::C42), "C42");
+        assert_eq!(format!("{}", Pattern::C106), "C106");
+    }
+
+    #[test]
+    fn from_str_round_trips_every_pattern() {
+        for &p in PATTERNS.iter() {
+            assert_eq!(format!("{}", p).parse(), Ok(p));
+            assert_eq!(format!("{}", p.as_u8()).parse(), Ok(p));
+        }
+    }
+
+    #[test]
+    fn from_str_rejects_bad_names() {
+        for name in &["", "C", "c42", "C+4", "+42", " 42", "C42 ", "Cx"] {
+            assert_eq!(name.parse::<Pattern>(),
+                       Err(Error::BadFormat(format!("invalid pattern name {:?}", name))));
+        }
+
+        assert_eq!("C107".parse::<Pattern>(),
+                   Err(Error::BadFormat(String::from("pattern value 107 out of range"))));
+        assert_eq!("999".parse::<Pattern>(),
+                   Err(Error::BadFormat(String::from("pattern value 999 out of range"))));
+    }
+
+    #[test]
+    fn try_from_u8_rejects_out_of_range() {
+        assert_eq!(Pattern::try_from_u8(106), Ok(Pattern::C106));
+        assert_eq!(Pattern::try_from_u8(107),
+                   Err(Error::BadFormat(String::from("pattern value 107 out of range"))));
+    }
+
+    #[test]
+    fn try_from_char_covers_ascii() {
+        for u in 0..128u8 {
+            let (p, sym) = Pattern::try_from_char(char::from(u)).unwrap();
+
+            assert_eq!(sym, if u < 32 { Symbology::A } else { Symbology::B });
+            if (32..127).contains(&u) {
+                assert_eq!(p.repr_static(sym).as_bytes(), [u]);
+            }
+        }
+
+        assert_eq!(Pattern::try_from_char('A'), Ok((Pattern::C33, Symbology::B)));
+        assert_eq!(Pattern::try_from_char('\0'), Ok((Pattern::C64, Symbology::A)));
+        assert_eq!(Pattern::try_from_char('\x7f'), Ok((Pattern::C95, Symbology::B)));
+    }
+
+    #[test]
+    fn try_from_char_rejects_non_ascii() {
+        for &c in &['\u{80}', '\u{e9}', '\u{ff}', '\u{100}', '\u{263a}', '\u{1f426}'] {
+            assert_eq!(Pattern::try_from_char(c), Err(CharEncodeError(c)));
+        }
+
+        assert_eq!(format!("{}", CharEncodeError('\u{e9}')),
+                   "cannot encode non-ASCII character '\u{e9}'");
+    }
+
+    quickcheck! {
+        fn try_from_u8_succeeds_exactly_for_valid_values(u: u8) -> bool {
+            Pattern::try_from_u8(u).is_ok() == (u <= 106)
+        }
+
+        fn try_from_u8_agrees_with_from(u: u8) -> bool {
+            Pattern::try_from_u8(u).map_or(true, |p| p == Pattern::from(u))
+        }
+    }
+
+    #[test]
+    fn repr_tables_are_complete() {
+        for table in &[REPR_TABLE_A, REPR_TABLE_B, REPR_TABLE_C] {
+            assert!(table.iter().all(|r| !r.is_empty()));
+        }
+
+        assert_eq!(REPR_TABLE_A[..64], REPR_TABLE_B[..64]);
+    }
+
+    #[test]
+    fn repr_static_uses_tables() {
+        assert_eq!(Pattern::C0.repr_static(Symbology::A), " ");
+        assert_eq!(Pattern::C65.repr_static(Symbology::A), "SOH");
+        assert_eq!(Pattern::C65.repr_static(Symbology::B), "a");
+        assert_eq!(Pattern::C95.repr_static(Symbology::B), "DEL");
+        assert_eq!(Pattern::C99.repr_static(Symbology::C), "99");
+        assert_eq!(Pattern::C101.repr_static(Symbology::A), "FNC4");
+        assert_eq!(Pattern::C106.repr_static(Symbology::C), "Stop");
+    }
+
+    #[test]
+    fn bar_widths_are_eleven_modules() {
+        for &p in PATTERNS.iter() {
+            let widths = p.bar_widths();
+            let bars: u8 = widths.iter().step_by(2).sum();
+
+            assert_eq!(widths.iter().sum::<u8>(), 11);
+            assert_eq!(bars % 2, 0);
+            assert_eq!(Pattern::from_bar_widths(widths), Some(p));
+        }
+
+        assert_eq!(Pattern::from_bar_widths([1, 1, 1, 1, 1, 6]), None);
+    }
+
+    #[test]
+    fn special_symbols_agree() {
+        for &s in &[Symbology::A, Symbology::B, Symbology::C] {
+            assert_eq!(Pattern::start(s).as_u8(), u8::start(s));
+            assert_eq!(Pattern::switch(s).as_u8(), u8::switch(s));
+        }
+        assert_eq!(Pattern::stop().as_u8(), u8::stop());
+    }
+}
@@ -0,0 +1,3595 @@
+//  This Source Code Form is subject to the terms of the Mozilla Public
+//  License, v. 2.0. If a copy of the MPL was not distributed with this
+//  file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Code 128 barcodes
+//!
+//! A Code 128 barcode is a sequence of symbols with values in `0..=106`: a
+//! start symbol selecting the initial symbology, the data symbols, a check
+//! symbol, and the stop symbol.  Symbols can be any type implementing
+//! [`Encoding`](encodings/trait.Encoding.html), such as plain `u8` values or
+//! [`Pattern`](encodings/enum.Pattern.html)s.
+//!
+//! ```
+//! use feather_code::barcode::code128::Code128;
+//! use feather_code::barcode::format::{Decode, Format};
+//!
+//! let symbols: [u8; 10] = [103, 48, 42, 42, 17, 18, 19, 35, 54, 106];
+//! let code = Code128::new(&symbols);
+//!
+//! assert!(code.checksum());
+//! assert_eq!(code.decode(), Ok(String::from("PJJ123C")));
+//! ```
+
+mod assembler;
+mod builder;
+#[macro_use]
+mod const_encoder;
+mod decoder;
+mod encoder;
+pub mod encodings;
+mod scan;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
+pub use self::assembler::{AssemblyState, MessageAssembler};
+pub use self::builder::Code128Builder;
+pub use self::const_encoder::Code128Arr;
+#[doc(hidden)]
+pub use self::const_encoder::{const_plan, ConstPlan};
+pub use self::decoder::DecodeChars;
+pub use self::encodings::{CharEncodeError, Encoding, Pattern};
+pub use self::scan::{scan_modules, Scan, ScanOptions, MAX_REPAIR_ATTEMPTS};
+
+pub(crate) use self::decoder::Item;
+use self::decoder::Walker;
+
+use alloc::borrow::Cow;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::slice;
+use core::fmt::Debug;
+use core::iter::FromIterator;
+
+#[cfg(feature = "arbitrary")]
+use arbitrary::Arbitrary;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use barcode::format::{Decode, Encode, Error, Format, Result, FUNCTION_CODE};
+use barcode::render::text::render_bars;
+use barcode::render::RenderOptions;
+
+/// The three character sets of Code 128, valued by their start symbol
+///
+/// Ordered by value, so A < B < C.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(Arbitrary))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Symbology {
+    /// Uppercase ASCII, digits, punctuation, and control characters
+    A = 103,
+    /// Upper and lowercase ASCII, digits, and punctuation
+    B = 104,
+    /// Pairs of digits
+    C = 105,
+}
+
+impl Symbology {
+    /// Every symbology, in order
+    pub const ALL: [Symbology; 3] = [Symbology::A, Symbology::B, Symbology::C];
+
+    /// Value of the start symbol selecting the symbology
+    pub const fn start_value(self) -> u8 {
+        self as u8
+    }
+
+    /// Value of the symbol switching to the symbology from another
+    ///
+    /// ```
+    /// use feather_code::barcode::code128::Symbology;
+    ///
+    /// assert_eq!(Symbology::A.code_value(), 101);
+    /// assert_eq!(Symbology::C.code_value(), 99);
+    /// ```
+    pub const fn code_value(self) -> u8 {
+        match self {
+            Symbology::A => 101,
+            Symbology::B => 100,
+            Symbology::C => 99,
+        }
+    }
+
+    /// Position of the symbology in `ALL`, for indexing tables by it
+    pub const fn index(self) -> usize {
+        (self as u8 - Symbology::A as u8) as usize
+    }
+
+    /// The symbology a start symbol value selects, if it is one
+    pub fn from_start_value(u: u8) -> Option<Symbology> {
+        Symbology::ALL.iter().cloned().find(|s| s.start_value() == u)
+    }
+}
+
+impl Default for Symbology {
+    /// Symbology B, holding the most of ASCII
+    ///
+    /// The encoder starts in it when no start is shorter, such as for
+    /// function codes alone.
+    ///
+    /// ```
+    /// use feather_code::barcode::code128::{Code128Builder, Encoding, Symbology};
+    ///
+    /// let code = Code128Builder::new().fnc3().build().unwrap();
+    ///
+    /// assert_eq!(code.symbols()[0].as_u8(), Symbology::default().start_value());
+    /// ```
+    fn default() -> Symbology {
+        Symbology::B
+    }
+}
+
+/// Most symbols in a barcode the encoders will write, counting the start,
+/// check, and stop symbols
+///
+/// The standard sets no limit, but printers and scanners do, and few
+/// accept more.  `encode`, `Code128Builder`, the appending methods of
+/// `Code128Buf`, `Code128Arr`, and `code128!` all hold to it.
+pub const MAX_SYMBOLS: usize = 232;
+
+/// Settings for `encode_with` and `Code128Builder::options`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct EncodeOptions {
+    /// Whether to write barcodes of more than `MAX_SYMBOLS` symbols
+    pub allow_oversize: bool,
+}
+
+/// A problem found while validating a Code 128 barcode
+///
+/// Indices count every symbol from the start symbol.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Diagnostic {
+    /// The first symbol is not a start symbol
+    MissingStart,
+    /// The last symbol is not the stop symbol
+    MissingStop,
+    /// The start symbol is followed directly by the stop symbol, with no
+    /// check symbol between them
+    MissingCheck,
+    /// The check symbol doesn't match the data
+    BadCheckDigit {
+        /// Check symbol computed from the data
+        expected: u8,
+        /// Check symbol in the barcode
+        found: u8,
+    },
+    /// A symbol has a value above 106
+    SymbolOutOfRange {
+        /// Position of the symbol
+        index: usize,
+        /// Value of the symbol
+        value: u8,
+    },
+    /// A stop symbol appears before the last symbol
+    InteriorStop {
+        /// Position of the stop symbol
+        index: usize,
+    },
+    /// The last data symbol is a shift, which has no symbol to apply to
+    UnexpectedShiftBeforeStop,
+    /// There are no data symbols between the start and check symbols, as
+    /// for empty text; decoding doesn't mind
+    EmptyPayload,
+    /// There are more than `MAX_SYMBOLS` symbols, which readers may refuse;
+    /// decoding doesn't mind
+    TooManySymbols {
+        /// Number of symbols
+        count: usize,
+    },
+}
+
+/// How a Code 128 barcode was put together, as found while decoding it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DecodeInfo {
+    /// Symbology selected by the start symbol
+    pub start_symbology: Symbology,
+    /// Number of switches to another symbology
+    pub switches: usize,
+    /// Number of single-character shifts
+    pub shifts: usize,
+    /// Whether an FNC1 appeared, as in GS1 barcodes
+    pub fnc1_present: bool,
+    /// Whether an FNC4 appeared, for extended ASCII
+    pub fnc4_present: bool,
+    /// Number of symbols, including the start, check, and stop symbols
+    pub symbol_count: usize,
+}
+
+/// The choices the planner made for `mixed_encode_verbose`'s input
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MixedEncodeStats {
+    /// Symbology selected by the start symbol
+    pub start: Symbology,
+    /// Number of switches to another symbology
+    pub switches: usize,
+    /// Number of single-character shifts
+    pub shifts: usize,
+    /// Number of symbols, including the start, check, and stop symbols
+    pub symbols: usize,
+}
+
+/// How `Code128::decode_with` treats FNC1 to FNC4, which carry no
+/// characters
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum FunctionCodePolicy {
+    /// Skip them, as `decode` does
+    #[default]
+    Ignore,
+    /// Fail with `BadSymbolAt` at the first one
+    Error,
+    /// Write the given character in their place
+    Emit(char),
+}
+
+/// Settings for `Code128::decode_with`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct DecodeOptions<'a> {
+    /// What to do with function codes
+    pub function_codes: FunctionCodePolicy,
+    /// Whether to decode symbols read back to front, as found by
+    /// `Code128::is_reversed`
+    pub allow_reversed: bool,
+    /// Characters to write for each ASCII byte in place of the byte itself,
+    /// such as visible glyphs for control characters
+    ///
+    /// Only characters read in symbologies A and B consult the map; Code C
+    /// digits and characters emitted for function codes are written as
+    /// they are.
+    pub charmap: Option<&'a [char; 128]>,
+}
+
+/// Everything `Code128::verify` found wrong with a barcode, or didn't
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VerifyReport<E> {
+    /// Whether there are start, check, and stop symbols and no more than
+    /// `MAX_SYMBOLS` symbols
+    pub length_ok: bool,
+    /// Whether there is a start symbol first and a stop symbol last and
+    /// nowhere else, every symbol is in range, and the data doesn't end in
+    /// a shift
+    pub structure_ok: bool,
+    /// Whether the check symbol matches the data; false when the barcode
+    /// is too malformed to tell
+    pub checksum_ok: bool,
+    /// The check symbol the data calls for, when there is a start and stop
+    /// symbol to find the data by
+    pub expected_check: Option<E>,
+}
+
+impl<E> VerifyReport<E> {
+    /// Whether every check passed
+    pub fn is_ok(&self) -> bool {
+        self.length_ok && self.structure_ok && self.checksum_ok
+    }
+}
+
+/// Text recovered by `Code128::decode_lenient`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LenientDecode {
+    /// The decoded text
+    pub text: String,
+    /// Whether the stop symbol was missing and had to be assumed
+    pub stop_synthesized: bool,
+}
+
+/// Text decoded by `Code128::decode_full`, with where its function codes
+/// and symbology changes fell
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodedBarcode {
+    /// The decoded text
+    pub text: String,
+    /// Symbology selected by the start symbol
+    pub start_symbology: Symbology,
+    /// Position in the text of each FNC1 to FNC4, in order; an FNC1 before
+    /// any text, at 0, marks a GS1 barcode
+    pub fnc_positions: Vec<usize>,
+    /// Position in the text of the first character read after each change
+    /// of symbology, by switch or shift, and the symbology it was read in
+    pub transitions: Vec<(usize, Symbology)>,
+}
+
+impl fmt::Display for DecodedBarcode {
+    /// The decoded text
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+impl AsRef<str> for DecodedBarcode {
+    fn as_ref(&self) -> &str {
+        &self.text
+    }
+}
+
+impl From<DecodedBarcode> for String {
+    fn from(decoded: DecodedBarcode) -> String {
+        decoded.text
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Diagnostic::MissingStart => write!(f, "missing start symbol"),
+            Diagnostic::MissingStop => write!(f, "missing stop symbol"),
+            Diagnostic::MissingCheck => write!(f, "missing check symbol"),
+            Diagnostic::BadCheckDigit { expected, found } => {
+                write!(f, "check symbol is {}, expected {}", found, expected)
+            }
+            Diagnostic::SymbolOutOfRange { index, value } => {
+                write!(f, "symbol {} has value {}, above 106", index, value)
+            }
+            Diagnostic::InteriorStop { index } => {
+                write!(f, "stop symbol at {} before the end", index)
+            }
+            Diagnostic::UnexpectedShiftBeforeStop => write!(f, "shift symbol before check symbol"),
+            Diagnostic::EmptyPayload => write!(f, "no data symbols"),
+            Diagnostic::TooManySymbols { count } => {
+                write!(f, "{} symbols, more than the {} allowed", count, MAX_SYMBOLS)
+            }
+        }
+    }
+}
+
+/// The symbology selected by the first of `symbols`, if it is a start
+/// symbol
+///
+/// Nothing past the first symbol is looked at, so this is cheaper than
+/// `Code128::data` for sorting a batch of scans by how they start.
+///
+/// ```
+/// use feather_code::barcode::code128::{detect_start, Symbology};
+///
+/// assert_eq!(detect_start(&[105u8, 12, 34]), Some(Symbology::C));
+/// assert_eq!(detect_start(&[106u8, 12, 34]), None);
+/// assert_eq!(detect_start::<u8>(&[]), None);
+/// ```
+pub fn detect_start<E: Encoding>(symbols: &[E]) -> Option<Symbology> {
+    symbols.first().and_then(|e| Symbology::from_start_value(e.as_u8()))
+}
+
+/// Value of the check symbol for data following the given start symbology
+///
+/// The start value plus each data value weighted by its position, modulo
+/// 103.  `data` excludes the start, check, and stop symbols and may be
+/// borrowed from any barcode, such as `Code128Parts::payload`.
+///
+/// ```
+/// use feather_code::barcode::code128::{weighted_checksum, Symbology};
+///
+/// assert_eq!(weighted_checksum(Symbology::A, &[48u8, 42, 42, 17, 18, 19, 35]), 54);
+/// ```
+pub fn weighted_checksum<E: Encoding>(start: Symbology, data: &[E]) -> u8 {
+    checksum_in_order(start, data)
+}
+
+/// `weighted_checksum` of the data symbols in the order `data` yields them
+fn checksum_in_order<'e, E, I>(start: Symbology, data: I) -> u8
+    where E: 'e + Encoding, I: IntoIterator<Item = &'e E>
+{
+    data.into_iter()
+        .enumerate()
+        .fold(add_to_checksum(0, 1, start.start_value()),
+              |sum, (i, e)| add_to_checksum(sum, i + 1, e.as_u8()))
+}
+
+/// Add the symbol `value` with weight `weight` to a partial check sum
+///
+/// The start symbol has weight 1 and each data symbol its position from 1.
+/// Every check symbol computation goes through here, at compile time too.
+// In `u32`, without wrapping: the weight is reduced modulo 103 before it
+// multiplies, so the largest total is 255 + 102 * 255 = 26265, far below
+// `u32::MAX`, however long the data is
+const fn add_to_checksum(sum: u8, weight: usize, value: u8) -> u8 {
+    ((sum as u32 + (weight % 103) as u32 * value as u32) % 103) as u8
+}
+
+/// Compute the check symbol for data symbols following a start symbology
+///
+/// `symbols` excludes the start, check, and stop symbols.  The result is
+/// exact for any length, though scanners rarely accept more than a few
+/// dozen symbols.
+///
+/// ```
+/// use feather_code::barcode::code128::{compute_checksum, Pattern, Symbology};
+///
+/// let data: Vec<Pattern> = vec![48, 42, 42, 17, 18, 19, 35].into_iter()
+///     .map(Pattern::from)
+///     .collect();
+///
+/// assert_eq!(compute_checksum(Symbology::A, &data), Pattern::C54);
+/// ```
+pub fn compute_checksum<E: Encoding + From<u8>>(start: Symbology, symbols: &[E]) -> E {
+    E::from(weighted_checksum(start, symbols))
+}
+
+/// `compute_checksum` for patterns, usable in const contexts
+///
+/// Lets a fixed barcode be built at compile time:
+///
+/// ```
+/// use feather_code::barcode::code128::{const_checksum, Pattern, Symbology};
+///
+/// const DATA: [Pattern; 3] = [Pattern::C44, Pattern::C47, Pattern::C52];
+/// const CODE: [Pattern; 6] = [
+///     Pattern::start(Symbology::A),
+///     DATA[0], DATA[1], DATA[2],
+///     const_checksum(Symbology::A, &DATA),
+///     Pattern::stop(),
+/// ];
+///
+/// assert_eq!(CODE[4], Pattern::C88);
+/// ```
+pub const fn const_checksum(start: Symbology, symbols: &[Pattern]) -> Pattern {
+    let mut sum = add_to_checksum(0, 1, start.start_value());
+    let mut i = 0;
+
+    while i < symbols.len() {
+        sum = add_to_checksum(sum, i + 1, symbols[i].as_u8());
+        i += 1;
+    }
+
+    Pattern::from_u8(sum)
+}
+
+/// Number of symbols `encode` produces for `input`, without encoding it
+///
+/// Counts the start, check, and stop symbols along with the data.  Returns
+/// `None` exactly when `encode` fails: when `input` is not ASCII, or needs
+/// more than `MAX_SYMBOLS` symbols.
+///
+/// ```
+/// use feather_code::barcode::code128::encoded_len;
+///
+/// assert_eq!(encoded_len("PJJ123C"), Some(10));
+/// assert_eq!(encoded_len("123456"), Some(6));
+/// assert_eq!(encoded_len("naïve"), None);
+/// assert_eq!(encoded_len(""), Some(3));
+/// assert_eq!(encoded_len(&"A".repeat(300)), None);
+/// ```
+pub fn encoded_len(input: &str) -> Option<usize> {
+    if !input.is_ascii() {
+        return None;
+    }
+
+    Some(encoder::planned_len(input.as_bytes()) + 2).filter(|&count| count <= MAX_SYMBOLS)
+}
+
+/// Encode arbitrary 8-bit data, such as Latin-1 text
+///
+/// Bytes in `128..=255` are written with FNC4, following the AIM extended
+/// ASCII convention, and come back through `Decode<Vec<u8>>`, or as Latin-1
+/// characters through `Decode<String>`.  FNC4 has no meaning in GS1, so
+/// the result is not a valid GS1 barcode, and readers without extended
+/// ASCII support will drop the high bits.
+///
+/// ```
+/// use feather_code::barcode::code128::encode_bytes;
+/// use feather_code::barcode::format::Decode;
+///
+/// let code = encode_bytes(b"M\xfcnchen").unwrap();
+/// let bytes: Vec<u8> = code.decode().unwrap();
+///
+/// assert_eq!(bytes, b"M\xfcnchen");
+/// ```
+pub fn encode_bytes(bytes: &[u8]) -> Result<Code128Buf<Pattern>> {
+    bytes.encode()
+}
+
+/// Encode bytes as `encode_bytes` does, with `options`
+///
+/// `encode` and `encode_bytes` use the default options, failing with
+/// `InvalidLength` for barcodes of more than `MAX_SYMBOLS` symbols;
+/// `allow_oversize` lifts that limit.  ASCII text encodes the same as its
+/// bytes.
+///
+/// ```
+/// use feather_code::barcode::code128::{encode_with, EncodeOptions, MAX_SYMBOLS};
+///
+/// let long = [b'x'; 240];
+/// let options = EncodeOptions { allow_oversize: true };
+///
+/// assert!(encode_with(&long, &EncodeOptions::default()).is_err());
+/// assert_eq!(encode_with(&long, &options).unwrap().symbols().len(), 243);
+/// assert!(243 > MAX_SYMBOLS);
+/// ```
+pub fn encode_with(bytes: &[u8], options: &EncodeOptions) -> Result<Code128Buf<Pattern>> {
+    encoder::finish(encoder::plan(bytes), options)
+}
+
+/// Encode ASCII text, shifting for lone characters from the other of
+/// symbologies A and B
+///
+/// A shift costs one symbol against two for switching away and back, so
+/// text that is mostly in one symbology with the odd character from the
+/// other comes out shorter.  Every choice between switching and shifting
+/// is weighed to find the fewest symbols; this is the same encoding as
+/// `str::encode`.  Fails with `BadFormat` for non-ASCII text.
+///
+/// ```
+/// use feather_code::barcode::code128::mixed_encode;
+///
+/// // Start A, then "\x02", "L", "O", "T", Shift, "x", "\x03"
+/// let code = mixed_encode("\x02LOTx\x03").unwrap();
+/// assert_eq!(code.symbols().len(), 10);
+/// ```
+pub fn mixed_encode(input: &str) -> Result<Code128Buf<Pattern>> {
+    mixed_encode_verbose(input).map(|(code, _)| code)
+}
+
+/// Encode like `mixed_encode`, also reporting the switches and shifts the
+/// planner chose
+///
+/// ```
+/// use feather_code::barcode::code128::{mixed_encode_verbose, Symbology};
+///
+/// let (_, stats) = mixed_encode_verbose("\x02LOTx\x03").unwrap();
+///
+/// assert_eq!(stats.start, Symbology::A);
+/// assert_eq!((stats.switches, stats.shifts), (0, 1));
+/// ```
+pub fn mixed_encode_verbose(input: &str) -> Result<(Code128Buf<Pattern>, MixedEncodeStats)> {
+    if let Some(c) = input.chars().find(|c| !c.is_ascii()) {
+        return Err(Error::BadFormat(format!("cannot encode non-ASCII character {:?}", c)));
+    }
+
+    let (planned, stats) = encoder::plan_with_stats(input.as_bytes());
+    let code = encoder::finish(planned, &EncodeOptions::default())?;
+
+    Ok((code, stats))
+}
+
+/// Encode ASCII text padded out to exactly `symbols` symbols, counting the
+/// start, check, and stop symbols
+///
+/// The padding is FNC4 symbols after the text, preceded by a switch out of
+/// Code C if the text ends there.  FNC4 only changes the character after
+/// it, and none follows, so the padding decodes to nothing, in plain and
+/// extended decoding alike.  Labels printed from one template then all
+/// have the same width.
+///
+/// Fails with `BadFormat` if the text can't be encoded, or `InvalidLength`
+/// if it needs more than `symbols` symbols.
+///
+/// ```
+/// use feather_code::barcode::code128::encode_padded;
+/// use feather_code::barcode::format::Decode;
+///
+/// let code = encode_padded("PJJ123C", 14).unwrap();
+///
+/// assert_eq!(code.symbols().len(), 14);
+/// assert_eq!(code.decode(), Ok(String::from("PJJ123C")));
+/// ```
+pub fn encode_padded(input: &str, symbols: usize) -> Result<Code128Buf<Pattern>> {
+    let code: Code128Buf<Pattern> = input.encode()?;
+    let len = code.symbols().len();
+
+    if len > symbols {
+        return Err(Error::InvalidLength(format!("{:?} needs {} symbols, more than {}",
+                                                input, len, symbols)));
+    }
+
+    let pad = symbols - len;
+    let padded = |fnc4s| {
+        (0..fnc4s).fold(Code128Builder::new().text(input), |b, _| b.fnc4()).build()
+    };
+
+    if pad == 0 {
+        return Ok(code);
+    }
+
+    let fnc4s = padded(pad)?;
+    if fnc4s.symbols().len() == symbols {
+        return Ok(fnc4s);
+    }
+
+    // The text ends in Code C, and switching out takes a symbol of its own
+    if pad > 1 {
+        return padded(pad - 1);
+    }
+
+    // Room for the switch alone
+    let start = code.start_symbology().unwrap_or(Symbology::C);
+    let mut data = code.0;
+    data.truncate(len - 2);
+    data.push(Pattern::C100);
+
+    let check = weighted_checksum(start, &data[1..]);
+    data.push(Pattern::from(check));
+    data.push(Pattern::stop());
+
+    Ok(Code128Buf(data))
+}
+
+/// Width in modules of `input` encoded and rendered with `options`
+///
+/// Every symbol is 11 modules wide except the 13 module stop symbol, and a
+/// quiet zone is added on each side.  Multiply by the X-dimension to get
+/// the printed width.  Returns `None` when `input` cannot be encoded.
+///
+/// ```
+/// use feather_code::barcode::code128::rendered_width_modules;
+/// use feather_code::barcode::render::RenderOptions;
+///
+/// let options = RenderOptions { quiet_zone: 10 };
+/// assert_eq!(rendered_width_modules("PJJ123C", &options), Some(132));
+/// ```
+pub fn rendered_width_modules(input: &str, options: &RenderOptions) -> Option<usize> {
+    encoded_len(input).map(|n| n * 11 + 2 + 2 * options.quiet_zone)
+}
+
+/// Decode several barcodes delivered back to back in one buffer
+///
+/// The buffer is split after each stop symbol, and every span is decoded
+/// as a complete barcode with its own start, check, and stop symbols.
+/// Symbols left over after the last stop symbol form a final span, which
+/// fails to decode.
+///
+/// ```
+/// use feather_code::barcode::code128::decode_all;
+///
+/// let symbols: [u8; 16] = [103, 48, 42, 42, 17, 18, 19, 35, 54, 106,
+///                          105, 12, 34, 56, 44, 106];
+///
+/// assert_eq!(decode_all(&symbols),
+///            vec![Ok(String::from("PJJ123C")), Ok(String::from("123456"))]);
+/// ```
+pub fn decode_all<E: Encoding + Debug>(symbols: &[E]) -> Vec<Result<String>> {
+    symbols.split_inclusive(|e| *e == E::stop())
+        .map(|span| Code128::new(span).decode())
+        .collect()
+}
+
+/// Decode many barcodes, in order
+///
+/// Text is decoded into one scratch buffer that is copied out at its final
+/// length, saving the reallocations of growing each result.
+///
+/// ```
+/// use feather_code::barcode::code128::{decode_batch, Code128};
+///
+/// let pjj: [u8; 10] = [103, 48, 42, 42, 17, 18, 19, 35, 54, 106];
+/// let digits: [u8; 6] = [105, 12, 34, 56, 44, 106];
+/// let results = decode_batch(&[Code128::new(&pjj), Code128::new(&digits)]);
+///
+/// assert_eq!(results, vec![Ok(String::from("PJJ123C")), Ok(String::from("123456"))]);
+/// ```
+pub fn decode_batch<'a, E: 'a + Encoding + Debug>(codes: &[Code128<'a, E>]) -> Vec<Result<String>> {
+    let mut scratch = String::new();
+
+    codes.iter().map(|code| decode_through(code, &mut scratch)).collect()
+}
+
+/// Decode many barcodes in parallel, in order
+///
+/// Like `decode_batch`, with one scratch buffer per rayon job.  Requires
+/// the `rayon` feature.
+#[cfg(feature = "rayon")]
+pub fn par_decode_batch<'a, E>(codes: &[Code128<'a, E>]) -> Vec<Result<String>>
+    where E: 'a + Encoding + Debug + Sync
+{
+    use rayon::prelude::*;
+
+    codes.par_iter().map_init(String::new, |scratch, code| decode_through(code, scratch)).collect()
+}
+
+/// Decode `code` through `scratch`, returning a copy of the text
+fn decode_through<E: Encoding + Debug>(code: &Code128<E>, scratch: &mut String) -> Result<String> {
+    scratch.clear();
+    code.decode_into(scratch)?;
+
+    Ok(scratch.as_str().into())
+}
+
+/// The parts of a Code 128 barcode, as split by `Code128::data`
+#[derive(Debug, PartialEq)]
+pub struct Code128Parts<'a, E: 'a + Encoding> {
+    /// Symbology selected by the start symbol
+    pub symbology: Symbology,
+    /// Data symbols between the start and check symbols
+    pub payload: &'a [E],
+    /// The check symbol, just before the stop symbol
+    pub check: &'a E,
+}
+
+/// A borrowed Code 128 barcode
+///
+/// Cloning copies the reference, not the symbols; use `to_owned` for an
+/// owned copy.
+#[derive(PartialEq)]
+pub struct Code128<'a, E: 'a + Encoding>(&'a [E]);
+
+impl<'a, E: 'a + Encoding> Clone for Code128<'a, E> {
+    fn clone(&self) -> Code128<'a, E> {
+        Code128(self.0)
+    }
+}
+
+impl<'a, E: 'a + Encoding> Code128<'a, E> {
+    /// Wrap a sequence of symbols, including start, check, and stop symbols
+    pub fn new(symbols: &'a [E]) -> Code128<'a, E> {
+        Code128(symbols)
+    }
+
+    /// Every module of the printed barcode, `true` for dark
+    ///
+    /// Runs from the leading quiet zone to the trailing one, including the
+    /// 2 module bar that ends the stop symbol.  Symbols above 106 are drawn
+    /// as the stop symbol.
+    ///
+    /// ```
+    /// use feather_code::barcode::code128::Code128;
+    /// use feather_code::barcode::render::QUIET_ZONE;
+    ///
+    /// let symbols: [u8; 10] = [103, 48, 42, 42, 17, 18, 19, 35, 54, 106];
+    /// let modules = Code128::new(&symbols).modules();
+    ///
+    /// assert_eq!(modules.len(), 11 * 9 + 13 + 2 * QUIET_ZONE);
+    /// ```
+    pub fn modules(&self) -> Vec<bool> {
+        let patterns: Vec<Pattern> = self.0.iter().map(|e| Pattern::from_u8(e.as_u8())).collect();
+
+        render_bars(&patterns).into_iter().map(|m| m == 1).collect()
+    }
+
+    /// Width of the bars in modules, for laying out space before rendering
+    ///
+    /// Every symbol is 11 modules wide, and the stop symbol ends with a
+    /// further 2 module bar.  Quiet zones are not included; multiply by the
+    /// X-dimension to get the printed width.
+    ///
+    /// ```
+    /// use feather_code::barcode::code128::Code128;
+    ///
+    /// let symbols: [u8; 10] = [103, 48, 42, 42, 17, 18, 19, 35, 54, 106];
+    ///
+    /// assert_eq!(Code128::new(&symbols).module_width(), 11 * 9 + 13);
+    /// ```
+    pub fn module_width(&self) -> usize {
+        11 * self.0.len() + 2
+    }
+
+    /// Every symbol value, including the start, check, and stop symbols
+    ///
+    /// ```
+    /// use feather_code::barcode::code128::{Code128, Pattern};
+    ///
+    /// let symbols = [Pattern::C103, Pattern::C48, Pattern::C42, Pattern::C106];
+    ///
+    /// assert_eq!(Code128::new(&symbols).to_bytes(), [103, 48, 42, 106]);
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.iter().map(Encoding::as_u8).collect()
+    }
+
+    /// A table of the symbols for debugging, one row per symbol
+    ///
+    /// Each row has the index, pattern name, value, and what the symbol
+    /// means in the symbology active there, starting in `start` and
+    /// following start symbols, switches, and shifts.  Values above 106
+    /// are shown as `?`.
+    ///
+    /// ```
+    /// use feather_code::barcode::code128::{Code128, Symbology};
+    ///
+    /// let symbols: [u8; 4] = [105, 12, 100, 33];
+    /// let dump = Code128::new(&symbols).dump(Symbology::B);
+    /// let lines: Vec<&str> = dump.lines().collect();
+    ///
+    /// assert_eq!(lines, ["index  pattern  value  meaning",
+    ///                    "    0  C105       105  Start C",
+    ///                    "    1  C12         12  12",
+    ///                    "    2  C100       100  Code B",
+    ///                    "    3  C33         33  A"]);
+    /// ```
+    pub fn dump(&self, start: Symbology) -> String {
+        let mut out = String::from("index  pattern  value  meaning\n");
+        let mut current = start;
+        let mut shift = None;
+
+        for (i, e) in self.0.iter().enumerate() {
+            let u = e.as_u8();
+            let active = shift.take().unwrap_or(current);
+
+            match Pattern::try_from_u8(u) {
+                Ok(p) => {
+                    let name = p.to_string();
+                    out += &format!("{:>5}  {:<7}  {:>5}  {}\n", i, name, u, p.repr_static(active));
+                }
+                Err(_) => out += &format!("{:>5}  {:<7}  {:>5}  ?\n", i, "?", u),
+            }
+
+            match (active, u) {
+                (_, 103..=105) => current = Symbology::from_start_value(u).unwrap_or(current),
+                (Symbology::A, 98) => shift = Some(Symbology::B),
+                (Symbology::B, 98) => shift = Some(Symbology::A),
+                (Symbology::A, 99) | (Symbology::B, 99) => current = Symbology::C,
+                (Symbology::A, 100) | (Symbology::C, 100) => current = Symbology::B,
+                (Symbology::B, 101) | (Symbology::C, 101) => current = Symbology::A,
+                _ => {}
+            }
+        }
+
+        out
+    }
+
+    /// The symbology selected by the start symbol
+    ///
+    /// Looks only at the first symbol: `None` if there are fewer than three
+    /// symbols or the first is not a start symbol.
+    ///
+    /// ```
+    /// use feather_code::barcode::code128::{Code128, Symbology};
+    ///
+    /// let symbols: [u8; 10] = [103, 48, 42, 42, 17, 18, 19, 35, 54, 106];
+    ///
+    /// assert_eq!(Code128::new(&symbols).start_symbology(), Some(Symbology::A));
+    /// assert_eq!(Code128::new(&symbols[1..]).start_symbology(), None);
+    /// ```
+    pub fn start_symbology(&self) -> Option<Symbology> {
+        if self.0.len() < 3 {
+            return None;
+        }
+
+        Symbology::from_start_value(self.0[0].as_u8())
+    }
+
+    /// Whether the last symbol is the stop symbol
+    pub fn end_with_stop(&self) -> bool {
+        self.0.last() == Some(&E::stop())
+    }
+
+    /// Whether the symbols look read back to front: the stop symbol first
+    /// and a start symbol last
+    ///
+    /// Only the ends are looked at; `DecodeOptions::allow_reversed` decodes
+    /// such barcodes.
+    ///
+    /// ```
+    /// use feather_code::barcode::code128::Code128;
+    ///
+    /// let symbols: [u8; 10] = [106, 54, 35, 19, 18, 17, 42, 42, 48, 103];
+    ///
+    /// assert!(Code128::new(&symbols).is_reversed());
+    /// assert!(!Code128::new(&symbols[..9]).is_reversed());
+    /// ```
+    pub fn is_reversed(&self) -> bool {
+        let n = self.0.len();
+
+        n >= 3 && self.0[0] == E::stop() &&
+        Symbology::from_start_value(self.0[n - 1].as_u8()).is_some()
+    }
+
+    /// Split the symbols into start symbology, payload, and check symbol
+    ///
+    /// Only the layout is checked: fails with `BadFormat` unless there are
+    /// at least three symbols, beginning with a start symbol and ending
+    /// with the stop symbol.  The check symbol and payload are not checked.
+    ///
+    /// ```
+    /// use feather_code::barcode::code128::{Code128, Symbology};
+    ///
+    /// let symbols: [u8; 10] = [103, 48, 42, 42, 17, 18, 19, 35, 54, 106];
+    /// let parts = Code128::new(&symbols).data().unwrap();
+    ///
+    /// assert_eq!(parts.symbology, Symbology::A);
+    /// assert_eq!(parts.payload, [48, 42, 42, 17, 18, 19, 35]);
+    /// assert_eq!(*parts.check, 54);
+    /// ```
+    ///
+    /// ```
+    /// use feather_code::barcode::code128::Code128;
+    /// use feather_code::barcode::format::Error;
+    ///
+    /// let symbols: [u8; 4] = [103, 48, 54, 105];
+    ///
+    /// assert_eq!(Code128::new(&symbols).data(),
+    ///            Err(Error::BadFormat(String::from("missing stop symbol"))));
+    /// ```
+    pub fn data(&self) -> Result<Code128Parts<'a, E>> {
+        let n = self.0.len();
+
+        if n < 3 {
+            return Err(Error::BadFormat(format!("expected at least 3 symbols, found {}", n)));
+        }
+
+        let symbology = detect_start(self.0).ok_or_else(|| {
+            Error::BadFormat(String::from("missing start symbol"))
+        })?;
+
+        if self.0[n - 1] != E::stop() {
+            return Err(Error::BadFormat(String::from("missing stop symbol")));
+        }
+
+        Ok(Code128Parts { symbology, payload: &self.0[1..n - 2], check: &self.0[n - 2] })
+    }
+
+    /// Split out the start symbology and data, failing unless the barcode
+    /// validates
+    ///
+    /// With `reversed`, the barcode is read as one that `is_reversed`: the
+    /// data is returned as stored, last symbol first, but is checked and
+    /// reported on in reading order.
+    fn check(&self, reversed: bool) -> Result<(Symbology, &'a [E])> {
+        let symbols = self.0;
+        let n = symbols.len();
+        let at = |i: usize| if reversed { &symbols[n - 1 - i] } else { &symbols[i] };
+
+        // Keep reporting every layout problem the same way
+        let start = if n >= 3 && *at(n - 1) == E::stop() {
+            Symbology::from_start_value(at(0).as_u8())
+        } else {
+            None
+        };
+        let start = start.ok_or_else(|| {
+            Error::BadFormat(String::from("missing start or stop symbol"))
+        })?;
+
+        if let Err(diagnostics) = self.validate_readable(reversed) {
+            let msg = match diagnostics[0] {
+                Diagnostic::BadCheckDigit { .. } => String::from("check symbol does not match"),
+                // The shift is the last data symbol, before the check and stop
+                Diagnostic::UnexpectedShiftBeforeStop => format!("dangling shift at {}", n - 3),
+                d => d.to_string(),
+            };
+
+            return Err(Error::BadFormat(msg));
+        }
+
+        let data = if reversed { &symbols[2..n - 1] } else { &symbols[1..n - 2] };
+        Ok((start, data))
+    }
+
+    /// `validate`, leaving out `EmptyPayload` and `TooManySymbols`, which
+    /// don't stop the barcode from being read
+    fn validate_readable(&self, reversed: bool) -> ::core::result::Result<(), Vec<Diagnostic>> {
+        self.validate_oriented(reversed).or_else(|mut diagnostics| {
+            diagnostics.retain(|d| {
+                !matches!(*d, Diagnostic::EmptyPayload | Diagnostic::TooManySymbols { .. })
+            });
+
+            if diagnostics.is_empty() {
+                Ok(())
+            } else {
+                Err(diagnostics)
+            }
+        })
+    }
+
+    /// Check the barcode, collecting every problem found
+    ///
+    /// The stop symbol must appear exactly once, as the last symbol, with
+    /// the check symbol immediately before it.  Barcodes with no data
+    /// symbols or longer than `MAX_SYMBOLS` are reported too, though they
+    /// still decode.
+    ///
+    /// ```
+    /// use feather_code::barcode::code128::{Code128, Diagnostic};
+    ///
+    /// let symbols: [u8; 5] = [103, 48, 200, 55, 12];
+    ///
+    /// assert_eq!(Code128::new(&symbols).validate(),
+    ///            Err(vec![Diagnostic::MissingStop,
+    ///                     Diagnostic::SymbolOutOfRange { index: 2, value: 200 }]));
+    /// ```
+    pub fn validate(&self) -> ::core::result::Result<(), Vec<Diagnostic>> {
+        self.validate_oriented(false)
+    }
+
+    /// `validate`, reading the symbols last first if `reversed`, with
+    /// indices counted in reading order
+    fn validate_oriented(&self, reversed: bool) -> ::core::result::Result<(), Vec<Diagnostic>> {
+        let mut diagnostics = Vec::new();
+        let symbols = self.0;
+        let n = symbols.len();
+        let at = |i: usize| if reversed { &symbols[n - 1 - i] } else { &symbols[i] };
+
+        let start = if n > 0 { Symbology::from_start_value(at(0).as_u8()) } else { None };
+
+        if start.is_none() {
+            diagnostics.push(Diagnostic::MissingStart);
+        }
+
+        let stopped = n >= 2 && *at(n - 1) == E::stop();
+        if !stopped {
+            diagnostics.push(Diagnostic::MissingStop);
+        }
+
+        if n > MAX_SYMBOLS {
+            diagnostics.push(Diagnostic::TooManySymbols { count: n });
+        }
+
+        for (index, e) in (0..n).map(at).enumerate() {
+            let value = e.as_u8();
+            if value as usize >= Pattern::count() {
+                diagnostics.push(Diagnostic::SymbolOutOfRange { index, value });
+            } else if *e == E::stop() && index + 1 < n {
+                diagnostics.push(Diagnostic::InteriorStop { index });
+            }
+        }
+
+        if let (Some(start), true) = (start, stopped) {
+            if n < 3 {
+                diagnostics.push(Diagnostic::MissingCheck);
+            } else if n == 3 {
+                diagnostics.push(Diagnostic::EmptyPayload);
+            }
+
+            if n >= 3 {
+                let data = (1..n - 2).map(at);
+                let expected = checksum_in_order(start, data.clone());
+                let found = at(n - 2).as_u8();
+
+                if expected != found {
+                    diagnostics.push(Diagnostic::BadCheckDigit { expected, found });
+                }
+
+                if ends_with_shift(start, data) {
+                    diagnostics.push(Diagnostic::UnexpectedShiftBeforeStop);
+                }
+            }
+        }
+
+        if diagnostics.is_empty() {
+            Ok(())
+        } else {
+            Err(diagnostics)
+        }
+    }
+}
+
+/// Every ASCII character in order, for borrowing one as a `&str`
+static ASCII: &str = {
+    const BYTES: [u8; 128] = {
+        let mut bytes = [0; 128];
+        let mut i = 0;
+        while i < 128 {
+            bytes[i] = i as u8;
+            i += 1;
+        }
+        bytes
+    };
+
+    match ::core::str::from_utf8(&BYTES) {
+        Ok(ascii) => ascii,
+        Err(_) => panic!("ASCII is UTF-8"),
+    }
+};
+
+/// Whether the last data symbol is a shift in the symbology active there
+fn ends_with_shift<'e, E, I>(start: Symbology, data: I) -> bool
+    where E: 'e + Encoding, I: IntoIterator<Item = &'e E>
+{
+    final_state(start, data).1.is_some()
+}
+
+/// The symbology active after the data symbols, and the one shifted to by
+/// the last of them, if it is a shift
+fn final_state<'e, E, I>(start: Symbology, data: I) -> (Symbology, Option<Symbology>)
+    where E: 'e + Encoding, I: IntoIterator<Item = &'e E>
+{
+    let mut current = start;
+    let mut shift = None;
+
+    for e in data {
+        let active = shift.take().unwrap_or(current);
+
+        match (active, e.as_u8()) {
+            (Symbology::C, 0..=99) | (Symbology::A, 101) | (Symbology::B, 100) => {}
+            (Symbology::A, 98) => shift = Some(Symbology::B),
+            (Symbology::B, 98) => shift = Some(Symbology::A),
+            (_, 99) => current = Symbology::C,
+            (_, 100) => current = Symbology::B,
+            (_, 101) => current = Symbology::A,
+            _ => {}
+        }
+    }
+
+    (current, shift)
+}
+
+impl<'a, E: 'a + Encoding + From<u8>> Code128<'a, E> {
+    /// Check the length, structure, and check symbol all at once, along
+    /// with the check symbol the data calls for
+    ///
+    /// ```
+    /// use feather_code::barcode::code128::Code128;
+    ///
+    /// let symbols: [u8; 10] = [103, 48, 42, 42, 17, 18, 19, 35, 55, 106];
+    /// let report = Code128::new(&symbols).verify();
+    ///
+    /// assert!(report.length_ok && report.structure_ok);
+    /// assert!(!report.checksum_ok);
+    /// assert_eq!(report.expected_check, Some(54));
+    /// ```
+    pub fn verify(&self) -> VerifyReport<E> {
+        let n = self.0.len();
+        let diagnostics = self.validate().err().unwrap_or_default();
+
+        let structure_ok = diagnostics.iter().all(|d| {
+            matches!(*d, Diagnostic::BadCheckDigit { .. } | Diagnostic::EmptyPayload |
+                         Diagnostic::TooManySymbols { .. })
+        });
+        let bad_check = diagnostics.iter().find_map(|d| match *d {
+            Diagnostic::BadCheckDigit { expected, .. } => Some(expected),
+            _ => None,
+        });
+        let parts = self.data().ok();
+
+        VerifyReport {
+            length_ok: (3..=MAX_SYMBOLS).contains(&n),
+            structure_ok,
+            checksum_ok: parts.is_some() && bad_check.is_none(),
+            expected_check: bad_check.or_else(|| parts.map(|p| p.check.as_u8())).map(E::from),
+        }
+    }
+}
+
+impl<'a, E: 'a + Encoding + Clone> Code128<'a, E> {
+    /// Copy the symbols into an owned barcode
+    pub fn to_owned(&self) -> Code128Buf<E> {
+        Code128Buf(self.0.to_vec())
+    }
+}
+
+impl<'a, E: 'a + Encoding + Debug> Code128<'a, E> {
+    /// Decode lazily, one character at a time
+    ///
+    /// The barcode is validated when the first character is requested, so
+    /// a malformed barcode yields a single error.  Iteration ends after the
+    /// first error.
+    ///
+    /// ```
+    /// use feather_code::barcode::code128::Code128;
+    ///
+    /// let symbols: [u8; 10] = [103, 48, 42, 42, 17, 18, 19, 35, 54, 106];
+    /// let prefix: String = Code128::new(&symbols).chars()
+    ///     .take(3)
+    ///     .collect::<Result<_, _>>()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(prefix, "PJJ");
+    /// ```
+    pub fn chars(&self) -> DecodeChars<'a, E> {
+        DecodeChars::new(self.clone())
+    }
+
+    /// Decode into any `fmt::Write`, such as a fixed-size buffer, without
+    /// allocating
+    ///
+    /// Characters are written as they are decoded, so on failure `out`
+    /// holds the text up to the error.  Fails like `decode`, or with
+    /// `DecodeErr` if `out` refuses a character.
+    ///
+    /// ```
+    /// use feather_code::barcode::code128::Code128;
+    ///
+    /// let symbols: [u8; 10] = [103, 48, 42, 42, 17, 18, 19, 35, 54, 106];
+    /// let mut text = String::new();
+    /// Code128::new(&symbols).decode_into(&mut text).unwrap();
+    ///
+    /// assert_eq!(text, "PJJ123C");
+    /// ```
+    pub fn decode_into<W: fmt::Write>(&self, out: &mut W) -> Result<()> {
+        for c in self.chars() {
+            out.write_char(c?).map_err(|_| {
+                Error::DecodeErr(String::from("output refused a decoded character"))
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// Decode without allocating when the text is empty or one character
+    ///
+    /// Those are borrowed from a static table; longer text is owned.  Fails
+    /// like `decode`.
+    ///
+    /// ```
+    /// use std::borrow::Cow;
+    /// use feather_code::barcode::code128::Code128;
+    ///
+    /// // "X" in Code B
+    /// let symbols: [u8; 4] = [104, 56, 57, 106];
+    ///
+    /// assert_eq!(Code128::new(&symbols).decode_cow(), Ok(Cow::Borrowed("X")));
+    /// ```
+    pub fn decode_cow(&self) -> Result<Cow<'static, str>> {
+        let mut chars = self.chars();
+
+        let first = match chars.next() {
+            Some(c) => c?,
+            None => return Ok(Cow::Borrowed("")),
+        };
+
+        let second = match chars.next() {
+            Some(c) => c?,
+            None if first.is_ascii() => {
+                let i = first as usize;
+                return Ok(Cow::Borrowed(&ASCII[i..i + 1]));
+            }
+            None => return Ok(Cow::Owned(first.to_string())),
+        };
+
+        let mut text = String::with_capacity(self.decoded_len_upper_bound());
+        text.push(first);
+        text.push(second);
+        for c in chars {
+            text.push(c?);
+        }
+
+        Ok(Cow::Owned(text))
+    }
+
+    /// Most characters `decode` can produce, for sizing buffers: two for
+    /// every data symbol, as in Code C
+    ///
+    /// ```
+    /// use feather_code::barcode::code128::Code128;
+    ///
+    /// let symbols: [u8; 10] = [103, 48, 42, 42, 17, 18, 19, 35, 54, 106];
+    ///
+    /// assert_eq!(Code128::new(&symbols).decoded_len_upper_bound(), 14);
+    /// ```
+    pub fn decoded_len_upper_bound(&self) -> usize {
+        2 * self.0.len().saturating_sub(3)
+    }
+
+    /// Re-encode the decoded data in as few symbols as possible
+    ///
+    /// For barcodes from encoders that pick symbologies poorly: the result
+    /// decodes to the same bytes, extended characters included, and is
+    /// never longer.  Function codes other than FNC4 are dropped, as
+    /// `decode` drops them.  Fails like `decode`.
+    ///
+    /// ```
+    /// use feather_code::barcode::code128::Code128;
+    /// use feather_code::barcode::format::Decode;
+    ///
+    /// // "123456" one digit at a time in Code B
+    /// let symbols: [u8; 9] = [104, 17, 18, 19, 20, 21, 22, 16, 106];
+    /// let code = Code128::new(&symbols).normalize().unwrap();
+    ///
+    /// assert_eq!(code.to_bytes(), [105, 12, 34, 56, 44, 106]);
+    /// assert_eq!(code.decode(), Ok(String::from("123456")));
+    /// ```
+    pub fn normalize(&self) -> Result<Code128Buf<Pattern>> {
+        let bytes: Vec<u8> = self.decode()?;
+
+        encode_bytes(&bytes)
+    }
+
+    /// Decode the text along with how the barcode encodes it
+    ///
+    /// ```
+    /// use feather_code::barcode::code128::{Code128, Symbology};
+    ///
+    /// let symbols: [u8; 11] = [105, 102, 42, 18, 40, 20, 50, 101, 16, 92, 106];
+    /// let (text, info) = Code128::new(&symbols).decode_with_info().unwrap();
+    ///
+    /// assert_eq!(text, "42184020500");
+    /// assert_eq!(info.start_symbology, Symbology::C);
+    /// assert!(info.fnc1_present);
+    /// ```
+    pub fn decode_with_info(&self) -> Result<(String, DecodeInfo)> {
+        let mut walker = Walker::with_info(self.clone(), true);
+        let text = walker.by_ref()
+            .map(|r| r.map(char::from))
+            .collect::<Result<String>>()?;
+        let info = walker.info.expect("walkers built by with_info gather info");
+
+        Ok((text, info))
+    }
+
+    /// Decode, noting where function codes and changes of symbology fall
+    /// in the text
+    ///
+    /// ```
+    /// use feather_code::barcode::code128::{Code128, Symbology};
+    ///
+    /// let symbols: [u8; 11] = [105, 102, 42, 18, 40, 20, 50, 101, 16, 92, 106];
+    /// let decoded = Code128::new(&symbols).decode_full().unwrap();
+    ///
+    /// assert_eq!(decoded.text, "42184020500");
+    /// assert_eq!(decoded.fnc_positions, [0]);
+    /// assert_eq!(decoded.transitions, [(10, Symbology::A)]);
+    /// ```
+    pub fn decode_full(&self) -> Result<DecodedBarcode> {
+        let mut walker = Walker::new(self.clone(), true);
+        let mut text = String::new();
+        let mut fnc_positions = Vec::new();
+        let mut transitions = Vec::new();
+        let mut last = None;
+
+        while let Some(item) = walker.next_item() {
+            match item? {
+                Item::Byte(b) => {
+                    if last.unwrap_or(walker.start) != walker.set {
+                        transitions.push((text.len(), walker.set));
+                    }
+                    last = Some(walker.set);
+                    text.push(char::from(b));
+                }
+                Item::Function { .. } => fnc_positions.push(text.len()),
+            }
+        }
+
+        Ok(DecodedBarcode {
+            text,
+            start_symbology: walker.start,
+            fnc_positions,
+            transitions,
+        })
+    }
+
+    /// Split the text into runs decoded in one symbology
+    ///
+    /// A character written with a shift is a run of its own, in the
+    /// symbology shifted to.  Function codes are skipped.
+    ///
+    /// ```
+    /// use feather_code::barcode::code128::{Code128, Symbology};
+    ///
+    /// // Code B "PJJ", then Code C "1234"
+    /// let symbols: [u8; 9] = [104, 48, 42, 42, 99, 12, 34, 95, 106];
+    ///
+    /// assert_eq!(Code128::new(&symbols).segments(),
+    ///            Ok(vec![(Symbology::B, String::from("PJJ")),
+    ///                    (Symbology::C, String::from("1234"))]));
+    /// ```
+    pub fn segments(&self) -> Result<Vec<(Symbology, String)>> {
+        let mut walker = Walker::new(self.clone(), true);
+        let mut segments: Vec<(Symbology, String)> = Vec::new();
+
+        while let Some(item) = walker.next_item() {
+            if let Item::Byte(b) = item? {
+                match segments.last_mut() {
+                    Some(&mut (set, ref mut text)) if set == walker.set => text.push(char::from(b)),
+                    _ => segments.push((walker.set, String::from(char::from(b)))),
+                }
+            }
+        }
+
+        Ok(segments)
+    }
+
+    /// Split the data symbols into runs read in one symbology
+    ///
+    /// A switch begins the run it switches to.  A shift ends its run, and
+    /// the character it applies to is a run of one in the symbology
+    /// shifted to.  Fails with `BadFormat` unless the barcode is framed
+    /// and its check symbol matches, as for `decode`.
+    ///
+    /// ```
+    /// use feather_code::barcode::code128::{Code128, Symbology};
+    ///
+    /// let symbols: [u8; 11] = [105, 102, 42, 18, 40, 20, 50, 101, 16, 92, 106];
+    ///
+    /// assert_eq!(Code128::new(&symbols).split_at_switch(),
+    ///            Ok(vec![(Symbology::C, &symbols[1..7]), (Symbology::A, &symbols[7..9])]));
+    /// ```
+    pub fn split_at_switch(&self) -> Result<Vec<(Symbology, &'a [E])>> {
+        let (start, data) = self.check(false)?;
+        let mut runs = Vec::new();
+        let mut current = start;
+        let mut begin = 0;
+        let mut i = 0;
+
+        while i < data.len() {
+            let next = match (current, data[i].as_u8()) {
+                (Symbology::C, 0..=99) | (Symbology::A, 101) | (Symbology::B, 100) => None,
+                (Symbology::A, 98) | (Symbology::B, 98) if i + 1 < data.len() => {
+                    let shifted = if current == Symbology::A { Symbology::B } else { Symbology::A };
+                    runs.push((current, &data[begin..i + 1]));
+                    runs.push((shifted, &data[i + 1..i + 2]));
+                    i += 2;
+                    begin = i;
+                    continue;
+                }
+                (_, 99) => Some(Symbology::C),
+                (_, 100) => Some(Symbology::B),
+                (_, 101) => Some(Symbology::A),
+                _ => None,
+            };
+
+            if let Some(set) = next {
+                if begin < i {
+                    runs.push((current, &data[begin..i]));
+                }
+                current = set;
+                begin = i;
+            }
+            i += 1;
+        }
+
+        if begin < data.len() {
+            runs.push((current, &data[begin..]));
+        }
+
+        Ok(runs)
+    }
+
+    /// Decode the bytes and function codes in order, for formats built on
+    /// top of Code 128 that give function codes a meaning
+    pub(crate) fn items(&self) -> Result<Vec<Item>> {
+        let mut walker = Walker::new(self.clone(), true);
+        let mut items = Vec::new();
+
+        while let Some(item) = walker.next_item() {
+            items.push(item?);
+        }
+
+        Ok(items)
+    }
+
+    /// Decode the text, handling function codes and mapping characters as
+    /// `options` says
+    ///
+    /// With the default options this is the same as `decode`.  Symbols
+    /// read back to front are only decoded if `options.allow_reversed` is
+    /// set; the check symbol is still computed in reading order.
+    ///
+    /// ```
+    /// use feather_code::barcode::code128::{Code128, DecodeOptions, FunctionCodePolicy};
+    ///
+    /// let symbols: [u8; 11] = [105, 102, 42, 18, 40, 20, 50, 101, 16, 92, 106];
+    /// let options = DecodeOptions {
+    ///     function_codes: FunctionCodePolicy::Emit('\u{1d}'),
+    ///     ..DecodeOptions::default()
+    /// };
+    ///
+    /// assert_eq!(Code128::new(&symbols).decode_with(&options),
+    ///            Ok(String::from("\u{1d}42184020500")));
+    /// ```
+    pub fn decode_with(&self, options: &DecodeOptions) -> Result<String> {
+        let mut walker = if options.allow_reversed && self.is_reversed() {
+            Walker::reversed(self.clone(), true)
+        } else {
+            Walker::new(self.clone(), true)
+        };
+        let mut text = String::new();
+
+        while let Some(item) = walker.next_item() {
+            match (item?, options.function_codes) {
+                (Item::Byte(b), _) => match options.charmap {
+                    Some(map) if walker.set != Symbology::C => text.push(map[b as usize & 0x7f]),
+                    _ => text.push(char::from(b)),
+                },
+                (Item::Function { .. }, FunctionCodePolicy::Ignore) => {}
+                (Item::Function { index, value }, FunctionCodePolicy::Error) => {
+                    return Err(Error::BadSymbolAt { index, value, context: FUNCTION_CODE });
+                }
+                (Item::Function { .. }, FunctionCodePolicy::Emit(c)) => text.push(c),
+            }
+        }
+
+        Ok(text)
+    }
+
+    /// Decode as far as possible, returning the text so far and the error
+    /// that stopped decoding, if any
+    ///
+    /// Never panics, which makes it suited to fuzzing.  Errors in the
+    /// framing or check symbol are found before any text is produced, so
+    /// only errors in the data leave partial text.
+    ///
+    /// ```
+    /// use feather_code::barcode::code128::Code128;
+    ///
+    /// // Code C with the digit pairs "12" and "34", then a start symbol
+    /// let symbols: [u8; 6] = [105, 12, 34, 103, 82, 106];
+    /// let (text, error) = Code128::new(&symbols).decode_partial();
+    ///
+    /// assert_eq!(text, "1234");
+    /// assert!(error.is_some());
+    /// ```
+    pub fn decode_partial(&self) -> (String, Option<Error>) {
+        let mut text = String::new();
+
+        for c in self.chars() {
+            match c {
+                Ok(c) => text.push(c),
+                Err(e) => return (text, Some(e)),
+            }
+        }
+
+        (text, None)
+    }
+
+    /// Decode, assuming a stop symbol if the barcode lacks one
+    ///
+    /// For damaged scans that lost the stop symbol: the last symbol is
+    /// taken as the check symbol and the rest as data, and the result says
+    /// whether the stop was assumed.  Every other check still applies, so
+    /// the check symbol must match.
+    ///
+    /// ```
+    /// use feather_code::barcode::code128::Code128;
+    ///
+    /// let symbols: [u8; 9] = [103, 48, 42, 42, 17, 18, 19, 35, 54];
+    /// let recovered = Code128::new(&symbols).decode_lenient().unwrap();
+    ///
+    /// assert_eq!(recovered.text, "PJJ123C");
+    /// assert!(recovered.stop_synthesized);
+    /// ```
+    pub fn decode_lenient(&self) -> Result<LenientDecode> {
+        if self.0.last() == Some(&E::stop()) {
+            return self.decode().map(|text| LenientDecode { text, stop_synthesized: false });
+        }
+
+        let mut symbols: Vec<u8> = self.0.iter().map(Encoding::as_u8).collect();
+        symbols.push(u8::stop());
+
+        Code128::new(&symbols)
+            .decode()
+            .map(|text| LenientDecode { text, stop_synthesized: true })
+    }
+
+    /// Decode, reading the symbols in reverse if they are framed backwards
+    ///
+    /// A scanner reading right to left reports the symbols last first, so
+    /// the stop symbol leads.  When decoding fails with `BadFormat`, the
+    /// symbols are walked last first and decoded again, without copying
+    /// them; if that fails too, the original error is returned.
+    ///
+    /// ```
+    /// use feather_code::barcode::code128::Code128;
+    ///
+    /// let symbols: [u8; 10] = [106, 54, 35, 19, 18, 17, 42, 42, 48, 103];
+    ///
+    /// assert_eq!(Code128::new(&symbols).decode_auto_orient(), Ok(String::from("PJJ123C")));
+    /// ```
+    pub fn decode_auto_orient(&self) -> Result<String> {
+        match self.decode() {
+            Err(Error::BadFormat(msg)) => {
+                Walker::reversed(self.clone(), true)
+                    .map(|r| r.map(char::from))
+                    .collect::<Result<String>>()
+                    .map_err(|_| Error::BadFormat(msg))
+            }
+            result => result,
+        }
+    }
+
+    /// Whether two barcodes decode to the same text
+    ///
+    /// Symbology choices and function codes are ignored, so differently
+    /// encoded barcodes of one payload compare equal.  Returns `false` if
+    /// either barcode fails to decode.
+    ///
+    /// ```
+    /// use feather_code::barcode::code128::Code128;
+    ///
+    /// // "123" in symbology A, and "12" in C followed by "3" in B
+    /// let a: [u8; 6] = [103, 17, 18, 19, 7, 106];
+    /// let c: [u8; 6] = [105, 12, 100, 19, 65, 106];
+    ///
+    /// assert!(Code128::new(&a).semantic_eq(&Code128::new(&c)));
+    /// ```
+    pub fn semantic_eq<E2: Encoding + Debug>(&self, other: &Code128<E2>) -> bool {
+        let mut ours = self.chars();
+        let mut theirs = other.chars();
+
+        loop {
+            match (ours.next(), theirs.next()) {
+                (None, None) => return true,
+                (Some(Ok(a)), Some(Ok(b))) if a == b => {}
+                _ => return false,
+            }
+        }
+    }
+}
+
+impl<'a, E: 'a + Encoding + Debug> fmt::Display for Code128<'a, E> {
+    /// Write the decoded text, or `<invalid: error>` if it doesn't decode
+    ///
+    /// ```
+    /// use feather_code::barcode::code128::Code128;
+    ///
+    /// let symbols: [u8; 10] = [103, 48, 42, 42, 17, 18, 19, 35, 54, 106];
+    ///
+    /// assert_eq!(Code128::new(&symbols).to_string(), "PJJ123C");
+    /// assert_eq!(Code128::new(&symbols[1..]).to_string(),
+    ///            "<invalid: bad format: missing start or stop symbol>");
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match Decode::<String>::decode(self) {
+            Ok(text) => f.write_str(&text),
+            Err(e) => write!(f, "<invalid: {}>", e),
+        }
+    }
+}
+
+impl<'a, E: 'a + Encoding + Debug> Debug for Code128<'a, E> {
+    /// Show the symbols and what they decode to, such as
+    /// `Code128([C103, C48, ...], decoded="PJJ123C")`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match Decode::<String>::decode(self) {
+            Ok(text) => write!(f, "Code128({:?}, decoded={:?})", self.0, text),
+            Err(e) => write!(f, "Code128({:?}, decoded=<invalid: {}>)", self.0, e),
+        }
+    }
+}
+
+impl<'a, E: 'a + Encoding> Format for Code128<'a, E> {
+    /// Whether `validate` finds no problems other than the length
+    fn checksum(&self) -> bool {
+        self.validate_readable(false).is_ok()
+    }
+}
+
+impl<'a, E: 'a + Encoding + Debug> Decode<String> for Code128<'a, E> {
+    fn decode(&self) -> Result<String> {
+        match self.decode_partial() {
+            (text, None) => Ok(text),
+            (_, Some(e)) => Err(e),
+        }
+    }
+}
+
+impl<'a, E: 'a + Encoding + Debug> Decode<Vec<u8>> for Code128<'a, E> {
+    /// Decode to bytes, applying FNC4 to recover values in `128..=255`
+    ///
+    /// Characters come back as their raw byte values, including control
+    /// characters from symbology A.  FNC1, FNC2, and FNC3 are skipped.
+    /// Without FNC4 every byte is in `0..=127`; FNC4 is applied rather than
+    /// skipped, so that the bytes given to `encode_bytes` come back as they
+    /// were.  `Decode<String>` decodes the same bytes as Latin-1 characters.
+    ///
+    /// ```
+    /// use feather_code::barcode::code128::{encode_bytes, Code128};
+    /// use feather_code::barcode::format::Decode;
+    ///
+    /// // Start A, NUL, "A", check, stop
+    /// let symbols: [u8; 5] = [103, 64, 33, 27, 106];
+    /// assert_eq!(Decode::<Vec<u8>>::decode(&Code128::new(&symbols)), Ok(vec![0, 65]));
+    ///
+    /// let code = encode_bytes(b"\xff").unwrap();
+    /// assert_eq!(Decode::<Vec<u8>>::decode(&code), Ok(vec![0xff]));
+    /// ```
+    fn decode(&self) -> Result<Vec<u8>> {
+        Walker::new(self.clone(), true).collect()
+    }
+}
+
+/// An owned Code 128 barcode
+///
+/// With the `serde` feature, serializes as the sequence of its symbols.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Code128Buf<E: Encoding>(Vec<E>);
+
+/// An owned Code 128 barcode of patterns, as the encoders produce
+pub type Code128Owned = Code128Buf<Pattern>;
+
+impl<E: Encoding> Code128Buf<E> {
+    /// Take ownership of a sequence of symbols, including start, check, and
+    /// stop symbols
+    pub fn new(symbols: Vec<E>) -> Code128Buf<E> {
+        Code128Buf(symbols)
+    }
+
+    /// Borrow as a `Code128`
+    pub fn as_code128(&self) -> Code128<'_, E> {
+        Code128(&self.0)
+    }
+
+    /// The symbols, including start, check, and stop symbols
+    pub fn symbols(&self) -> &[E] {
+        &self.0
+    }
+
+    /// Every symbol value, including the start, check, and stop symbols
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.as_code128().to_bytes()
+    }
+
+    /// A table of the symbols for debugging, one row per symbol
+    pub fn dump(&self, start: Symbology) -> String {
+        self.as_code128().dump(start)
+    }
+
+    /// The symbology selected by the start symbol
+    pub fn start_symbology(&self) -> Option<Symbology> {
+        self.as_code128().start_symbology()
+    }
+
+    /// Whether the last symbol is the stop symbol
+    pub fn end_with_stop(&self) -> bool {
+        self.as_code128().end_with_stop()
+    }
+
+    /// Check the barcode, collecting every problem found
+    pub fn validate(&self) -> ::core::result::Result<(), Vec<Diagnostic>> {
+        self.as_code128().validate()
+    }
+
+    /// Every module of the printed barcode, `true` for dark
+    pub fn modules(&self) -> Vec<bool> {
+        self.as_code128().modules()
+    }
+
+    /// Width of the bars in modules, as `Code128::module_width`
+    pub fn module_width(&self) -> usize {
+        self.as_code128().module_width()
+    }
+}
+
+impl Code128Buf<Pattern> {
+    /// Read symbol values, as from `to_bytes`, failing with `BadFormat`
+    /// for values above 106
+    ///
+    /// The symbols are not otherwise checked.
+    ///
+    /// ```
+    /// use feather_code::barcode::code128::{Code128Buf, Pattern};
+    ///
+    /// let values = [103, 48, 42, 42, 17, 18, 19, 35, 54, 106];
+    /// let code = Code128Buf::from_bytes(&values).unwrap();
+    ///
+    /// assert_eq!(code.symbols()[1], Pattern::C48);
+    /// assert_eq!(code.to_bytes(), values);
+    /// ```
+    pub fn from_bytes(bytes: &[u8]) -> Result<Code128Buf<Pattern>> {
+        bytes.iter().map(|&u| Pattern::try_from_u8(u)).collect::<Result<_>>().map(Code128Buf)
+    }
+
+    /// Append a data symbol, updating the check symbol by the new symbol's
+    /// weight alone
+    ///
+    /// The symbol is written as is, so it must make sense in the symbology
+    /// active at the end.  Fails with `BadFormat` for a start or stop
+    /// symbol, or unless the barcode has a start, check, and stop symbol.
+    ///
+    /// ```
+    /// use feather_code::barcode::code128::{Code128Buf, Pattern};
+    /// use feather_code::barcode::format::{Decode, Encode, Format};
+    ///
+    /// let mut code: Code128Buf<Pattern> = "PJJ123".encode().unwrap();
+    /// code.push_symbol(Pattern::C35).unwrap();
+    ///
+    /// assert!(code.checksum());
+    /// assert_eq!(code.decode(), Ok(String::from("PJJ123C")));
+    /// ```
+    pub fn push_symbol(&mut self, symbol: Pattern) -> Result<()> {
+        if symbol.as_u8() > Pattern::max_data() as u8 {
+            return Err(Error::BadFormat(format!("{} is not a data symbol", symbol.as_u8())));
+        }
+
+        self.append_data(&[symbol.as_u8()])
+    }
+
+    /// Append ASCII text, switching symbology as needed, and update the
+    /// check symbol incrementally
+    ///
+    /// The text is planned as by `encode`, starting with a switch where its
+    /// first symbology differs from the one active at the end.  Fails with
+    /// `BadFormat` for non-ASCII text or a barcode without a start, check,
+    /// and stop symbol.
+    ///
+    /// ```
+    /// use feather_code::barcode::code128::{Code128Buf, Pattern};
+    /// use feather_code::barcode::format::{Decode, Encode};
+    ///
+    /// let mut code: Code128Buf<Pattern> = "LOT-".encode().unwrap();
+    /// code.push_str("000417").unwrap();
+    ///
+    /// assert_eq!(code.decode(), Ok(String::from("LOT-000417")));
+    /// ```
+    pub fn push_str(&mut self, text: &str) -> Result<()> {
+        if let Some(c) = text.chars().find(|c| !c.is_ascii()) {
+            return Err(Error::BadFormat(format!("cannot encode non-ASCII character {:?}", c)));
+        }
+
+        if text.is_empty() {
+            return self.append_data(&[]);
+        }
+
+        let planned = encoder::plan(text.as_bytes());
+        let set = Symbology::from_start_value(planned[0]).expect("plans begin with a start symbol");
+        self.append_in(set, &planned[1..])
+    }
+
+    /// Append digits in pairs in Code C, switching to it if need be, and
+    /// update the check symbol incrementally
+    ///
+    /// Fails with `BadFormat` for anything but an even number of digits,
+    /// as `Code128Builder::push_c_digits` does, or a barcode without a
+    /// start, check, and stop symbol.
+    pub fn extend_digits(&mut self, digits: &str) -> Result<()> {
+        if !digits.len().is_multiple_of(2) || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(Error::BadFormat(format!("{:?} is not an even number of digits",
+                                                digits)));
+        }
+
+        let pairs: Vec<u8> = digits.as_bytes()
+            .chunks(2)
+            .map(|p| (p[0] - b'0') * 10 + p[1] - b'0')
+            .collect();
+
+        self.append_in(Symbology::C, &pairs)
+    }
+
+    /// Append data symbol values written in `set`, switching to it first
+    /// if another symbology is active
+    ///
+    /// Fails with `BadFormat` if the barcode ends with a shift, which would
+    /// apply to the first appended symbol.
+    fn append_in(&mut self, set: Symbology, values: &[u8]) -> Result<()> {
+        let parts = self.as_code128().data()?;
+        let (current, shift) = final_state(parts.symbology, parts.payload);
+
+        if shift.is_some() {
+            return Err(Error::BadFormat(String::from("cannot append after a trailing shift")));
+        }
+
+        if current == set {
+            self.append_data(values)
+        } else {
+            let mut switched = vec![set.code_value()];
+            switched.extend_from_slice(values);
+            self.append_data(&switched)
+        }
+    }
+
+    /// Insert data symbol values before the check symbol, adding each
+    /// one's weighted value to it rather than summing the data again
+    ///
+    /// Fails with `InvalidLength`, leaving the barcode as it was, if that
+    /// would make more than `MAX_SYMBOLS` symbols.
+    fn append_data(&mut self, values: &[u8]) -> Result<()> {
+        let parts = self.as_code128().data()?;
+
+        let count = self.0.len() + values.len();
+        if count > MAX_SYMBOLS {
+            return Err(Error::InvalidLength(Diagnostic::TooManySymbols { count }.to_string()));
+        }
+        let first_weight = parts.payload.len() + 1;
+        let mut check = parts.check.as_u8() % 103;
+
+        #[cfg(debug_assertions)]
+        let (start, was_valid) = {
+            let start = parts.symbology;
+            (start, weighted_checksum(start, parts.payload) == check)
+        };
+
+        let n = self.0.len();
+        self.0.truncate(n - 2);
+
+        for (weight, &value) in (first_weight..).zip(values) {
+            check = add_to_checksum(check, weight, value);
+            self.0.push(Pattern::from(value));
+        }
+
+        #[cfg(debug_assertions)]
+        debug_assert!(!was_valid ||
+                      weighted_checksum(start, &self.0[1..]) == check,
+                      "incremental check symbol differs from the full sum");
+
+        self.0.push(Pattern::from(check));
+        self.0.push(Pattern::stop());
+        Ok(())
+    }
+}
+
+impl<E: Encoding + Debug> Code128Buf<E> {
+    /// Decode the text along with how the barcode encodes it
+    pub fn decode_with_info(&self) -> Result<(String, DecodeInfo)> {
+        self.as_code128().decode_with_info()
+    }
+
+    /// Decode, noting where function codes and changes of symbology fall
+    pub fn decode_full(&self) -> Result<DecodedBarcode> {
+        self.as_code128().decode_full()
+    }
+
+    /// Decode as far as possible, returning the text so far and the error
+    /// that stopped decoding, if any
+    pub fn decode_partial(&self) -> (String, Option<Error>) {
+        self.as_code128().decode_partial()
+    }
+
+    /// Decode into any `fmt::Write`, as `Code128::decode_into`
+    pub fn decode_into<W: fmt::Write>(&self, out: &mut W) -> Result<()> {
+        self.as_code128().decode_into(out)
+    }
+
+    /// Decode, borrowing empty and one character text, as
+    /// `Code128::decode_cow`
+    pub fn decode_cow(&self) -> Result<Cow<'static, str>> {
+        self.as_code128().decode_cow()
+    }
+
+    /// Most characters `decode` can produce, as
+    /// `Code128::decoded_len_upper_bound`
+    pub fn decoded_len_upper_bound(&self) -> usize {
+        self.as_code128().decoded_len_upper_bound()
+    }
+
+    /// Re-encode in as few symbols as possible, as `Code128::normalize`
+    pub fn normalize(&self) -> Result<Code128Buf<Pattern>> {
+        self.as_code128().normalize()
+    }
+
+    /// Decode, assuming a stop symbol if the barcode lacks one
+    pub fn decode_lenient(&self) -> Result<LenientDecode> {
+        self.as_code128().decode_lenient()
+    }
+}
+
+impl<E: Encoding + From<u8>> Code128Buf<E> {
+    /// Check the length, structure, and check symbol all at once, as
+    /// `Code128::verify`
+    pub fn verify(&self) -> VerifyReport<E> {
+        self.as_code128().verify()
+    }
+}
+
+impl<E: Encoding> IntoIterator for Code128Buf<E> {
+    type Item = E;
+    type IntoIter = vec::IntoIter<E>;
+
+    /// Take the symbols, including start, check, and stop symbols
+    fn into_iter(self) -> vec::IntoIter<E> {
+        self.0.into_iter()
+    }
+}
+
+impl<'a, E: Encoding> IntoIterator for &'a Code128Buf<E> {
+    type Item = &'a E;
+    type IntoIter = slice::Iter<'a, E>;
+
+    fn into_iter(self) -> slice::Iter<'a, E> {
+        self.0.iter()
+    }
+}
+
+impl FromIterator<char> for Code128Buf<Pattern> {
+    /// Encode the characters as `str::encode` does
+    ///
+    /// Best effort, for text known to encode: panics for anything `encode`
+    /// rejects, that is non-ASCII characters or more than `MAX_SYMBOLS`
+    /// symbols.  `TryCollect` reports those as errors.
+    ///
+    /// ```
+    /// use feather_code::barcode::code128::{Code128Buf, Pattern};
+    /// use feather_code::barcode::format::Decode;
+    ///
+    /// let code: Code128Buf<Pattern> = "Hello World".chars().collect();
+    ///
+    /// assert_eq!(code.decode(), Ok(String::from("Hello World")));
+    /// ```
+    fn from_iter<I: IntoIterator<Item = char>>(chars: I) -> Code128Buf<Pattern> {
+        match chars.into_iter().try_collect_code128() {
+            Ok(code) => code,
+            Err(e) => panic!("cannot collect into a Code 128 barcode: {}", e),
+        }
+    }
+}
+
+/// Collecting characters into a barcode, failing rather than panicking
+///
+/// ```
+/// use feather_code::barcode::code128::TryCollect;
+/// use feather_code::barcode::format::Decode;
+///
+/// let code = "PJJ123C".chars().try_collect_code128().unwrap();
+/// assert_eq!(code.decode(), Ok(String::from("PJJ123C")));
+///
+/// assert!("na\u{ef}ve".chars().try_collect_code128().is_err());
+/// ```
+pub trait TryCollect: Iterator<Item = char> + Sized {
+    /// Encode the characters as `str::encode` does, failing like it
+    fn try_collect_code128(self) -> Result<Code128Buf<Pattern>>;
+}
+
+impl<I: Iterator<Item = char>> TryCollect for I {
+    fn try_collect_code128(self) -> Result<Code128Buf<Pattern>> {
+        let text: String = self.collect();
+
+        text.encode()
+    }
+}
+
+impl fmt::Display for Code128Buf<Pattern> {
+    /// Write the pattern names separated by spaces, such as
+    /// `C104 C48 C42 C42 C53 C106`
+    ///
+    /// Unlike the borrowed `Code128`, which writes the decoded text, this
+    /// writes every symbol, so it parses back with `FromStr`.
+    ///
+    /// ```
+    /// use feather_code::barcode::code128::{Code128Buf, Pattern};
+    /// use feather_code::barcode::format::Encode;
+    ///
+    /// let code: Code128Buf<Pattern> = "PJJ".encode().unwrap();
+    /// let notation = code.to_string();
+    ///
+    /// assert_eq!(notation, "C104 C48 C42 C42 C53 C106");
+    /// assert_eq!(notation.parse(), Ok(code));
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, p) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{}", p)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl ::core::str::FromStr for Code128Buf<Pattern> {
+    type Err = Error;
+
+    /// Parse pattern names separated by whitespace, as `Display` writes
+    /// them
+    ///
+    /// Fails with `BadFormat` at the first word that isn't a pattern name.
+    /// The symbols are not validated.
+    fn from_str(s: &str) -> Result<Code128Buf<Pattern>> {
+        s.split_whitespace()
+            .map(|name| {
+                Pattern::from_name(name).ok_or_else(|| {
+                    Error::BadFormat(format!("invalid pattern name {:?}", name))
+                })
+            })
+            .collect::<Result<Vec<_>>>()
+            .map(Code128Buf)
+    }
+}
+
+impl<'a, E: 'a + Encoding + Clone> From<Code128<'a, E>> for Code128Buf<E> {
+    fn from(code: Code128<'a, E>) -> Code128Buf<E> {
+        code.to_owned()
+    }
+}
+
+impl<E: Encoding> Format for Code128Buf<E> {
+    fn checksum(&self) -> bool {
+        self.as_code128().checksum()
+    }
+}
+
+impl<E, T> Decode<T> for Code128Buf<E>
+    where E: Encoding,
+          for<'a> Code128<'a, E>: Decode<T>
+{
+    fn decode(&self) -> Result<T> {
+        self.as_code128().decode()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use barcode::format::DecodeAs;
+    use core::fmt;
+    use core::str::FromStr;
+
+    const PJJ123C: [u8; 10] = [103, 48, 42, 42, 17, 18, 19, 35, 54, 106];
+    const GS1: [u8; 11] = [105, 102, 42, 18, 40, 20, 50, 101, 16, 92, 106];
+    const SHIFT: [u8; 10] = [103, 51, 40, 98, 73, 38, 52, 1, 93, 106];
+
+    #[test]
+    fn symbology_values() {
+        for (i, &s) in Symbology::ALL.iter().enumerate() {
+            assert_eq!(s.index(), i);
+            assert_eq!(u8::start(s), s.start_value());
+            assert_eq!(u8::switch(s), s.code_value());
+            assert_eq!(Symbology::from_start_value(s.start_value()), Some(s));
+        }
+
+        assert!(Symbology::A < Symbology::B && Symbology::B < Symbology::C);
+        assert_eq!(Symbology::from_start_value(106), None);
+        assert_eq!(Symbology::from_start_value(99), None);
+    }
+
+    /// Printable ASCII from arbitrary text, for appending
+    fn printable(s: &str) -> String {
+        s.chars().map(|c| (b' ' + (c as u32 % 95) as u8) as char).collect()
+    }
+
+    #[test]
+    fn push_symbol_updates_check() {
+        let mut code: Code128Buf<Pattern> = "PJJ".encode().unwrap();
+        for &symbol in &[Pattern::C17, Pattern::C18, Pattern::C19, Pattern::C35] {
+            code.push_symbol(symbol).unwrap();
+            assert!(code.checksum());
+        }
+
+        assert_eq!(code.decode(), Ok(String::from("PJJ123C")));
+
+        let mut broken = Code128Buf::new(vec![Pattern::C103, Pattern::C48]);
+        assert_eq!(broken.push_symbol(Pattern::C42),
+                   Err(Error::BadFormat(String::from("expected at least 3 symbols, found 2"))));
+    }
+
+    #[test]
+    fn push_symbol_rejects_start_and_stop() {
+        let mut code: Code128Buf<Pattern> = "PJJ".encode().unwrap();
+        for &symbol in &[Pattern::C103, Pattern::C104, Pattern::C105, Pattern::C106] {
+            let message = format!("{} is not a data symbol", symbol.as_u8());
+
+            assert_eq!(code.push_symbol(symbol), Err(Error::BadFormat(message)));
+        }
+
+        assert_eq!(code.decode(), Ok(String::from("PJJ")));
+    }
+
+    #[test]
+    fn append_refuses_trailing_shift() {
+        let mut code: Code128Buf<Pattern> = "ab".encode().unwrap();
+        code.push_symbol(Pattern::C98).unwrap();
+        let shifted = code.clone();
+
+        let refused = Err(Error::BadFormat(String::from("cannot append after a trailing shift")));
+        assert_eq!(code.push_str("cd"), refused);
+        assert_eq!(code.extend_digits("12"), refused);
+        assert_eq!(code, shifted);
+    }
+
+    #[test]
+    fn push_str_and_extend_digits_switch_symbology() {
+        let mut code: Code128Buf<Pattern> = "12".encode().unwrap();
+        code.push_str("ab").unwrap();
+        code.extend_digits("3456").unwrap();
+        code.push_str("\n").unwrap();
+        code.push_str("").unwrap();
+
+        assert!(code.checksum());
+        assert_eq!(code.decode(), Ok(String::from("12ab3456\n")));
+
+        assert_eq!(code.extend_digits("123"),
+                   Err(Error::BadFormat(String::from("\"123\" is not an even number of digits"))));
+        assert!(code.push_str("caf\u{e9}").is_err());
+        assert_eq!(code.decode(), Ok(String::from("12ab3456\n")));
+    }
+
+    #[test]
+    fn appending_stops_at_max_symbols() {
+        let mut code: Code128Buf<Pattern> = [b'x'; MAX_SYMBOLS - 4][..].encode().unwrap();
+        code.push_symbol(Pattern::C1).unwrap();
+        assert_eq!(code.symbols().len(), MAX_SYMBOLS);
+
+        let err = Err(Error::InvalidLength(String::from("233 symbols, more than the 232 allowed")));
+        assert_eq!(code.push_symbol(Pattern::C1), err);
+        assert_eq!(code.push_str("x"), err);
+        assert_eq!(code.symbols().len(), MAX_SYMBOLS);
+        assert!(code.checksum());
+    }
+
+    quickcheck! {
+        fn appends_keep_check_symbol(ops: Vec<(u8, String)>) -> bool {
+            let mut code: Code128Buf<Pattern> = "PJJ".encode().unwrap();
+
+            for &(op, ref s) in &ops {
+                let _ = match op % 3 {
+                    0 => code.push_symbol(Pattern::from(op % 103)),
+                    1 => code.push_str(&printable(s)),
+                    _ => code.extend_digits(&format!("{:02}", op % 100)),
+                };
+            }
+
+            let n = code.symbols().len();
+            let start = code.start_symbology().unwrap();
+            weighted_checksum(start, &code.symbols()[1..n - 2]) == code.symbols()[n - 2].as_u8()
+        }
+
+        fn appends_decode_to_joined_text(pieces: Vec<(bool, String)>) -> bool {
+            let mut code: Code128Buf<Pattern> = "PJJ".encode().unwrap();
+            let mut text = String::from("PJJ");
+
+            for &(digits, ref s) in &pieces {
+                let piece = if digits {
+                    let mut d: String = s.chars().map(|c| (b'0' + c as u8 % 10) as char).collect();
+                    d.truncate(d.len() / 2 * 2);
+                    d
+                } else {
+                    printable(s)
+                };
+                let pushed = if digits {
+                    code.extend_digits(&piece)
+                } else {
+                    code.push_str(&piece)
+                };
+
+                match pushed {
+                    Ok(()) => text.push_str(&piece),
+                    // Past MAX_SYMBOLS the barcode is left as it was
+                    Err(Error::InvalidLength(_)) => {}
+                    Err(_) => return false,
+                }
+            }
+
+            code.checksum() && Decode::<String>::decode(&code) == Ok(text)
+        }
+    }
+
+    #[test]
+    fn decode_cow_borrows_short_text() {
+        use alloc::borrow::Cow;
+
+        // FNC1 alone decodes to nothing
+        let data = [102u8];
+        let empty = [104, 102, compute_checksum(Symbology::B, &data), 106];
+        assert_eq!(Code128::new(&empty).decode_cow(), Ok(Cow::Borrowed("")));
+
+        for b in 0..128u8 {
+            let code = Code128Builder::new().text(&(b as char).to_string()).build();
+            let text = code.unwrap().decode_cow().unwrap();
+
+            assert!(matches!(text, Cow::Borrowed(_)), "{:?}", b as char);
+            assert_eq!(text, (b as char).to_string());
+        }
+
+        for symbols in &[&PJJ123C[..], &GS1[..], &SHIFT[..]] {
+            let code = Code128::new(symbols);
+
+            assert!(matches!(code.decode_cow(), Ok(Cow::Owned(_))));
+            assert_eq!(code.decode_cow().map(Cow::into_owned), code.decode());
+        }
+
+        assert_eq!(Code128::new(&PJJ123C[..9]).decode_cow().map(Cow::into_owned),
+                   Code128::new(&PJJ123C[..9]).decode());
+    }
+
+    #[test]
+    fn collect_chars() {
+        let code: Code128Buf<Pattern> = "PJJ123C".chars().collect();
+        assert_eq!(Ok(code), "PJJ123C".encode());
+
+        let code = "HackFSU 2024".chars().rev().try_collect_code128().unwrap();
+        assert_eq!(code.decode(), Ok(String::from("4202 USFkcaH")));
+
+        assert_eq!("".chars().try_collect_code128(), "".encode());
+        let msg = "cannot encode non-ASCII character '\u{e9}'";
+        assert_eq!("caf\u{e9}".chars().try_collect_code128(),
+                   Err(Error::BadFormat(String::from(msg))));
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot collect into a Code 128 barcode")]
+    fn collect_panics_on_non_ascii() {
+        let _: Code128Buf<Pattern> = "caf\u{e9}".chars().collect();
+    }
+
+    #[test]
+    fn split_at_switch_fixtures() {
+        assert_eq!(Code128::new(&PJJ123C).split_at_switch(),
+                   Ok(vec![(Symbology::A, &PJJ123C[1..8])]));
+        assert_eq!(Code128::new(&GS1).split_at_switch(),
+                   Ok(vec![(Symbology::C, &GS1[1..7]), (Symbology::A, &GS1[7..9])]));
+        assert_eq!(Code128::new(&SHIFT).split_at_switch(),
+                   Ok(vec![(Symbology::A, &SHIFT[1..4]),
+                           (Symbology::B, &SHIFT[4..5]),
+                           (Symbology::A, &SHIFT[5..8])]));
+    }
+
+    #[test]
+    fn split_at_switch_runs_cover_the_data() {
+        for text in &["PJJ123C", "a\x01b\x02c", "12ab3456\n", "x", "123456", "AB12c\x1f9"] {
+            let code: Code128Buf<Pattern> = text.encode().unwrap();
+            let runs = code.as_code128().split_at_switch().unwrap();
+            let joined: Vec<Pattern> = runs.iter()
+                .flat_map(|&(_, run)| run.iter().cloned())
+                .collect();
+            let n = code.symbols().len();
+
+            assert_eq!(joined, &code.symbols()[1..n - 2], "{:?}", text);
+            assert!(runs.iter().all(|&(_, run)| !run.is_empty()));
+            assert!(runs.windows(2).all(|w| w[0].0 != w[1].0), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn split_at_switch_rejects_malformed() {
+        assert_eq!(Code128::new(&PJJ123C[..9]).split_at_switch(),
+                   Err(Error::BadFormat(String::from("missing start or stop symbol"))));
+
+        let mut symbols = PJJ123C;
+        symbols[8] = 55;
+        assert_eq!(Code128::new(&symbols).split_at_switch(),
+                   Err(Error::BadFormat(String::from("check symbol does not match"))));
+    }
+
+    #[test]
+    fn symbology_orders_by_start_value() {
+        use alloc::collections::BTreeMap;
+
+        let mut counts = BTreeMap::new();
+        for &s in &[Symbology::C, Symbology::A, Symbology::C, Symbology::B] {
+            *counts.entry(s).or_insert(0) += 1;
+        }
+
+        assert_eq!(counts.into_iter().collect::<Vec<_>>(),
+                   [(Symbology::A, 1), (Symbology::B, 1), (Symbology::C, 2)]);
+
+        for a in &Symbology::ALL {
+            for b in &Symbology::ALL {
+                assert_eq!(a.cmp(b), a.start_value().cmp(&b.start_value()));
+            }
+        }
+    }
+
+    const PJJ123C_DATA: [Pattern; 7] = [
+        Pattern::C48, Pattern::C42, Pattern::C42, Pattern::C17, Pattern::C18, Pattern::C19,
+        Pattern::C35,
+    ];
+    const PJJ123C_CHECK: Pattern = const_checksum(Symbology::A, &PJJ123C_DATA);
+
+    // Fails to compile if the checksum is wrong
+    const _: () = assert!(PJJ123C_CHECK.as_u8() == 54);
+
+    #[test]
+    fn const_checksum_builds_valid_code() {
+        const CODE: [Pattern; 10] = [
+            Pattern::start(Symbology::A),
+            PJJ123C_DATA[0], PJJ123C_DATA[1], PJJ123C_DATA[2], PJJ123C_DATA[3],
+            PJJ123C_DATA[4], PJJ123C_DATA[5], PJJ123C_DATA[6],
+            PJJ123C_CHECK,
+            Pattern::stop(),
+        ];
+
+        assert!(Code128::new(&CODE).checksum());
+        assert_eq!(Code128::new(&CODE).decode(), Ok(String::from("PJJ123C")));
+    }
+
+    quickcheck! {
+        fn const_checksum_matches_compute_checksum(data: Vec<u8>) -> bool {
+            let data: Vec<Pattern> = data.into_iter().map(|u| Pattern::from(u % 107)).collect();
+
+            Symbology::ALL.iter().all(|&s| const_checksum(s, &data) == compute_checksum(s, &data))
+        }
+    }
+
+    #[test]
+    fn checksum_u8() {
+        assert!(Code128::new(&PJJ123C).checksum());
+        assert!(Code128::new(&GS1).checksum());
+        assert!(!Code128::new(&[103u8, 48, 42, 42, 17, 18, 19, 35, 55, 106]).checksum());
+    }
+
+    #[test]
+    fn checksum_pattern() {
+        let symbols: Vec<Pattern> = PJJ123C.iter().map(|&u| Pattern::from(u)).collect();
+
+        assert!(Code128::new(&symbols).checksum());
+    }
+
+    #[test]
+    fn compute_checksum_matches_fixtures() {
+        assert_eq!(compute_checksum(Symbology::A, &PJJ123C[1..8]), 54);
+        assert_eq!(compute_checksum(Symbology::C, &GS1[1..9]), 92);
+        assert_eq!(compute_checksum(Symbology::C, &[Pattern::C12, Pattern::C34, Pattern::C56]),
+                   Pattern::C44);
+        assert_eq!(compute_checksum::<u8>(Symbology::B, &[]), 104 % 103);
+    }
+
+    #[test]
+    fn weighted_checksum_matches_fixtures() {
+        let gs1: Vec<Pattern> = GS1[1..9].iter().map(|&u| Pattern::from(u)).collect();
+
+        assert_eq!(weighted_checksum(Symbology::A, &PJJ123C[1..8]), 54);
+        assert_eq!(weighted_checksum(Symbology::C, &gs1), 92);
+        assert_eq!(weighted_checksum::<u8>(Symbology::C, &[]), 2);
+
+        let parts = Code128::new(&PJJ123C).data().unwrap();
+        assert_eq!(weighted_checksum(parts.symbology, parts.payload), *parts.check);
+    }
+
+    #[test]
+    fn compute_checksum_long_data() {
+        let data = vec![Pattern::C102; 100_000];
+        let sum = (1..100_001u64).fold(105, |acc, i| acc + i * 102);
+
+        assert_eq!(compute_checksum(Symbology::C, &data).as_u8() as u64, sum % 103);
+    }
+
+    #[test]
+    fn short_data_is_invalid() {
+        assert!(!Code128::new(&[103u8, 106]).checksum());
+        assert!(Decode::<String>::decode(&Code128::new(&[103u8, 106])).is_err());
+    }
+
+    quickcheck! {
+        fn valid_code_checksums(code: testing::ValidCode128) -> bool {
+            Code128::new(&code.0).checksum()
+        }
+
+        fn valid_code_decodes(code: testing::ValidCode128) -> bool {
+            Decode::<String>::decode(&Code128::new(&code.0)).is_ok()
+        }
+
+        fn valid_code_decodes_to_at_most_two_bytes_per_symbol(code: testing::ValidCode128)
+                                                              -> bool {
+            let text: String = Code128::new(&code.0).decode().unwrap();
+
+            text.len() <= 2 * code.0.len()
+        }
+
+        fn normalize_decodes_the_same(code: testing::ValidCode128) -> bool {
+            let code = Code128::new(&code.0);
+
+            let normal = code.normalize().unwrap();
+
+            Decode::<String>::decode(&normal) == code.decode() &&
+            Decode::<Vec<u8>>::decode(&normal) == code.decode()
+        }
+
+        fn normalize_never_lengthens(code: testing::ValidCode128) -> bool {
+            let once = Code128::new(&code.0).normalize().unwrap();
+            let twice = once.normalize().unwrap();
+
+            once.symbols().len() <= code.0.len() && twice.symbols().len() <= once.symbols().len()
+        }
+    }
+
+    #[test]
+    fn validate_fixtures() {
+        assert_eq!(Code128::new(&PJJ123C).validate(), Ok(()));
+        assert_eq!(Code128::new(&GS1).validate(), Ok(()));
+        assert_eq!(Code128::new(&SHIFT).validate(), Ok(()));
+    }
+
+    #[test]
+    fn validate_bad_check_digit() {
+        let symbols = [103u8, 48, 42, 42, 17, 18, 19, 35, 55, 106];
+
+        assert_eq!(Code128::new(&symbols).validate(),
+                   Err(vec![Diagnostic::BadCheckDigit { expected: 54, found: 55 }]));
+    }
+
+    #[test]
+    fn validate_collects_every_problem() {
+        let symbols = [12u8, 48, 150, 42, 98, 7];
+
+        assert_eq!(Code128::new(&symbols).validate(),
+                   Err(vec![Diagnostic::MissingStart,
+                            Diagnostic::MissingStop,
+                            Diagnostic::SymbolOutOfRange { index: 2, value: 150 }]));
+    }
+
+    #[test]
+    fn validate_interior_stop() {
+        let data = [48u8, 106, 42];
+        let symbols = [103, 48, 106, 42, compute_checksum(Symbology::A, &data), 106];
+
+        assert_eq!(Code128::new(&symbols).validate(),
+                   Err(vec![Diagnostic::InteriorStop { index: 2 }]));
+        assert_eq!(Decode::<String>::decode(&Code128::new(&symbols)),
+                   Err(Error::BadFormat(String::from("stop symbol at 2 before the end"))));
+    }
+
+    #[test]
+    fn validate_double_stop() {
+        let mut symbols = PJJ123C.to_vec();
+        symbols.push(106);
+
+        assert_eq!(Code128::new(&symbols).validate(),
+                   Err(vec![Diagnostic::InteriorStop { index: 9 },
+                            Diagnostic::BadCheckDigit { expected: 74, found: 106 }]));
+        assert!(Decode::<String>::decode(&Code128::new(&symbols)).is_err());
+    }
+
+    #[test]
+    fn validate_stop_as_check_digit() {
+        let symbols = [103u8, 48, 42, 42, 17, 18, 19, 35, 106, 106];
+
+        assert_eq!(Code128::new(&symbols).validate(),
+                   Err(vec![Diagnostic::InteriorStop { index: 8 },
+                            Diagnostic::BadCheckDigit { expected: 54, found: 106 }]));
+    }
+
+    quickcheck! {
+        fn interior_stop_never_decodes(data: Vec<u8>, at: usize) -> bool {
+            let mut data: Vec<u8> = data.into_iter().map(|u| u % 103).collect();
+            data.insert(at % (data.len() + 1), 106);
+
+            let mut symbols = vec![104u8];
+            symbols.extend(&data);
+            symbols.push(compute_checksum(Symbology::B, &data));
+            symbols.push(106);
+
+            let code = Code128::new(&symbols);
+
+            Decode::<String>::decode(&code).is_err() && Decode::<Vec<u8>>::decode(&code).is_err()
+        }
+    }
+
+    #[test]
+    fn validate_empty_payload() {
+        assert_eq!(Code128::new(&[104u8, 1, 106]).validate(),
+                   Err(vec![Diagnostic::EmptyPayload]));
+        assert_eq!(Code128::new(&[104u8, 106]).validate(),
+                   Err(vec![Diagnostic::MissingCheck]));
+        assert_eq!(Code128::new(&[104u8, 1, 106]).decode(), Ok(String::new()));
+        assert_eq!(Code128::<u8>::new(&[]).validate(),
+                   Err(vec![Diagnostic::MissingStart, Diagnostic::MissingStop]));
+    }
+
+    #[test]
+    fn validate_shift_before_stop() {
+        let symbols = [104u8, 33, 98, compute_checksum(Symbology::B, &[33u8, 98]), 106];
+
+        assert_eq!(Code128::new(&symbols).validate(),
+                   Err(vec![Diagnostic::UnexpectedShiftBeforeStop]));
+        assert!(!Code128::new(&symbols).checksum());
+        assert_eq!(Decode::<String>::decode(&Code128::new(&symbols)),
+                   Err(Error::BadFormat(String::from("dangling shift at 2"))));
+    }
+
+    #[test]
+    fn decode_back_to_back_shifts() {
+        let data = [33u8, 98, 98, 33];
+        let symbols = [104, 33, 98, 98, 33, compute_checksum(Symbology::B, &data), 106];
+
+        assert_eq!(Decode::<String>::decode(&Code128::new(&symbols)),
+                   Err(Error::BadTransitionAt { index: 2, from: Symbology::A, to: 98 }));
+    }
+
+    #[test]
+    fn decode_back_to_back_shifts_at_end() {
+        let data = [33u8, 98, 98];
+        let symbols = [104, 33, 98, 98, compute_checksum(Symbology::B, &data), 106];
+
+        assert_eq!(Decode::<String>::decode(&Code128::new(&symbols)),
+                   Err(Error::BadFormat(String::from("dangling shift at 3"))));
+    }
+
+    quickcheck! {
+        fn trailing_shift_never_decodes(chars: Vec<u8>, start_a: bool) -> bool {
+            // Characters common to A and B, so the data never leaves the
+            // start symbology
+            let mut data: Vec<u8> = chars.into_iter().map(|u| u % 64).collect();
+            data.push(98);
+
+            let start = if start_a { Symbology::A } else { Symbology::B };
+            let mut symbols = vec![start.start_value()];
+            symbols.extend(&data);
+            symbols.push(compute_checksum(start, &data));
+            symbols.push(106);
+
+            let msg = format!("dangling shift at {}", data.len());
+
+            Decode::<String>::decode(&Code128::new(&symbols)) == Err(Error::BadFormat(msg))
+        }
+    }
+
+    #[test]
+    fn validate_code_c_98_is_not_a_shift() {
+        let symbols = [105u8, 12, 98, compute_checksum(Symbology::C, &[12u8, 98]), 106];
+
+        assert_eq!(Code128::new(&symbols).validate(), Ok(()));
+    }
+
+    #[test]
+    fn validate_code128buf() {
+        let code = Code128Buf::new(vec![Pattern::C103, Pattern::C48, Pattern::C55, Pattern::C106]);
+
+        assert_eq!(code.validate(),
+                   Err(vec![Diagnostic::BadCheckDigit { expected: 48, found: 55 }]));
+    }
+
+    #[test]
+    fn clone_shares_symbols() {
+        let code = Code128::new(&PJJ123C);
+        let copy = code.clone();
+
+        assert_eq!(copy, code);
+        assert!(::core::ptr::eq(copy.0, code.0));
+    }
+
+    #[test]
+    fn to_owned_validates_independently() {
+        let mut symbols = PJJ123C;
+        let owned = Code128::new(&symbols).to_owned();
+
+        symbols[1] = 49;
+
+        assert!(!Code128::new(&symbols).checksum());
+        assert!(owned.checksum());
+        assert_eq!(owned.decode(), Ok(String::from("PJJ123C")));
+    }
+
+    #[test]
+    fn decode_full_positions() {
+        // Start B "PJJ", shift to A for a tab, FNC3, switch to C for "1234"
+        let mut symbols = vec![104, 48, 42, 42, 98, 73, 96, 99, 12, 34];
+        symbols.push(weighted_checksum(Symbology::B, &symbols[1..]));
+        symbols.push(106);
+        let decoded = Code128::new(&symbols).decode_full().unwrap();
+
+        assert_eq!(decoded.to_string(), "PJJ\t1234");
+        assert_eq!(decoded.start_symbology, Symbology::B);
+        assert_eq!(decoded.fnc_positions, [4]);
+        assert_eq!(decoded.transitions, [(3, Symbology::A), (4, Symbology::C)]);
+        let text: String = Code128::new(&symbols).decode().unwrap();
+        assert_eq!(decoded.as_ref(), text);
+        assert_eq!(String::from(decoded), "PJJ\t1234");
+    }
+
+    #[test]
+    fn pattern_notation_round_trip() {
+        let every: Code128Buf<Pattern> = Code128Buf::new((0..107).map(Pattern::from).collect());
+        let notation = every.to_string();
+
+        for (u, name) in notation.split(' ').enumerate() {
+            assert_eq!(name, format!("C{}", u));
+            assert_eq!(Pattern::from_name(name), Some(Pattern::from(u as u8)));
+        }
+        assert_eq!(notation.parse(), Ok(every));
+
+        let code: Code128Buf<Pattern> = " C103\tC48  C54\nC106 ".parse().unwrap();
+        assert_eq!(code.symbols(), [Pattern::C103, Pattern::C48, Pattern::C54, Pattern::C106]);
+        assert_eq!("".parse(), Ok(Code128Buf::<Pattern>::new(Vec::new())));
+        assert_eq!("C103 48 C106".parse::<Code128Buf<Pattern>>(),
+                   Err(Error::BadFormat(String::from("invalid pattern name \"48\""))));
+        assert!("C103 C107".parse::<Code128Buf<Pattern>>().is_err());
+    }
+
+    #[test]
+    fn cloned_buf_decodes_identically() {
+        let code: Code128Buf<Pattern> = "HackFSU \u{1}2024".encode().unwrap();
+        let copy = code.clone();
+
+        assert_eq!(copy, code);
+        assert_eq!(Decode::<String>::decode(&copy), code.decode());
+        assert_eq!(copy.as_code128().to_owned(), code);
+    }
+
+    #[test]
+    fn code128buf_into_iter() {
+        let code: Code128Buf<Pattern> = "PJJ123C".encode().unwrap();
+        let borrowed: Vec<u8> = (&code).into_iter().map(Encoding::as_u8).collect();
+
+        assert_eq!(borrowed, [104, 48, 42, 42, 17, 18, 19, 35, 55, 106]);
+
+        let owned: Vec<Pattern> = code.into_iter().collect();
+        assert_eq!(owned.iter().map(Encoding::as_u8).collect::<Vec<u8>>(), borrowed);
+    }
+
+    #[test]
+    fn code128buf_from_code128() {
+        let symbols: Vec<Pattern> = GS1.iter().map(|&u| Pattern::from(u)).collect();
+        let owned = Code128Buf::from(Code128::new(&symbols));
+
+        assert_eq!(owned.symbols(), &symbols[..]);
+        assert_eq!(owned.validate(), Ok(()));
+    }
+
+    #[test]
+    fn semantic_eq_ignores_symbology_and_function_codes() {
+        let pjj = Code128::new(&PJJ123C);
+        let encoded: Code128Buf<Pattern> = "PJJ123C".encode().unwrap();
+        let gs1_payload: Code128Buf<Pattern> = "42184020500".encode().unwrap();
+
+        assert!(pjj.semantic_eq(&encoded.as_code128()));
+        assert!(Code128::new(&GS1).semantic_eq(&gs1_payload.as_code128()));
+        assert!(!pjj.semantic_eq(&Code128::new(&GS1)));
+        assert!(!pjj.semantic_eq(&Code128::new(&PJJ123C[..7])));
+    }
+
+    #[test]
+    fn semantic_eq_fails_on_decode_errors() {
+        let broken = [103u8, 48, 42, 42, 17, 18, 19, 35, 55, 106];
+
+        assert!(!Code128::new(&broken).semantic_eq(&Code128::new(&broken)));
+        assert!(!Code128::new(&PJJ123C).semantic_eq(&Code128::new(&broken)));
+    }
+
+    #[test]
+    fn decode_with_info_shift() {
+        let (text, info) = Code128::new(&SHIFT).decode_with_info().unwrap();
+
+        assert_eq!(text, "SHiFT!");
+        assert_eq!(info, DecodeInfo {
+            start_symbology: Symbology::A,
+            switches: 0,
+            shifts: 1,
+            fnc1_present: false,
+            fnc4_present: false,
+            symbol_count: 10,
+        });
+    }
+
+    #[test]
+    fn decode_with_info_country_code() {
+        let (text, info) = Code128::new(&GS1).decode_with_info().unwrap();
+
+        assert_eq!(text, "42184020500");
+        assert_eq!(info, DecodeInfo {
+            start_symbology: Symbology::C,
+            switches: 1,
+            shifts: 0,
+            fnc1_present: true,
+            fnc4_present: false,
+            symbol_count: 11,
+        });
+    }
+
+    #[test]
+    fn decode_with_info_fnc4() {
+        let code = encode_bytes(b"\xc1").unwrap();
+        let (_, info) = code.decode_with_info().unwrap();
+
+        assert!(info.fnc4_present);
+        assert!(!info.fnc1_present);
+    }
+
+    #[test]
+    fn decode_with_info_errors_like_decode() {
+        let broken = &PJJ123C[..9];
+
+        assert_eq!(Code128::new(broken).decode_with_info().map(|(text, _)| text),
+                   Code128::new(broken).decode());
+    }
+
+    #[test]
+    fn modules_include_quiet_zones_and_final_bar() {
+        use barcode::render::QUIET_ZONE;
+
+        for symbols in &[&PJJ123C[..], &GS1[..], &SHIFT[..]] {
+            let modules = Code128::new(symbols).modules();
+            let n = modules.len();
+
+            assert_eq!(n, 11 * (symbols.len() - 1) + 13 + 2 * QUIET_ZONE);
+            assert!(!modules[..QUIET_ZONE].contains(&true));
+            assert!(!modules[n - QUIET_ZONE..].contains(&true));
+            assert_eq!(modules[n - QUIET_ZONE - 2..n - QUIET_ZONE], [true, true]);
+        }
+    }
+
+    #[test]
+    fn module_width_matches_rendered_modules() {
+        use barcode::render::{RenderOptions, QUIET_ZONE};
+
+        assert_eq!(Code128::new(&PJJ123C).module_width(), 112);
+        assert_eq!(Code128::new(&GS1).module_width(), 123);
+
+        for symbols in &[&PJJ123C[..], &GS1[..], &SHIFT[..]] {
+            let code = Code128::new(symbols);
+
+            assert_eq!(code.module_width() + 2 * QUIET_ZONE, code.modules().len());
+            assert_eq!(code.to_owned().module_width(), code.module_width());
+        }
+
+        let options = RenderOptions { quiet_zone: 0 };
+        let code: Code128Buf<Pattern> = "HackFSU 2024".encode().unwrap();
+        assert_eq!(Some(code.module_width()), rendered_width_modules("HackFSU 2024", &options));
+    }
+
+    #[test]
+    fn decode_lenient_complete_barcode() {
+        assert_eq!(Code128::new(&GS1).decode_lenient(), Ok(LenientDecode {
+            text: String::from("42184020500"),
+            stop_synthesized: false,
+        }));
+    }
+
+    #[test]
+    fn decode_lenient_missing_stop() {
+        for fixture in &[&PJJ123C[..], &GS1[..], &SHIFT[..]] {
+            let truncated = &fixture[..fixture.len() - 1];
+            let recovered = Code128::new(truncated).decode_lenient().unwrap();
+
+            assert!(recovered.stop_synthesized);
+            assert_eq!(Ok(recovered.text), Code128::new(fixture).decode());
+            assert!(Decode::<String>::decode(&Code128::new(truncated)).is_err());
+        }
+    }
+
+    #[test]
+    fn decode_lenient_still_checks() {
+        assert!(Code128::new(&PJJ123C[..8]).decode_lenient().is_err());
+        assert!(Code128::new(&[103u8, 48, 42, 42, 17, 18, 19, 35, 55]).decode_lenient().is_err());
+        assert!(Code128::<u8>::new(&[]).decode_lenient().is_err());
+    }
+
+    /// A buffer that refuses to grow past `N` bytes
+    struct Fixed<const N: usize> {
+        buf: [u8; N],
+        len: usize,
+    }
+
+    impl<const N: usize> Fixed<N> {
+        fn new() -> Fixed<N> {
+            Fixed { buf: [0; N], len: 0 }
+        }
+
+        fn as_str(&self) -> &str {
+            core::str::from_utf8(&self.buf[..self.len]).unwrap()
+        }
+    }
+
+    impl<const N: usize> fmt::Write for Fixed<N> {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            let end = self.len + s.len();
+            if end > N {
+                return Err(fmt::Error);
+            }
+
+            self.buf[self.len..end].copy_from_slice(s.as_bytes());
+            self.len = end;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn decode_into_fixed_buffer() {
+        for symbols in &[&PJJ123C[..], &GS1[..], &SHIFT[..]] {
+            let code = Code128::new(symbols);
+            let mut out = Fixed::<32>::new();
+
+            assert!(code.decoded_len_upper_bound() <= 32);
+            code.decode_into(&mut out).unwrap();
+            assert_eq!(Ok(String::from(out.as_str())), code.decode());
+            assert!(out.len <= code.decoded_len_upper_bound());
+        }
+    }
+
+    #[test]
+    fn decode_into_full_buffer() {
+        let mut out = Fixed::<4>::new();
+
+        assert_eq!(Code128::new(&PJJ123C).decode_into(&mut out),
+                   Err(Error::DecodeErr(String::from("output refused a decoded character"))));
+        assert_eq!(out.as_str(), "PJJ1");
+
+        let mut out = Fixed::<32>::new();
+        assert!(Code128::new(&[103u8, 106]).decode_into(&mut out).is_err());
+        assert_eq!(Code128::new(&[103u8, 106]).decoded_len_upper_bound(), 0);
+    }
+
+    #[test]
+    fn segments_of_shift_codes() {
+        assert_eq!(Code128::new(&SHIFT).segments(),
+                   Ok(vec![(Symbology::A, String::from("SH")),
+                           (Symbology::B, String::from("i")),
+                           (Symbology::A, String::from("FT!"))]));
+    }
+
+    #[test]
+    fn segments_of_fixtures() {
+        assert_eq!(Code128::new(&PJJ123C).segments(),
+                   Ok(vec![(Symbology::A, String::from("PJJ123C"))]));
+        assert_eq!(Code128::new(&GS1).segments(),
+                   Ok(vec![(Symbology::C, String::from("4218402050")),
+                           (Symbology::A, String::from("0"))]));
+        assert!(Code128::new(&[103u8, 106]).segments().is_err());
+    }
+
+    #[test]
+    fn decode_auto_orient_reads_both_ways() {
+        let mut reversed = PJJ123C;
+        reversed.reverse();
+
+        assert_eq!(Code128::new(&PJJ123C).decode_auto_orient(), Ok(String::from("PJJ123C")));
+        assert_eq!(Code128::new(&reversed).decode_auto_orient(), Ok(String::from("PJJ123C")));
+        assert!(Decode::<String>::decode(&Code128::new(&reversed)).is_err());
+    }
+
+    #[test]
+    fn decode_auto_orient_keeps_original_error() {
+        let mut reversed = PJJ123C;
+        reversed.reverse();
+        reversed[1] = 55;
+        let code = Code128::new(&reversed);
+
+        assert_eq!(code.decode_auto_orient(), code.decode());
+        assert!(code.decode_auto_orient().is_err());
+    }
+
+    #[test]
+    fn reversed_check_agrees_with_reversed_copy() {
+        let damaged: [(usize, u8); 5] = [(0, 0), (3, 200), (5, 106), (7, 98), (8, 55)];
+
+        for &(index, value) in &damaged {
+            let mut symbols = PJJ123C;
+            symbols[index] = value;
+            let mut reversed = symbols;
+            reversed.reverse();
+
+            let copy = Code128::new(&symbols);
+            let view = Code128::new(&reversed);
+            assert_eq!(view.validate_oriented(true), copy.validate());
+            assert_eq!(view.check(true).map(|(s, _)| s), copy.check(false).map(|(s, _)| s));
+        }
+    }
+
+    #[test]
+    fn data_splits_parts() {
+        let parts = Code128::new(&GS1).data().unwrap();
+
+        assert_eq!(parts, Code128Parts {
+            symbology: Symbology::C,
+            payload: &GS1[1..9],
+            check: &GS1[9],
+        });
+    }
+
+    #[test]
+    fn data_reports_layout_errors() {
+        let err = |msg: &str| Err(Error::BadFormat(String::from(msg)));
+
+        assert_eq!(Code128::new(&[103u8, 106]).data(), err("expected at least 3 symbols, found 2"));
+        assert_eq!(Code128::new(&[12u8, 34, 106]).data(), err("missing start symbol"));
+        assert_eq!(Code128::new(&PJJ123C[..9]).data(), err("missing stop symbol"));
+    }
+
+    #[test]
+    fn decode_all_back_to_back() {
+        let symbols: Vec<Pattern> = PJJ123C.iter()
+            .chain(&GS1)
+            .chain(&SHIFT)
+            .map(|&u| Pattern::from(u))
+            .collect();
+
+        assert_eq!(decode_all(&symbols), vec![Ok(String::from("PJJ123C")),
+                                              Ok(String::from("42184020500")),
+                                              Ok(String::from("SHiFT!"))]);
+    }
+
+    #[test]
+    fn decode_all_checks_each_span() {
+        let mut symbols = PJJ123C.to_vec();
+        symbols.extend(&[103u8, 48, 42, 42, 17, 18, 19, 35, 55, 106]);
+        symbols.extend(&GS1);
+        symbols.extend(&[104u8, 33]);
+
+        let decoded = decode_all(&symbols);
+
+        assert_eq!(decoded.len(), 4);
+        assert_eq!(decoded[0], Ok(String::from("PJJ123C")));
+        assert_eq!(decoded[1],
+                   Err(Error::BadFormat(String::from("check symbol does not match"))));
+        assert_eq!(decoded[2], Ok(String::from("42184020500")));
+        assert!(decoded[3].is_err());
+    }
+
+    #[test]
+    fn decode_all_empty() {
+        assert_eq!(decode_all::<u8>(&[]), vec![]);
+    }
+
+    #[test]
+    fn decode_symbology_a() {
+        assert_eq!(Code128::new(&PJJ123C).decode(), Ok(String::from("PJJ123C")));
+    }
+
+    #[test]
+    fn decode_symbology_c_with_fnc1() {
+        assert_eq!(Code128::new(&GS1).decode(), Ok(String::from("42184020500")));
+    }
+
+    #[test]
+    fn decode_shift_codes() {
+        assert_eq!(Code128::new(&SHIFT).decode(), Ok(String::from("SHiFT!")));
+    }
+
+    #[test]
+    fn decode_bytes_skips_function_codes() {
+        assert_eq!(Decode::<Vec<u8>>::decode(&Code128::new(&GS1)), Ok(b"42184020500".to_vec()));
+    }
+
+    #[test]
+    fn decode_bytes_control_characters() {
+        let data = [64u8, 90, 95, 33];
+        let symbols = [103, 64, 90, 95, 33, compute_checksum(Symbology::A, &data), 106];
+
+        assert_eq!(Decode::<Vec<u8>>::decode(&Code128::new(&symbols)),
+                   Ok(vec![0x00, 0x1a, 0x1f, b'A']));
+    }
+
+    #[test]
+    fn encode_bytes_latin1() {
+        let latin1 = b"caf\xe9 cr\xe8me br\xfbl\xe9e";
+        let code = encode_bytes(latin1).unwrap();
+
+        assert!(code.checksum());
+        assert_eq!(Decode::<Vec<u8>>::decode(&code), Ok(latin1.to_vec()));
+        assert_eq!(code.decode(), Ok(String::from("café crème brûlée")));
+        assert_eq!(Decode::<Vec<u8>>::decode(&encode_bytes(b"").unwrap()), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn normalize_keeps_extended_characters() {
+        // "\xc8ack" with FNC4 before the first letter, then a needless
+        // switch to Code A and back
+        let data = [100u8, 40, 101, 100, 65, 67, 75];
+        let mut symbols = vec![104u8];
+        symbols.extend(&data);
+        symbols.push(compute_checksum(Symbology::B, &data));
+        symbols.push(106);
+
+        let code = Code128::new(&symbols).normalize().unwrap();
+
+        assert!(code.symbols().len() < symbols.len());
+        assert_eq!(Decode::<Vec<u8>>::decode(&code), Ok(b"\xc8ack".to_vec()));
+        assert_eq!(code.decode(), Ok(String::from("\u{c8}ack")));
+    }
+
+    #[test]
+    fn mixed_encode_shifts_for_lone_characters() {
+        let (code, stats) = mixed_encode_verbose("\x01ABCdEFG\x02").unwrap();
+
+        assert_eq!(stats, MixedEncodeStats {
+            start: Symbology::A,
+            switches: 0,
+            shifts: 1,
+            symbols: 13,
+        });
+        assert_eq!(code.symbols()[5], Pattern::C98);
+        assert_eq!(code.decode(), Ok(String::from("\x01ABCdEFG\x02")));
+    }
+
+    #[test]
+    fn mixed_encode_switches_for_runs() {
+        let (code, stats) = mixed_encode_verbose("\x01\x02ABCdefgh\x03\x04").unwrap();
+
+        assert_eq!((stats.start, stats.switches, stats.shifts), (Symbology::A, 2, 0));
+        assert_eq!(stats.symbols, code.symbols().len());
+        assert_eq!(code.decode(), Ok(String::from("\x01\x02ABCdefgh\x03\x04")));
+    }
+
+    #[test]
+    fn mixed_encode_counts_digit_switches() {
+        let (_, stats) = mixed_encode_verbose("PJJ123456C").unwrap();
+
+        assert_eq!((stats.start, stats.switches, stats.shifts), (Symbology::B, 2, 0));
+        let (_, stats) = mixed_encode_verbose("").unwrap();
+        assert_eq!((stats.start, stats.switches, stats.shifts), (Symbology::B, 0, 0));
+        assert!(mixed_encode("naïve").is_err());
+    }
+
+    quickcheck! {
+        fn mixed_encode_stats_match_decoded_info(bytes: Vec<u8>) -> bool {
+            let text: String = bytes.iter().map(|&b| char::from(b % 128)).collect();
+            let (code, stats) = mixed_encode_verbose(&text).unwrap();
+            let (_, info) = code.decode_with_info().unwrap();
+
+            stats == MixedEncodeStats {
+                start: info.start_symbology,
+                switches: info.switches,
+                shifts: info.shifts,
+                symbols: info.symbol_count,
+            }
+        }
+    }
+
+    #[test]
+    fn encode_padded_hits_every_width() {
+        for text in &["PJJ123C", "123456", "x", "LOT 12345678", ""] {
+            let len = encoded_len(text).unwrap();
+
+            for symbols in len..len + 8 {
+                let code = encode_padded(text, symbols).unwrap();
+
+                assert_eq!(code.symbols().len(), symbols, "{:?} in {}", text, symbols);
+                assert!(code.checksum());
+                assert_eq!(code.decode(), Ok(String::from(*text)));
+                assert_eq!(Decode::<Vec<u8>>::decode(&code), Ok(text.as_bytes().to_vec()));
+            }
+        }
+    }
+
+    #[test]
+    fn encode_padded_rejects_overlong_text() {
+        let msg = "\"PJJ123C\" needs 10 symbols, more than 9";
+        assert_eq!(encode_padded("PJJ123C", 9), Err(Error::InvalidLength(String::from(msg))));
+        assert!(encode_padded("", 2).is_err());
+    }
+
+    #[test]
+    fn decode_with_ignores_function_codes_by_default() {
+        for symbols in &[&PJJ123C[..], &GS1[..], &SHIFT[..]] {
+            let code = Code128::new(symbols);
+
+            assert_eq!(code.decode_with(&DecodeOptions::default()), code.decode());
+        }
+    }
+
+    #[test]
+    fn decode_with_rejects_function_codes() {
+        let options = DecodeOptions {
+            function_codes: FunctionCodePolicy::Error,
+            ..DecodeOptions::default()
+        };
+
+        assert_eq!(Code128::new(&GS1).decode_with(&options),
+                   Err(Error::BadSymbolAt { index: 0, value: 102, context: FUNCTION_CODE }));
+        assert_eq!(Code128::new(&PJJ123C).decode_with(&options),
+                   Ok(String::from("PJJ123C")));
+    }
+
+    #[test]
+    fn decode_with_emits_every_function_code() {
+        // FNC3, FNC2, and FNC1 in Code B around "a" extended by FNC4
+        let data = [96u8, 97, 100, 65, 102];
+        let mut symbols = vec![104u8];
+        symbols.extend(&data);
+        symbols.push(compute_checksum(Symbology::B, &data));
+        symbols.push(106);
+
+        let options = DecodeOptions {
+            function_codes: FunctionCodePolicy::Emit('|'),
+            ..DecodeOptions::default()
+        };
+        let code = Code128::new(&symbols);
+
+        assert_eq!(code.decode_with(&options), Ok(String::from("||\u{e1}|")));
+        assert_eq!(code.decode(), Ok(String::from("\u{e1}")));
+
+        let options = DecodeOptions {
+            function_codes: FunctionCodePolicy::Error,
+            ..DecodeOptions::default()
+        };
+        assert_eq!(code.decode_with(&options),
+                   Err(Error::BadSymbolAt { index: 0, value: 96, context: FUNCTION_CODE }));
+    }
+
+    #[test]
+    fn decode_with_charmap() {
+        let mut map = ['\0'; 128];
+        for (b, c) in (0..128u8).zip(map.iter_mut()) {
+            *c = char::from(b);
+        }
+        map[b'\t' as usize] = '\u{2409}';
+        map[b'1' as usize] = 'I';
+
+        let code: Code128Buf<Pattern> = "PJJ\t1 123456".encode().unwrap();
+        let options = DecodeOptions {
+            function_codes: FunctionCodePolicy::Emit('\t'),
+            charmap: Some(&map),
+            ..DecodeOptions::default()
+        };
+        let expected: String = code.as_code128()
+            .segments()
+            .unwrap()
+            .into_iter()
+            .flat_map(|(set, text)| {
+                text.bytes()
+                    .map(|b| if set == Symbology::C { char::from(b) } else { map[b as usize] })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        assert!(expected.starts_with("PJJ\u{2409}I "));
+        assert!(expected.ends_with("23456"));
+        assert_eq!(code.as_code128().decode_with(&options), Ok(expected));
+
+        // Function codes are emitted as given, not through the map
+        assert_eq!(Code128::new(&GS1).decode_with(&options),
+                   Ok(String::from("\t42184020500")));
+    }
+
+    const REVERSED: DecodeOptions<'static> = DecodeOptions {
+        function_codes: FunctionCodePolicy::Ignore,
+        allow_reversed: true,
+        charmap: None,
+    };
+
+    #[test]
+    fn decode_with_reads_reversed_fixtures() {
+        for symbols in &[&PJJ123C[..], &GS1[..], &SHIFT[..]] {
+            let mut reversed = symbols.to_vec();
+            reversed.reverse();
+            let code = Code128::new(&reversed);
+
+            assert!(code.is_reversed());
+            assert!(!Code128::new(symbols).is_reversed());
+            assert_eq!(code.decode_with(&REVERSED), Code128::new(symbols).decode());
+            assert_eq!(Code128::new(symbols).decode_with(&REVERSED),
+                       Code128::new(symbols).decode());
+            assert!(code.decode_with(&DecodeOptions::default()).is_err());
+        }
+    }
+
+    #[test]
+    fn decode_with_reversed_reports_logical_positions() {
+        let mut symbols = GS1.to_vec();
+        symbols.reverse();
+        let options = DecodeOptions { function_codes: FunctionCodePolicy::Error, ..REVERSED };
+
+        assert_eq!(Code128::new(&symbols).decode_with(&options),
+                   Err(Error::BadSymbolAt { index: 0, value: 102, context: FUNCTION_CODE }));
+
+        // The check symbol is weighted in reading order, so a reversed
+        // barcode with its data also reversed doesn't pass
+        let mut symbols = PJJ123C.to_vec();
+        symbols[1..8].reverse();
+        symbols.reverse();
+        assert_eq!(Code128::new(&symbols).decode_with(&REVERSED),
+                   Err(Error::BadFormat(String::from("check symbol does not match"))));
+    }
+
+    #[test]
+    fn palindromic_symbols_are_not_reversed() {
+        let stops: [u8; 5] = [106, 17, 103, 17, 106];
+        let starts: [u8; 5] = [103, 17, 106, 17, 103];
+
+        for symbols in &[&stops[..], &starts[..]] {
+            let code = Code128::new(symbols);
+
+            assert!(!code.is_reversed());
+            assert_eq!(code.decode_with(&REVERSED), code.decode());
+        }
+
+        // Stop first and start last, but too short or with a second stop
+        for symbols in &[&[106u8, 103][..], &[106, 54, 103], &[106, 54, 106, 103]] {
+            assert_eq!(Code128::new(symbols).is_reversed(), symbols.len() >= 3);
+            assert!(Code128::new(symbols).decode_with(&REVERSED).is_err());
+        }
+    }
+
+    #[test]
+    fn decode_partial_keeps_text_before_error() {
+        let symbols = [104u8, 48, 42, 98, 105, 23, 106];
+        let (text, error) = Code128::new(&symbols).decode_partial();
+
+        assert_eq!(text, "PJ");
+        assert_eq!(error, Decode::<String>::decode(&Code128::new(&symbols)).err());
+        assert!(error.is_some());
+    }
+
+    #[test]
+    fn decode_partial_complete() {
+        assert_eq!(Code128::new(&PJJ123C).decode_partial(), (String::from("PJJ123C"), None));
+
+        let (text, error) = Code128::new(&[103u8, 48, 106]).decode_partial();
+        assert_eq!(text, "");
+        assert!(error.is_some());
+    }
+
+    #[test]
+    fn dump_pjj123c() {
+        let dump = Code128::new(&PJJ123C).dump(Symbology::B);
+        let lines: Vec<&str> = dump.lines().collect();
+
+        assert_eq!(lines, ["index  pattern  value  meaning",
+                           "    0  C103       103  Start A",
+                           "    1  C48         48  P",
+                           "    2  C42         42  J",
+                           "    3  C42         42  J",
+                           "    4  C17         17  1",
+                           "    5  C18         18  2",
+                           "    6  C19         19  3",
+                           "    7  C35         35  C",
+                           "    8  C54         54  V",
+                           "    9  C106       106  Stop"]);
+        assert!(dump.ends_with('\n'));
+    }
+
+    #[test]
+    fn dump_follows_shifts_and_switches() {
+        let dump = Code128::new(&SHIFT).dump(Symbology::A);
+        let meanings: Vec<&str> = dump.lines().skip(1).map(|l| &l[23..]).collect();
+
+        assert_eq!(meanings, ["Start A", "S", "H", "Shift B", "i", "F", "T", "!", "GS", "Stop"]);
+
+        let dump = Code128::new(&[104u8, 99, 12, 200, 101, 64]).dump(Symbology::B);
+        let meanings: Vec<&str> = dump.lines().skip(1).map(|l| &l[23..]).collect();
+
+        assert_eq!(meanings, ["Start B", "Code C", "12", "?", "Code A", "NUL"]);
+        assert!(dump.contains("    3  ?          200  ?"));
+    }
+
+    #[test]
+    fn display_decodes() {
+        let patterns: Vec<Pattern> = PJJ123C.iter().map(|&u| Pattern::from(u)).collect();
+
+        assert_eq!(format!("{}", Code128::new(&patterns)), "PJJ123C");
+        assert_eq!(format!("{}", Code128::new(&GS1)), "42184020500");
+        assert_eq!(format!("{}", Code128::new(&patterns[..9])),
+                   "<invalid: bad format: missing start or stop symbol>");
+    }
+
+    #[test]
+    fn debug_shows_symbols_and_text() {
+        let patterns: Vec<Pattern> = PJJ123C.iter().map(|&u| Pattern::from(u)).collect();
+
+        assert_eq!(format!("{:?}", Code128::new(&patterns)),
+                   "Code128([C103, C48, C42, C42, C17, C18, C19, C35, C54, C106], \
+                    decoded=\"PJJ123C\")");
+        assert_eq!(format!("{:?}", Code128::new(&[103u8, 106])),
+                   "Code128([103, 106], \
+                    decoded=<invalid: bad format: missing start or stop symbol>)");
+    }
+
+    #[test]
+    fn equal_bufs_hash_equal() {
+        use std::collections::HashSet;
+
+        let mut seen = HashSet::new();
+        seen.insert(Code128Buf::from_bytes(&PJJ123C).unwrap());
+        seen.insert(Code128Buf::from_bytes(&PJJ123C).unwrap());
+
+        assert_eq!(seen.len(), 1);
+
+        seen.insert(Code128Buf::from_bytes(&GS1).unwrap());
+        assert_eq!(seen.len(), 2);
+    }
+
+    #[test]
+    fn to_bytes_matches_fixtures() {
+        for fixture in &[&PJJ123C[..], &GS1[..], &SHIFT[..]] {
+            let patterns: Vec<Pattern> = fixture.iter().map(|&u| Pattern::from(u)).collect();
+
+            assert_eq!(Code128::new(&patterns).to_bytes(), *fixture);
+            assert_eq!(Code128::new(fixture).to_bytes(), *fixture);
+        }
+    }
+
+    #[test]
+    fn from_bytes_rejects_out_of_range() {
+        assert_eq!(Code128Buf::from_bytes(&[103, 107, 106]),
+                   Err(Error::BadFormat(String::from("pattern value 107 out of range"))));
+        assert_eq!(Code128Buf::from_bytes(&[]), Ok(Code128Buf::new(Vec::new())));
+    }
+
+    quickcheck! {
+        fn from_bytes_round_trips(values: Vec<u8>) -> bool {
+            let values: Vec<u8> = values.into_iter().map(|u| u % 107).collect();
+            let code = Code128Buf::from_bytes(&values).unwrap();
+
+            code.to_bytes() == values && Code128Buf::from_bytes(&code.to_bytes()) == Ok(code)
+        }
+    }
+
+    #[test]
+    fn start_symbology_of_each_start() {
+        for &s in &Symbology::ALL {
+            let symbols = [s.start_value(), 0, 106];
+
+            assert_eq!(Code128::new(&symbols).start_symbology(), Some(s));
+        }
+
+        let code: Code128Buf<Pattern> = "123456".encode().unwrap();
+        assert_eq!(code.start_symbology(), Some(Symbology::C));
+    }
+
+    #[test]
+    fn start_symbology_malformed() {
+        assert_eq!(Code128::<u8>::new(&[]).start_symbology(), None);
+        assert_eq!(Code128::new(&[103u8, 106]).start_symbology(), None);
+        assert_eq!(Code128::new(&[48u8, 42, 106]).start_symbology(), None);
+        assert_eq!(Code128::new(&[106u8, 42, 106]).start_symbology(), None);
+    }
+
+    #[test]
+    fn detect_start_looks_only_at_first_symbol() {
+        for &s in &Symbology::ALL {
+            assert_eq!(detect_start(&[s.start_value()]), Some(s));
+            assert_eq!(detect_start(&[Pattern::from(s.start_value()), Pattern::from(200)]),
+                       Some(s));
+        }
+
+        assert_eq!(detect_start(&PJJ123C), Code128::new(&PJJ123C).start_symbology());
+        assert_eq!(detect_start(&PJJ123C[1..]), None);
+    }
+
+    #[test]
+    fn verify_reports_each_problem() {
+        let report = Code128::new(&PJJ123C).verify();
+        assert!(report.is_ok());
+        assert_eq!(report.expected_check, Some(54));
+
+        // No stop symbol to find the check symbol by
+        let report = Code128::new(&PJJ123C[..9]).verify();
+        assert_eq!(report, VerifyReport {
+            length_ok: true,
+            structure_ok: false,
+            checksum_ok: false,
+            expected_check: None,
+        });
+
+        // Out of range, but the check symbol can still be computed
+        let mut symbols = PJJ123C;
+        symbols[3] = 120;
+        let report = Code128::new(&symbols).verify();
+        assert!(report.length_ok && !report.structure_ok && !report.checksum_ok);
+        assert_eq!(report.expected_check, Some(weighted_checksum(Symbology::A, &symbols[1..8])));
+
+        // No data, which still reads as empty text
+        let report = Code128::new(&[103u8, 0, 106]).verify();
+        assert!(report.length_ok && report.structure_ok && report.checksum_ok);
+
+        let report = Code128::new(&[103u8, 106]).verify();
+        assert!(!report.length_ok && !report.structure_ok && !report.checksum_ok);
+
+        let options = EncodeOptions { allow_oversize: true };
+        let long = encode_with(&[b'x'; MAX_SYMBOLS], &options).unwrap();
+        let report = long.verify();
+        assert!(!report.length_ok && report.structure_ok && report.checksum_ok);
+        assert_eq!(report.expected_check, long.symbols().get(long.symbols().len() - 2).cloned());
+    }
+
+    #[test]
+    fn end_with_stop() {
+        assert!(Code128::new(&PJJ123C).end_with_stop());
+        assert!(Code128::new(&[106u8]).end_with_stop());
+        assert!(!Code128::new(&PJJ123C[..9]).end_with_stop());
+        assert!(!Code128::<u8>::new(&[]).end_with_stop());
+        assert!(Code128Buf::new(PJJ123C.to_vec()).end_with_stop());
+    }
+
+    #[test]
+    fn decode_batch_in_order() {
+        let bad = [103u8, 48, 42, 42, 17, 18, 19, 35, 55, 106];
+        let codes = [Code128::new(&PJJ123C), Code128::new(&bad), Code128::new(&GS1)];
+        let results = decode_batch(&codes);
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0], Ok(String::from("PJJ123C")));
+        assert!(results[1].is_err());
+        assert_eq!(results[2], Ok(String::from("42184020500")));
+        assert!(decode_batch::<u8>(&[]).is_empty());
+    }
+
+    #[cfg(feature = "rayon")]
+    quickcheck! {
+        fn par_decode_batch_matches_sequential(codes: Vec<Vec<u8>>) -> bool {
+            let codes: Vec<Code128<u8>> = codes.iter().map(|c| Code128::new(c)).collect();
+
+            par_decode_batch(&codes) == decode_batch(&codes)
+        }
+    }
+
+    quickcheck! {
+        fn decode_batch_matches_decode(codes: Vec<Vec<u8>>) -> bool {
+            let codes: Vec<Code128<u8>> = codes.iter().map(|c| Code128::new(c)).collect();
+            let single: Vec<Result<String>> = codes.iter().map(|c| c.decode()).collect();
+
+            decode_batch(&codes) == single
+        }
+
+        fn decode_partial_reads_back_encoded_bytes(bytes: Vec<u8>) -> bool {
+            let options = EncodeOptions { allow_oversize: true };
+            let code = encode_with(&bytes, &options).unwrap();
+            let expected: String = bytes.iter().map(|&b| char::from(b)).collect();
+
+            code.as_code128().decode_partial() == (expected, None)
+        }
+
+        fn decode_partial_stops_at_damage(bytes: Vec<u8>, at: usize) -> bool {
+            let options = EncodeOptions { allow_oversize: true };
+            let mut symbols: Vec<u8> = encode_with(&bytes, &options).unwrap()
+                .symbols()
+                .iter()
+                .map(Encoding::as_u8)
+                .collect();
+            let n = symbols.len();
+            if n == 3 {
+                return true;
+            }
+
+            // A start symbol among the data, behind a check symbol that fits
+            let index = at % (n - 3);
+            symbols[index + 1] = 103;
+            let start = detect_start(&symbols).unwrap();
+            symbols[n - 2] = weighted_checksum(start, &symbols[1..n - 2]);
+
+            let expected: String = bytes.iter().map(|&b| char::from(b)).collect();
+            let (text, error) = Code128::new(&symbols).decode_partial();
+
+            expected.starts_with(&text) && match error {
+                Some(Error::BadTransitionAt { index: i, to: 103, .. }) => i == index,
+                _ => false,
+            }
+        }
+    }
+
+    #[test]
+    fn decode_rejects_bad_checksum() {
+        let symbols = [103u8, 48, 42, 42, 17, 18, 19, 35, 55, 106];
+
+        assert_eq!(Decode::<String>::decode(&Code128::new(&symbols)),
+                   Err(Error::BadFormat(String::from("check symbol does not match"))));
+    }
+
+    #[test]
+    fn decode_rejects_missing_stop() {
+        assert!(Decode::<String>::decode(&Code128::new(&PJJ123C[..9])).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn symbology_serde_round_trip() {
+        for &s in &[Symbology::A, Symbology::B, Symbology::C] {
+            let json = ::serde_json::to_string(&s).unwrap();
+            assert_eq!(::serde_json::from_str::<Symbology>(&json).unwrap(), s);
+
+            let bytes = ::bincode::serialize(&s).unwrap();
+            assert_eq!(::bincode::deserialize::<Symbology>(&bytes).unwrap(), s);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn code128buf_serde_round_trip() {
+        let code = Code128Buf::new(PJJ123C.iter().map(|&u| Pattern::from(u)).collect());
+
+        let json = ::serde_json::to_string(&code).unwrap();
+        assert_eq!(json, "[103,48,42,42,17,18,19,35,54,106]");
+        assert_eq!(::serde_json::from_str::<Code128Buf<Pattern>>(&json).unwrap(), code);
+
+        let bytes = ::bincode::serialize(&code).unwrap();
+        assert_eq!(::bincode::deserialize::<Code128Buf<Pattern>>(&bytes).unwrap(), code);
+    }
+
+    #[test]
+    fn decode_as_u64() {
+        assert_eq!(Code128::new(&GS1).decode_as::<u64>(), Ok(42184020500));
+    }
+
+    #[test]
+    fn decode_as_f64() {
+        let symbols = [104u8, 19, 14, 18, 21, 83, 106];
+
+        assert_eq!(Code128::new(&symbols).decode_as::<f64>(), Ok(3.25));
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Lot(u32);
+
+    #[derive(Debug)]
+    struct LotErr;
+
+    impl fmt::Display for LotErr {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "not a lot number")
+        }
+    }
+
+    impl FromStr for Lot {
+        type Err = LotErr;
+
+        fn from_str(s: &str) -> ::core::result::Result<Lot, LotErr> {
+            if !s.starts_with("LOT") {
+                return Err(LotErr);
+            }
+
+            s[3..].parse().map(Lot).map_err(|_| LotErr)
+        }
+    }
+
+    #[test]
+    fn decode_as_custom_type() {
+        let symbols = [104u8, 44, 47, 52, 20, 18, 53, 106];
+
+        assert_eq!(Code128::new(&symbols).decode_as::<Lot>(), Ok(Lot(42)));
+        assert_eq!(Code128::new(&PJJ123C).decode_as::<Lot>(),
+                   Err(Error::DecodeErr(String::from("unable to parse \"PJJ123C\": not a lot number"))));
+    }
+
+    #[test]
+    fn decode_as_preserves_parse_error() {
+        match Code128::new(&PJJ123C).decode_as::<u64>() {
+            Err(Error::DecodeErr(msg)) => {
+                assert!(msg.contains("\"PJJ123C\""));
+                assert!(msg.contains("invalid digit found in string"));
+            }
+            other => panic!("expected DecodeErr, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn decode_as_propagates_decode_errors() {
+        assert!(Code128::new(&PJJ123C[..9]).decode_as::<u64>().is_err());
+    }
+}
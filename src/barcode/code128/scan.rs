@@ -0,0 +1,277 @@
+//  This Source Code Form is subject to the terms of the Mozilla Public
+//  License, v. 2.0. If a copy of the MPL was not distributed with this
+//  file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Reading Code 128 barcodes back from a row of modules
+//!
+//! Every symbol is 11 modules wide, so a scan line is cut into 11 module
+//! chunks rather than measured bar by bar, and a damaged module spoils only
+//! the symbol it falls in.  With `ScanOptions::max_symbol_errors` above
+//! zero, such symbols are repaired to their nearest patterns and the check
+//! symbol decides whether the repair stands.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use super::encodings::PATTERNS;
+use super::{Code128Buf, Pattern};
+use barcode::format::{Error, Format, Result};
+use barcode::render;
+
+/// Modules in every symbol, not counting the final bar after the stop symbol
+const SYMBOL_MODULES: usize = 11;
+
+/// Most combinations of tied candidates `scan_modules` tries before giving
+/// up on a repair
+pub const MAX_REPAIR_ATTEMPTS: usize = 4096;
+
+/// Settings for `scan_modules`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ScanOptions {
+    /// Most symbols that may be repaired; zero reads only undamaged bars
+    pub max_symbol_errors: usize,
+}
+
+/// A barcode read by `scan_modules`
+#[derive(Debug, PartialEq)]
+pub struct Scan {
+    /// The symbols read
+    pub code: Code128Buf<Pattern>,
+    /// Number of symbols replaced by their nearest pattern
+    pub repaired: usize,
+}
+
+/// The modules of a pattern as the low 11 bits, first module highest
+fn module_bits(p: Pattern) -> u16 {
+    let mut bits = 0;
+
+    for (i, &w) in p.bar_widths().iter().enumerate() {
+        for _ in 0..w {
+            bits = bits << 1 | if i % 2 == 0 { 1 } else { 0 };
+        }
+    }
+
+    bits
+}
+
+fn chunk_bits(chunk: &[bool]) -> u16 {
+    chunk.iter().fold(0, |bits, &dark| bits << 1 | dark as u16)
+}
+
+/// Patterns nearest to `bits` by Hamming distance, with that distance
+fn nearest(bits: u16) -> (u32, Vec<Pattern>) {
+    let distance = |p: &Pattern| (module_bits(*p) ^ bits).count_ones();
+    let best = PATTERNS.iter().map(distance).min().unwrap_or(0);
+
+    (best, PATTERNS.iter().cloned().filter(|p| distance(p) == best).collect())
+}
+
+/// Read the symbols from a row of modules, `true` for dark
+///
+/// Light modules on either side are skipped, and the rest must be 11
+/// modules per symbol plus the final 2 module bar.  Symbols whose modules
+/// match no pattern fail the scan with `BadFormat`, unless `options` allows
+/// repairing them: each is replaced by its nearest pattern, trying every
+/// pattern tied for nearest, and the first repair that validates is kept.
+/// Damage with more than `MAX_REPAIR_ATTEMPTS` such combinations fails with
+/// `BadFormat` without trying any.  The symbols are not otherwise
+/// validated.
+///
+/// ```
+/// use feather_code::barcode::code128::{scan_modules, Code128Buf, Pattern, ScanOptions};
+/// use feather_code::barcode::format::Encode;
+///
+/// let code: Code128Buf<Pattern> = "PJJ123C".encode().unwrap();
+/// let mut modules = code.modules();
+/// modules[40] = !modules[40];
+///
+/// let options = ScanOptions { max_symbol_errors: 1 };
+/// let scan = scan_modules(&modules, &options).unwrap();
+///
+/// assert_eq!(scan.code, code);
+/// assert_eq!(scan.repaired, 1);
+/// ```
+pub fn scan_modules(modules: &[bool], options: &ScanOptions) -> Result<Scan> {
+    let bars = render::bars(modules)?;
+
+    if bars.len() < 3 * SYMBOL_MODULES + 2 || (bars.len() - 2) % SYMBOL_MODULES != 0 {
+        return Err(Error::BadFormat(format!("{} modules of bars is not 11 per symbol plus 2",
+                                            bars.len())));
+    }
+
+    let mut symbols = Vec::with_capacity(bars.len() / SYMBOL_MODULES);
+    let mut damaged = Vec::new();
+
+    for (i, chunk) in bars[..bars.len() - 2].chunks(SYMBOL_MODULES).enumerate() {
+        let (distance, candidates) = nearest(chunk_bits(chunk));
+        symbols.push(candidates[0]);
+
+        if distance > 0 {
+            if damaged.len() == options.max_symbol_errors {
+                return Err(Error::BadFormat(format!("unrecognized modules for symbol {}", i)));
+            }
+            damaged.push((i, candidates));
+        }
+    }
+
+    if !bars[bars.len() - 2] {
+        return Err(Error::BadFormat(String::from("missing final bar after stop symbol")));
+    }
+
+    let mut code = Code128Buf::new(symbols);
+
+    if damaged.is_empty() {
+        return Ok(Scan { code, repaired: 0 });
+    }
+
+    let attempts = damaged.iter().try_fold(1usize, |n, d| {
+        n.checked_mul(d.1.len()).filter(|&n| n <= MAX_REPAIR_ATTEMPTS)
+    });
+    if attempts.is_none() {
+        return Err(Error::BadFormat(format!("too many ways to repair {} damaged symbols",
+                                            damaged.len())));
+    }
+
+    // Try every combination of tied candidates, like an odometer
+    let mut choice = vec![0; damaged.len()];
+    loop {
+        if code.checksum() {
+            return Ok(Scan { code, repaired: damaged.len() });
+        }
+
+        let mut d = 0;
+        while d < damaged.len() && choice[d] + 1 == damaged[d].1.len() {
+            choice[d] = 0;
+            d += 1;
+        }
+
+        if d == damaged.len() {
+            return Err(Error::BadFormat(format!("could not repair {} damaged symbols",
+                                                damaged.len())));
+        }
+
+        choice[d] += 1;
+
+        let mut symbols: Vec<Pattern> = code.into_iter().collect();
+        for (&(at, ref candidates), &c) in damaged.iter().zip(&choice) {
+            symbols[at] = candidates[c];
+        }
+        code = Code128Buf::new(symbols);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use barcode::format::{Decode, Encode};
+
+    const ONE: ScanOptions = ScanOptions { max_symbol_errors: 1 };
+    const TWO: ScanOptions = ScanOptions { max_symbol_errors: 2 };
+
+    fn encoded(text: &str) -> Code128Buf<Pattern> {
+        text.encode().unwrap()
+    }
+
+    #[test]
+    fn module_bits_of_start_a() {
+        // Start A is 2 1 1 4 1 2
+        assert_eq!(module_bits(Pattern::C103), 0b11010000100);
+        assert_eq!(module_bits(Pattern::C106), 0b11000111010);
+    }
+
+    #[test]
+    fn scan_undamaged() {
+        let code = encoded("HackFSU 2024");
+        let scan = scan_modules(&code.modules(), &ScanOptions::default()).unwrap();
+
+        assert_eq!(scan, Scan { code, repaired: 0 });
+    }
+
+    #[test]
+    fn scan_repairs_one_corrupted_module() {
+        let code = encoded("PJJ123C");
+        let modules = code.modules();
+
+        // Every module of the data symbols, one at a time
+        for m in 21..21 + 7 * 11 {
+            let mut damaged = modules.clone();
+            damaged[m] = !damaged[m];
+
+            let scan = scan_modules(&damaged, &ONE).unwrap();
+            assert_eq!(scan.code, code, "flipped module {}", m);
+            assert_eq!(scan.repaired, 1);
+            assert_eq!(scan.code.decode(), Ok(String::from("PJJ123C")));
+        }
+    }
+
+    #[test]
+    fn scan_repairs_two_corrupted_modules() {
+        let code = encoded("PJJ123C");
+        let mut modules = code.modules();
+        modules[25] = !modules[25];
+        modules[60] = !modules[60];
+
+        let scan = scan_modules(&modules, &TWO).unwrap();
+        assert_eq!(scan, Scan { code, repaired: 2 });
+
+        assert_eq!(scan_modules(&modules, &ONE),
+                   Err(Error::BadFormat(String::from("unrecognized modules for symbol 4"))));
+    }
+
+    #[test]
+    fn scan_without_recovery_rejects_damage() {
+        let mut modules = encoded("PJJ123C").modules();
+        modules[25] = !modules[25];
+
+        assert_eq!(scan_modules(&modules, &ScanOptions::default()),
+                   Err(Error::BadFormat(String::from("unrecognized modules for symbol 1"))));
+    }
+
+    #[test]
+    fn scan_rejects_repairs_failing_checksum() {
+        let code = encoded("PJJ123C");
+        let mut modules = code.modules();
+
+        // Turn C48 into C42, a valid pattern, so only the checksum notices,
+        // then damage another symbol so a repair is attempted
+        let j: Vec<bool> = modules[43..54].to_vec();
+        modules[21..32].copy_from_slice(&j);
+        modules[60] = !modules[60];
+
+        assert!(scan_modules(&modules, &TWO).is_err());
+    }
+
+    #[test]
+    fn scan_gives_up_on_too_many_combinations() {
+        let code = encoded("HackFSU feather codes");
+        let mut modules = code.modules();
+
+        // All dark, which ties several patterns for nearest
+        let ties = nearest(0x7ff).1.len();
+        assert!(ties > 1);
+
+        let mut symbols = 1;
+        while ties.pow(symbols as u32) <= MAX_REPAIR_ATTEMPTS {
+            symbols += 1;
+        }
+
+        for i in 1..=symbols {
+            let at = 10 + 11 * i;
+            modules[at..at + 11].copy_from_slice(&[true; 11]);
+        }
+
+        let options = ScanOptions { max_symbol_errors: symbols };
+        assert_eq!(scan_modules(&modules, &options),
+                   Err(Error::BadFormat(format!("too many ways to repair {} damaged symbols",
+                                                symbols))));
+    }
+
+    #[test]
+    fn scan_rejects_bad_lengths() {
+        let modules = encoded("PJJ123C").modules();
+
+        assert_eq!(scan_modules(&[false; 20], &ONE),
+                   Err(Error::BadFormat(String::from("no bars found"))));
+        assert!(scan_modules(&modules[..modules.len() - 12], &ONE).is_err());
+    }
+}
@@ -0,0 +1,101 @@
+//  This Source Code Form is subject to the terms of the Mozilla Public
+//  License, v. 2.0. If a copy of the MPL was not distributed with this
+//  file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Quickcheck generators of valid Code 128 barcodes
+//!
+//! Available with the `testing` feature, for property testing readers and
+//! other code that consumes barcodes.
+//!
+//! ```
+//! # #[macro_use] extern crate quickcheck;
+//! # extern crate feather_code;
+//! use feather_code::barcode::code128::testing::ValidCode128;
+//! use feather_code::barcode::code128::Code128;
+//! use feather_code::barcode::format::Decode;
+//!
+//! # fn main() {
+//! fn decodes(code: ValidCode128) -> bool {
+//!     Decode::<String>::decode(&Code128::new(&code.0)).is_ok()
+//! }
+//!
+//! quickcheck::quickcheck(decodes as fn(ValidCode128) -> bool);
+//! # }
+//! ```
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use quickcheck::{Arbitrary, Gen};
+
+use super::{Code128Builder, Encoding};
+
+/// Symbol values of a structurally valid barcode
+///
+/// The payload is a few segments of ASCII text, some left to the encoder
+/// and some pinned to a symbology or written with a shift, so generated
+/// barcodes use every start symbol, switches, and shifts.  The check
+/// symbol is always right and the stop symbol always last.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidCode128(pub Vec<u8>);
+
+/// Up to `max` bytes picked by `pick` from random ones, at least one
+fn bytes<F: Fn(u8) -> u8>(g: &mut Gen, max: usize, pick: F) -> String {
+    let len = usize::arbitrary(g) % max + 1;
+
+    (0..len).map(|_| pick(u8::arbitrary(g)) as char).collect()
+}
+
+impl Arbitrary for ValidCode128 {
+    fn arbitrary(g: &mut Gen) -> ValidCode128 {
+        let segments = usize::arbitrary(g) % 4 + 1;
+        let mut builder = Code128Builder::new();
+
+        for _ in 0..segments {
+            builder = match u8::arbitrary(g) % 6 {
+                0 => builder.text(&bytes(g, 8, |b| b % 95 + 32)),
+                1 => builder.push_a(&bytes(g, 8, |b| b % 96)),
+                2 => builder.push_b(&bytes(g, 8, |b| b % 96 + 32)),
+                3 => {
+                    let pairs = usize::arbitrary(g) % 4 + 1;
+                    let digits: String = (0..2 * pairs)
+                        .map(|_| (u8::arbitrary(g) % 10 + b'0') as char)
+                        .collect();
+                    builder.push_c_digits(&digits)
+                }
+                4 => builder.shift_a((u8::arbitrary(g) % 32) as char),
+                _ => builder.shift_b((u8::arbitrary(g) % 32 + 96) as char),
+            };
+        }
+
+        let code = builder.build().unwrap_or_else(|e| panic!("generated barcode failed: {}", e));
+
+        ValidCode128(code.symbols().iter().map(Encoding::as_u8).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use barcode::code128::{Code128, Symbology};
+
+    #[test]
+    fn generates_every_start_and_shifts() {
+        let mut g = Gen::new(20);
+        let mut starts = Vec::new();
+        let mut shifts = 0;
+
+        for _ in 0..500 {
+            let code = ValidCode128::arbitrary(&mut g);
+            let (_, info) = Code128::new(&code.0).decode_with_info().unwrap();
+
+            starts.push(info.start_symbology);
+            shifts += info.shifts;
+        }
+
+        for s in &[Symbology::A, Symbology::B, Symbology::C] {
+            assert!(starts.contains(s), "no barcode starts in {:?}", s);
+        }
+        assert!(shifts > 0);
+    }
+}
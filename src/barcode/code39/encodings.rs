@@ -0,0 +1,196 @@
+//  This Source Code Form is subject to the terms of the Mozilla Public
+//  License, v. 2.0. If a copy of the MPL was not distributed with this
+//  file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! The symbols of a [`Code39`](../struct.Code39.html)
+//!
+//! Unlike Code 128, where a symbol is six bars and spaces of one to four
+//! modules each, a Code 39 symbol is nine elements, each either narrow or
+//! wide, and three of them wide.
+
+use core::fmt;
+
+use barcode::format::Error;
+
+/// The 44 symbols of Code 39: the 43 characters, valued as in the check
+/// character, then the `*` delimiter
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Code39Symbol {
+    D0,
+    D1,
+    D2,
+    D3,
+    D4,
+    D5,
+    D6,
+    D7,
+    D8,
+    D9,
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    G,
+    H,
+    I,
+    J,
+    K,
+    L,
+    M,
+    N,
+    O,
+    P,
+    Q,
+    R,
+    S,
+    T,
+    U,
+    V,
+    W,
+    X,
+    Y,
+    Z,
+    Dash,
+    Dot,
+    Space,
+    Dollar,
+    Slash,
+    Plus,
+    Percent,
+    /// The start and stop delimiter, written `*`
+    Star,
+}
+
+/// Every symbol, indexed by value
+static SYMBOLS: [Code39Symbol; 44] = [
+    Code39Symbol::D0, Code39Symbol::D1, Code39Symbol::D2, Code39Symbol::D3, Code39Symbol::D4,
+    Code39Symbol::D5, Code39Symbol::D6, Code39Symbol::D7, Code39Symbol::D8, Code39Symbol::D9,
+    Code39Symbol::A, Code39Symbol::B, Code39Symbol::C, Code39Symbol::D, Code39Symbol::E,
+    Code39Symbol::F, Code39Symbol::G, Code39Symbol::H, Code39Symbol::I, Code39Symbol::J,
+    Code39Symbol::K, Code39Symbol::L, Code39Symbol::M, Code39Symbol::N, Code39Symbol::O,
+    Code39Symbol::P, Code39Symbol::Q, Code39Symbol::R, Code39Symbol::S, Code39Symbol::T,
+    Code39Symbol::U, Code39Symbol::V, Code39Symbol::W, Code39Symbol::X, Code39Symbol::Y,
+    Code39Symbol::Z, Code39Symbol::Dash, Code39Symbol::Dot, Code39Symbol::Space,
+    Code39Symbol::Dollar, Code39Symbol::Slash, Code39Symbol::Plus, Code39Symbol::Percent,
+    Code39Symbol::Star,
+];
+
+/// Character of each symbol, indexed by value
+static CHARS: &[u8; 44] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ-. $/+%*";
+
+/// Wide elements of each symbol, indexed by value: the first bar is the
+/// highest of nine bits, a set bit is wide
+static ELEMENTS: [u16; 44] = [
+    0b000110100, 0b100100001, 0b001100001, 0b101100000, 0b000110001,
+    0b100110000, 0b001110000, 0b000100101, 0b100100100, 0b001100100,
+    0b100001001, 0b001001001, 0b101001000, 0b000011001, 0b100011000,
+    0b001011000, 0b000001101, 0b100001100, 0b001001100, 0b000011100,
+    0b100000011, 0b001000011, 0b101000010, 0b000010011, 0b100010010,
+    0b001010010, 0b000000111, 0b100000110, 0b001000110, 0b000010110,
+    0b110000001, 0b011000001, 0b111000000, 0b010010001, 0b110010000,
+    0b011010000, 0b010000101, 0b110000100, 0b011000100, 0b010101000,
+    0b010100010, 0b010001010, 0b000101010, 0b010010100,
+];
+
+impl Code39Symbol {
+    /// Number of symbols, the 43 characters and `*`
+    pub const fn count() -> usize {
+        SYMBOLS.len()
+    }
+
+    /// Numeric value of the symbol, its weight in the check character
+    pub const fn as_u8(self) -> u8 {
+        self as u8
+    }
+
+    /// Convert a value to its symbol, failing with `BadFormat` above 43
+    pub fn try_from_u8(u: u8) -> Result<Code39Symbol, Error> {
+        match SYMBOLS.get(u as usize) {
+            Some(&s) => Ok(s),
+            None => Err(Error::BadFormat(format!("Code 39 symbol value {} out of range", u))),
+        }
+    }
+
+    /// The symbol for a character, if Code 39 has one
+    ///
+    /// ```
+    /// use feather_code::barcode::code39::Code39Symbol;
+    ///
+    /// assert_eq!(Code39Symbol::from_char('Q'), Some(Code39Symbol::Q));
+    /// assert_eq!(Code39Symbol::from_char('q'), None);
+    /// ```
+    pub fn from_char(c: char) -> Option<Code39Symbol> {
+        CHARS.iter().position(|&b| b as char == c).map(|u| SYMBOLS[u])
+    }
+
+    /// The character the symbol stands for
+    pub fn to_char(self) -> char {
+        CHARS[self as usize] as char
+    }
+
+    /// Whether each element is wide, bars and spaces alternating from the
+    /// first bar
+    ///
+    /// ```
+    /// use feather_code::barcode::code39::Code39Symbol;
+    ///
+    /// let wide = Code39Symbol::Star.elements();
+    /// assert_eq!(wide, [false, true, false, false, true, false, true, false, false]);
+    /// ```
+    pub fn elements(self) -> [bool; 9] {
+        let bits = ELEMENTS[self as usize];
+        let mut wide = [false; 9];
+
+        for (i, w) in wide.iter_mut().enumerate() {
+            *w = bits >> (8 - i) & 1 == 1;
+        }
+
+        wide
+    }
+
+    /// Find the symbol drawn with the given wide elements
+    pub fn from_elements(wide: [bool; 9]) -> Option<Code39Symbol> {
+        let bits = wide.iter().fold(0, |bits, &w| bits << 1 | w as u16);
+
+        ELEMENTS.iter().position(|&e| e == bits).map(|u| SYMBOLS[u])
+    }
+}
+
+impl fmt::Display for Code39Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_char())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_match_table() {
+        for (u, &s) in SYMBOLS.iter().enumerate() {
+            assert_eq!(s.as_u8() as usize, u);
+            assert_eq!(Code39Symbol::try_from_u8(u as u8), Ok(s));
+        }
+        assert!(Code39Symbol::try_from_u8(44).is_err());
+    }
+
+    #[test]
+    fn three_of_nine_elements_are_wide() {
+        for &s in SYMBOLS.iter() {
+            assert_eq!(s.elements().iter().filter(|&&w| w).count(), 3, "{}", s);
+            assert_eq!(Code39Symbol::from_elements(s.elements()), Some(s));
+        }
+    }
+
+    #[test]
+    fn chars_round_trip() {
+        for &s in SYMBOLS.iter() {
+            assert_eq!(Code39Symbol::from_char(s.to_char()), Some(s));
+        }
+        assert_eq!(Code39Symbol::Space.to_char(), ' ');
+        assert_eq!(Code39Symbol::from_char('#'), None);
+    }
+}
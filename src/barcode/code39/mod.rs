@@ -0,0 +1,304 @@
+//  This Source Code Form is subject to the terms of the Mozilla Public
+//  License, v. 2.0. If a copy of the MPL was not distributed with this
+//  file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Code 39 barcodes
+//!
+//! A Code 39 barcode, also called 3 of 9, is a sequence of
+//! [`Code39Symbol`](encodings/enum.Code39Symbol.html)s between two `*`
+//! delimiters.  Its 43 characters are digits, uppercase letters, and
+//! `- . $ / + %` and space.  A modulo 43 check character before the
+//! closing `*` is optional, so the barcode must say whether it has one.
+//!
+//! ```
+//! use feather_code::barcode::code39::{Code39, Code39Symbol};
+//! use feather_code::barcode::format::{Decode, Format};
+//!
+//! let symbols: Vec<Code39Symbol> = "*PJJ-123C*".chars()
+//!     .map(|c| Code39Symbol::from_char(c).unwrap())
+//!     .collect();
+//! let code = Code39::new(&symbols);
+//!
+//! assert!(code.checksum());
+//! assert_eq!(code.decode(), Ok(String::from("PJJ-123C")));
+//! ```
+
+pub mod encodings;
+
+pub use self::encodings::Code39Symbol;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use barcode::format::{Decode, Error, Format, Result, IN_DATA};
+use barcode::render::{self, QUIET_ZONE};
+
+/// Modules of a narrow bar or space
+const NARROW: usize = 1;
+
+/// Modules of a wide bar or space, three times a narrow one
+const WIDE: usize = 3;
+
+/// The check character for a barcode's data: the sum of the symbol values
+/// modulo 43
+///
+/// ```
+/// use feather_code::barcode::code39::{check_symbol, Code39Symbol};
+///
+/// let data = [Code39Symbol::C, Code39Symbol::O, Code39Symbol::D, Code39Symbol::E];
+/// assert_eq!(check_symbol(&data), Code39Symbol::K);
+/// ```
+pub fn check_symbol(data: &[Code39Symbol]) -> Code39Symbol {
+    let sum: usize = data.iter().map(|s| s.as_u8() as usize).sum();
+
+    Code39Symbol::try_from_u8((sum % 43) as u8).unwrap_or(Code39Symbol::D0)
+}
+
+/// A Code 39 barcode borrowing its symbols, delimiters included
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Code39<'a> {
+    symbols: &'a [Code39Symbol],
+    check: bool,
+}
+
+impl<'a> Code39<'a> {
+    /// Wrap symbols without a check character
+    pub fn new(symbols: &'a [Code39Symbol]) -> Code39<'a> {
+        Code39 { symbols, check: false }
+    }
+
+    /// Wrap symbols whose last symbol before the closing `*` is the check
+    /// character
+    pub fn with_check(symbols: &'a [Code39Symbol]) -> Code39<'a> {
+        Code39 { symbols, check: true }
+    }
+
+    /// The symbols, as given
+    pub fn symbols(&self) -> &'a [Code39Symbol] {
+        self.symbols
+    }
+
+    /// Whether the barcode has a check character
+    pub fn has_check(&self) -> bool {
+        self.check
+    }
+
+    /// Every module of the printed barcode, `true` for dark, with wide
+    /// elements three modules and a narrow space between symbols
+    ///
+    /// Runs from the leading quiet zone to the trailing one, as for
+    /// `Code128::modules`.
+    pub fn modules(&self) -> Vec<bool> {
+        let mut modules = vec![false; QUIET_ZONE];
+
+        for (i, s) in self.symbols.iter().enumerate() {
+            if i > 0 {
+                modules.push(false);
+            }
+
+            for (e, &wide) in s.elements().iter().enumerate() {
+                let width = if wide { WIDE } else { NARROW };
+                modules.extend((0..width).map(|_| e % 2 == 0));
+            }
+        }
+
+        modules.extend_from_slice(&[false; QUIET_ZONE]);
+        modules
+    }
+
+    /// Split out the data and the check character, failing unless the
+    /// barcode is delimited and has no `*` among its data
+    fn parts(&self) -> Result<(&'a [Code39Symbol], Option<Code39Symbol>)> {
+        let n = self.symbols.len();
+        let least = if self.check { 3 } else { 2 };
+
+        if n < least || self.symbols[0] != Code39Symbol::Star ||
+           self.symbols[n - 1] != Code39Symbol::Star {
+            return Err(Error::BadFormat(String::from("missing start or stop delimiter")));
+        }
+
+        let inner = &self.symbols[1..n - 1];
+        if let Some(i) = inner.iter().position(|&s| s == Code39Symbol::Star) {
+            return Err(Error::BadSymbolAt {
+                index: i,
+                value: Code39Symbol::Star.as_u8(),
+                context: IN_DATA,
+            });
+        }
+
+        if self.check {
+            Ok((&inner[..inner.len() - 1], inner.last().cloned()))
+        } else {
+            Ok((inner, None))
+        }
+    }
+}
+
+impl<'a> Format for Code39<'a> {
+    /// Whether the barcode is delimited and, if it has a check character,
+    /// whether that matches
+    fn checksum(&self) -> bool {
+        match self.parts() {
+            Ok((data, Some(check))) => check_symbol(data) == check,
+            Ok((_, None)) => true,
+            Err(_) => false,
+        }
+    }
+
+    fn has_checksum(&self) -> bool {
+        self.check
+    }
+}
+
+/// Read the symbols from a row of modules, `true` for dark
+///
+/// Light modules on either side are skipped.  Every bar and space must be
+/// one or three modules, nine of them to a symbol with a one module space
+/// between symbols.  Fails with `BadFormat` otherwise; the symbols are not
+/// validated, and the modules can't say whether there is a check
+/// character, so wrap them with `Code39::new` or `Code39::with_check`.
+///
+/// ```
+/// use feather_code::barcode::code39::{scan_modules, Code39, Code39Symbol};
+///
+/// let symbols: Vec<Code39Symbol> = "*PJJ-123C*".chars()
+///     .map(|c| Code39Symbol::from_char(c).unwrap())
+///     .collect();
+///
+/// assert_eq!(scan_modules(&Code39::new(&symbols).modules()), Ok(symbols));
+/// ```
+pub fn scan_modules(modules: &[bool]) -> Result<Vec<Code39Symbol>> {
+    let bars = render::bars(modules)?;
+    let mut runs = Vec::new();
+    let mut rest = bars;
+
+    while let Some(&dark) = rest.first() {
+        let run = rest.iter().take_while(|&&m| m == dark).count();
+        runs.push(run);
+        rest = &rest[run..];
+    }
+
+    if runs.len() % 10 != 9 {
+        return Err(Error::BadFormat(format!("{} bars and spaces is not 9 per symbol", runs.len())));
+    }
+
+    runs.chunks(10)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let unrecognized = || {
+                Error::BadFormat(format!("unrecognized modules for symbol {}", i))
+            };
+
+            if chunk.get(9).is_some_and(|&gap| gap != NARROW) {
+                return Err(unrecognized());
+            }
+
+            let mut wide = [false; 9];
+            for (w, &run) in wide.iter_mut().zip(chunk) {
+                *w = match run {
+                    NARROW => false,
+                    WIDE => true,
+                    _ => return Err(unrecognized()),
+                };
+            }
+
+            Code39Symbol::from_elements(wide).ok_or_else(unrecognized)
+        })
+        .collect()
+}
+
+impl<'a> Decode<String> for Code39<'a> {
+    /// The characters between the delimiters, without any check character
+    fn decode(&self) -> Result<String> {
+        let (data, check) = self.parts()?;
+
+        if check.is_some_and(|check| check_symbol(data) != check) {
+            return Err(Error::BadFormat(String::from("check character does not match")));
+        }
+
+        Ok(data.iter().map(|s| s.to_char()).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn symbols(text: &str) -> Vec<Code39Symbol> {
+        text.chars().map(|c| Code39Symbol::from_char(c).unwrap()).collect()
+    }
+
+    #[test]
+    fn decode_without_check() {
+        let symbols = symbols("*HACK FSU $1.50*");
+        let code = Code39::new(&symbols);
+
+        assert!(code.checksum());
+        assert_eq!(code.decode(), Ok(String::from("HACK FSU $1.50")));
+    }
+
+    #[test]
+    fn decode_with_check() {
+        // Wikipedia's example: "CODE39" with check character W
+        let symbols = symbols("*CODE39W*");
+        let code = Code39::with_check(&symbols);
+
+        assert!(code.checksum());
+        assert_eq!(code.decode(), Ok(String::from("CODE39")));
+        assert_eq!(Code39::new(&symbols).decode(), Ok(String::from("CODE39W")));
+    }
+
+    #[test]
+    fn rejects_wrong_check() {
+        let symbols = symbols("*CODE39X*");
+        let code = Code39::with_check(&symbols);
+
+        assert!(!code.checksum());
+        assert_eq!(code.decode(),
+                   Err(Error::BadFormat(String::from("check character does not match"))));
+    }
+
+    #[test]
+    fn rejects_bad_delimiters() {
+        for text in &["PJJ*", "*PJJ", "*", "*PJ*J*"] {
+            let symbols = symbols(text);
+            let code = Code39::new(&symbols);
+
+            assert!(!code.checksum(), "{}", text);
+            assert!(code.decode().is_err(), "{}", text);
+        }
+
+        let symbols = symbols("**");
+        assert_eq!(Code39::new(&symbols).decode(), Ok(String::new()));
+        assert!(Code39::with_check(&symbols).decode().is_err());
+    }
+
+    #[test]
+    fn modules_round_trip() {
+        let symbols = symbols("*CODE39W*");
+        let modules = Code39::with_check(&symbols).modules();
+
+        // Fifteen modules a symbol, with a space between each
+        assert_eq!(modules.len(), 2 * QUIET_ZONE + 9 * 15 + 8);
+        assert_eq!(modules[QUIET_ZONE..QUIET_ZONE + 15],
+                   [true, false, false, false, true, false, true, true, true, false, true, true,
+                    true, false, true]);
+
+        let read = scan_modules(&modules).unwrap();
+        assert_eq!(read, symbols);
+        assert_eq!(Code39::with_check(&read).decode(), Ok(String::from("CODE39")));
+    }
+
+    #[test]
+    fn scan_rejects_bad_widths() {
+        let mut modules = Code39::new(&symbols("*A*")).modules();
+
+        assert_eq!(scan_modules(&modules[..modules.len() - QUIET_ZONE - 1]),
+                   Err(Error::BadFormat(String::from("27 bars and spaces is not 9 per symbol"))));
+
+        // Widen the space after the first symbol
+        modules.insert(QUIET_ZONE + 15, false);
+        assert_eq!(scan_modules(&modules),
+                   Err(Error::BadFormat(String::from("unrecognized modules for symbol 0"))));
+    }
+}
@@ -0,0 +1,220 @@
+//  This Source Code Form is subject to the terms of the Mozilla Public
+//  License, v. 2.0. If a copy of the MPL was not distributed with this
+//  file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! The symbols of a [`Code93`](../struct.Code93.html)
+//!
+//! A Code 93 symbol is 9 modules: three bars and three spaces of one to
+//! four modules each, like a narrower Code 128 symbol.  Besides the 43
+//! characters it shares with Code 39, it has four shift symbols for full
+//! ASCII and the `*` delimiter.
+
+use core::fmt;
+
+use barcode::format::Error;
+
+/// The 48 symbols of Code 93: the 43 characters, the four shifts, all
+/// valued as in the check characters, then the `*` delimiter
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Code93Symbol {
+    D0,
+    D1,
+    D2,
+    D3,
+    D4,
+    D5,
+    D6,
+    D7,
+    D8,
+    D9,
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    G,
+    H,
+    I,
+    J,
+    K,
+    L,
+    M,
+    N,
+    O,
+    P,
+    Q,
+    R,
+    S,
+    T,
+    U,
+    V,
+    W,
+    X,
+    Y,
+    Z,
+    Dash,
+    Dot,
+    Space,
+    Dollar,
+    Slash,
+    Plus,
+    Percent,
+    /// Shift to control characters, written `($)`
+    ShiftDollar,
+    /// Shift to punctuation and control characters, written `(%)`
+    ShiftPercent,
+    /// Shift to punctuation, written `(/)`
+    ShiftSlash,
+    /// Shift to lowercase letters, written `(+)`
+    ShiftPlus,
+    /// The start and stop delimiter, written `*`
+    Star,
+}
+
+/// Every symbol, indexed by value
+static SYMBOLS: [Code93Symbol; 48] = [
+    Code93Symbol::D0, Code93Symbol::D1, Code93Symbol::D2, Code93Symbol::D3, Code93Symbol::D4,
+    Code93Symbol::D5, Code93Symbol::D6, Code93Symbol::D7, Code93Symbol::D8, Code93Symbol::D9,
+    Code93Symbol::A, Code93Symbol::B, Code93Symbol::C, Code93Symbol::D, Code93Symbol::E,
+    Code93Symbol::F, Code93Symbol::G, Code93Symbol::H, Code93Symbol::I, Code93Symbol::J,
+    Code93Symbol::K, Code93Symbol::L, Code93Symbol::M, Code93Symbol::N, Code93Symbol::O,
+    Code93Symbol::P, Code93Symbol::Q, Code93Symbol::R, Code93Symbol::S, Code93Symbol::T,
+    Code93Symbol::U, Code93Symbol::V, Code93Symbol::W, Code93Symbol::X, Code93Symbol::Y,
+    Code93Symbol::Z, Code93Symbol::Dash, Code93Symbol::Dot, Code93Symbol::Space,
+    Code93Symbol::Dollar, Code93Symbol::Slash, Code93Symbol::Plus, Code93Symbol::Percent,
+    Code93Symbol::ShiftDollar, Code93Symbol::ShiftPercent, Code93Symbol::ShiftSlash,
+    Code93Symbol::ShiftPlus, Code93Symbol::Star,
+];
+
+/// Character of each of the first 43 symbols, indexed by value
+static CHARS: &[u8; 43] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ-. $/+%";
+
+/// Modules of each symbol, indexed by value: the first module is the
+/// highest of nine bits, a set bit dark
+static MODULES: [u16; 48] = [
+    0b100010100, 0b101001000, 0b101000100, 0b101000010, 0b100101000,
+    0b100100100, 0b100100010, 0b101010000, 0b100010010, 0b100001010,
+    0b110101000, 0b110100100, 0b110100010, 0b110010100, 0b110010010,
+    0b110001010, 0b101101000, 0b101100100, 0b101100010, 0b100110100,
+    0b100011010, 0b101011000, 0b101001100, 0b101000110, 0b100101100,
+    0b100010110, 0b110110100, 0b110110010, 0b110101100, 0b110100110,
+    0b110010110, 0b110011010, 0b101101100, 0b101100110, 0b100110110,
+    0b100111010, 0b100101110, 0b111010100, 0b111010010, 0b111001010,
+    0b101101110, 0b101110110, 0b110101110, 0b100100110, 0b111011010,
+    0b111010110, 0b100110010, 0b101011110,
+];
+
+impl Code93Symbol {
+    /// Number of symbols, the 47 valued ones and `*`
+    pub const fn count() -> usize {
+        SYMBOLS.len()
+    }
+
+    /// Numeric value of the symbol, its weight in the check characters
+    pub const fn as_u8(self) -> u8 {
+        self as u8
+    }
+
+    /// Convert a value to its symbol, failing with `BadFormat` above 47
+    pub fn try_from_u8(u: u8) -> Result<Code93Symbol, Error> {
+        match SYMBOLS.get(u as usize) {
+            Some(&s) => Ok(s),
+            None => Err(Error::BadFormat(format!("Code 93 symbol value {} out of range", u))),
+        }
+    }
+
+    /// The symbol written directly for a character, if there is one
+    ///
+    /// Other ASCII characters are written as a shift and a letter.
+    ///
+    /// ```
+    /// use feather_code::barcode::code93::Code93Symbol;
+    ///
+    /// assert_eq!(Code93Symbol::from_char('Q'), Some(Code93Symbol::Q));
+    /// assert_eq!(Code93Symbol::from_char('q'), None);
+    /// ```
+    pub fn from_char(c: char) -> Option<Code93Symbol> {
+        CHARS.iter().position(|&b| b as char == c).map(|u| SYMBOLS[u])
+    }
+
+    /// The character the symbol stands for, if it isn't a shift or `*`
+    pub fn to_char(self) -> Option<char> {
+        CHARS.get(self as usize).map(|&b| b as char)
+    }
+
+    /// Whether each module is dark, from the first bar
+    ///
+    /// ```
+    /// use feather_code::barcode::code93::Code93Symbol;
+    ///
+    /// let modules = Code93Symbol::Star.modules();
+    /// assert_eq!(modules, [true, false, true, false, true, true, true, true, false]);
+    /// ```
+    pub fn modules(self) -> [bool; 9] {
+        let bits = MODULES[self as usize];
+        let mut dark = [false; 9];
+
+        for (i, d) in dark.iter_mut().enumerate() {
+            *d = bits >> (8 - i) & 1 == 1;
+        }
+
+        dark
+    }
+
+    /// Find the symbol drawn with the given modules
+    pub fn from_modules(dark: [bool; 9]) -> Option<Code93Symbol> {
+        let bits = dark.iter().fold(0, |bits, &d| bits << 1 | d as u16);
+
+        MODULES.iter().position(|&m| m == bits).map(|u| SYMBOLS[u])
+    }
+}
+
+impl fmt::Display for Code93Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Code93Symbol::ShiftDollar => write!(f, "($)"),
+            Code93Symbol::ShiftPercent => write!(f, "(%)"),
+            Code93Symbol::ShiftSlash => write!(f, "(/)"),
+            Code93Symbol::ShiftPlus => write!(f, "(+)"),
+            Code93Symbol::Star => write!(f, "*"),
+            s => write!(f, "{}", CHARS[s as usize] as char),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_match_table() {
+        for (u, &s) in SYMBOLS.iter().enumerate() {
+            assert_eq!(s.as_u8() as usize, u);
+            assert_eq!(Code93Symbol::try_from_u8(u as u8), Ok(s));
+        }
+        assert!(Code93Symbol::try_from_u8(48).is_err());
+    }
+
+    #[test]
+    fn three_bars_and_spaces_in_nine_modules() {
+        for &s in SYMBOLS.iter() {
+            let modules = s.modules();
+            let runs = 1 + modules.windows(2).filter(|w| w[0] != w[1]).count();
+
+            assert!(modules[0] && !modules[8], "{}", s);
+            assert_eq!(runs, 6, "{}", s);
+            assert_eq!(Code93Symbol::from_modules(modules), Some(s));
+        }
+    }
+
+    #[test]
+    fn chars_round_trip() {
+        for &s in SYMBOLS[..43].iter() {
+            assert_eq!(s.to_char().and_then(Code93Symbol::from_char), Some(s));
+        }
+        assert_eq!(Code93Symbol::ShiftPlus.to_char(), None);
+        assert_eq!(Code93Symbol::Star.to_char(), None);
+        assert_eq!(Code93Symbol::from_char('*'), None);
+    }
+}
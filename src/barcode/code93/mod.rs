@@ -0,0 +1,467 @@
+//  This Source Code Form is subject to the terms of the Mozilla Public
+//  License, v. 2.0. If a copy of the MPL was not distributed with this
+//  file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Code 93 barcodes
+//!
+//! A Code 93 barcode is a sequence of
+//! [`Code93Symbol`](encodings/enum.Code93Symbol.html)s between two `*`
+//! delimiters.  It has the 43 characters of Code 39 and reaches the rest of
+//! ASCII through four shift symbols, each pairing with a letter.  Two check
+//! characters before the closing `*` are mandatory: C, weighting the data
+//! from the right by 1 to 20, and K, weighting the data and C by 1 to 15,
+//! both modulo 47.
+//!
+//! ```
+//! use feather_code::barcode::code93::Code93Buf;
+//! use feather_code::barcode::format::{Decode, Encode, Format};
+//!
+//! let code: Code93Buf = "TEST93".encode().unwrap();
+//!
+//! assert_eq!(code.to_string(), "*TEST93+6*");
+//! assert!(code.checksum());
+//! assert_eq!(code.decode(), Ok(String::from("TEST93")));
+//! ```
+
+pub mod encodings;
+
+pub use self::encodings::Code93Symbol;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use barcode::format::{Decode, Encode, Error, Format, Result, AFTER_SHIFT, IN_DATA};
+use barcode::render::{self, QUIET_ZONE};
+
+/// The check character weighting `symbols` from the right by `1` up to
+/// `max`, then `1` again, modulo 47
+fn weighted_check(symbols: &[Code93Symbol], max: usize) -> Code93Symbol {
+    let sum: usize = symbols.iter()
+        .rev()
+        .zip((1..=max).cycle())
+        .map(|(s, w)| w * s.as_u8() as usize)
+        .sum();
+
+    Code93Symbol::try_from_u8((sum % 47) as u8).unwrap_or(Code93Symbol::D0)
+}
+
+/// The C and K check characters for a barcode's data
+///
+/// ```
+/// use feather_code::barcode::code93::{check_symbols, Code93Symbol};
+///
+/// let data = [Code93Symbol::T, Code93Symbol::E, Code93Symbol::S, Code93Symbol::T,
+///             Code93Symbol::D9, Code93Symbol::D3];
+/// assert_eq!(check_symbols(&data), (Code93Symbol::Plus, Code93Symbol::D6));
+/// ```
+pub fn check_symbols(data: &[Code93Symbol]) -> (Code93Symbol, Code93Symbol) {
+    let c = weighted_check(data, 20);
+    let mut with_c = data.to_vec();
+    with_c.push(c);
+
+    (c, weighted_check(&with_c, 15))
+}
+
+/// The symbols writing an ASCII character: a shift and a letter, or just
+/// the character's own symbol
+fn ascii_symbols(b: u8) -> (Option<Code93Symbol>, Code93Symbol) {
+    if let Some(s) = Code93Symbol::from_char(b as char) {
+        return (None, s);
+    }
+
+    let (shift, letter) = match b {
+        0 => (Code93Symbol::ShiftPercent, b'U'),
+        1..=26 => (Code93Symbol::ShiftDollar, b'A' + b - 1),
+        27..=31 => (Code93Symbol::ShiftPercent, b'A' + b - 27),
+        33..=58 => (Code93Symbol::ShiftSlash, b'A' + b - 33),
+        59..=63 => (Code93Symbol::ShiftPercent, b'F' + b - 59),
+        64 => (Code93Symbol::ShiftPercent, b'V'),
+        91..=95 => (Code93Symbol::ShiftPercent, b'K' + b - 91),
+        96 => (Code93Symbol::ShiftPercent, b'W'),
+        97..=122 => (Code93Symbol::ShiftPlus, b'A' + b - 97),
+        _ => (Code93Symbol::ShiftPercent, b'P' + b - 123),
+    };
+
+    (Some(shift), Code93Symbol::from_char(letter as char).unwrap_or(Code93Symbol::A))
+}
+
+/// The ASCII character a shift and the symbol after it write, if any
+fn shifted_char(shift: Code93Symbol, s: Code93Symbol) -> Option<char> {
+    let letter = match s.to_char() {
+        Some(c @ 'A'..='Z') => c as u8 - b'A',
+        _ => return None,
+    };
+
+    let b = match shift {
+        Code93Symbol::ShiftDollar => 1 + letter,
+        Code93Symbol::ShiftSlash => 33 + letter,
+        Code93Symbol::ShiftPlus => 97 + letter,
+        _ => match letter {
+            0..=4 => 27 + letter,
+            5..=9 => 59 + letter - 5,
+            10..=14 => 91 + letter - 10,
+            15..=19 => 123 + letter - 15,
+            20 => 0,
+            21 => 64,
+            22 => 96,
+            _ => return None,
+        },
+    };
+
+    Some(b as char)
+}
+
+/// A Code 93 barcode borrowing its symbols, delimiters and check
+/// characters included
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Code93<'a>(&'a [Code93Symbol]);
+
+impl<'a> Code93<'a> {
+    /// Wrap symbols ending in the C and K check characters and the closing
+    /// `*`
+    pub fn new(symbols: &'a [Code93Symbol]) -> Code93<'a> {
+        Code93(symbols)
+    }
+
+    /// The symbols, as given
+    pub fn symbols(&self) -> &'a [Code93Symbol] {
+        self.0
+    }
+
+    /// Split out the data and the two check characters, failing unless the
+    /// barcode is delimited and has no `*` among its data
+    fn parts(&self) -> Result<(&'a [Code93Symbol], (Code93Symbol, Code93Symbol))> {
+        let n = self.0.len();
+
+        if n < 4 || self.0[0] != Code93Symbol::Star || self.0[n - 1] != Code93Symbol::Star {
+            return Err(Error::BadFormat(String::from("missing start or stop delimiter")));
+        }
+
+        let inner = &self.0[1..n - 1];
+        if let Some(i) = inner.iter().position(|&s| s == Code93Symbol::Star) {
+            return Err(Error::BadSymbolAt {
+                index: i,
+                value: Code93Symbol::Star.as_u8(),
+                context: IN_DATA,
+            });
+        }
+
+        let k = inner.len() - 2;
+        Ok((&inner[..k], (inner[k], inner[k + 1])))
+    }
+
+    /// Every module of the printed barcode, `true` for dark
+    ///
+    /// Runs from the leading quiet zone to the trailing one, as for
+    /// `Code128::modules`, and includes the one module bar that ends the
+    /// closing `*`.
+    pub fn modules(&self) -> Vec<bool> {
+        let mut modules = vec![false; QUIET_ZONE];
+
+        for s in self.0 {
+            modules.extend_from_slice(&s.modules());
+        }
+
+        modules.push(true);
+        modules.extend_from_slice(&[false; QUIET_ZONE]);
+        modules
+    }
+}
+
+impl<'a> Format for Code93<'a> {
+    /// Whether the barcode is delimited and both check characters match
+    fn checksum(&self) -> bool {
+        match self.parts() {
+            Ok((data, checks)) => check_symbols(data) == checks,
+            Err(_) => false,
+        }
+    }
+}
+
+impl<'a> Decode<String> for Code93<'a> {
+    /// The ASCII text between the delimiters, shift pairs read as the
+    /// characters they stand for
+    fn decode(&self) -> Result<String> {
+        let (data, checks) = self.parts()?;
+
+        if check_symbols(data) != checks {
+            return Err(Error::BadFormat(String::from("check characters do not match")));
+        }
+
+        let mut text = String::with_capacity(data.len());
+        let mut symbols = data.iter().cloned().enumerate();
+
+        while let Some((_, s)) = symbols.next() {
+            if let Some(c) = s.to_char() {
+                text.push(c);
+                continue;
+            }
+
+            match symbols.next() {
+                Some((index, next)) => match shifted_char(s, next) {
+                    Some(c) => text.push(c),
+                    None => {
+                        return Err(Error::BadSymbolAt {
+                            index,
+                            value: next.as_u8(),
+                            context: AFTER_SHIFT,
+                        });
+                    }
+                },
+                None => return Err(Error::BadFormat(format!("{} with nothing to shift", s))),
+            }
+        }
+
+        Ok(text)
+    }
+}
+
+impl<'a> fmt::Display for Code93<'a> {
+    /// Every symbol, delimiters and check characters included, with shifts
+    /// written `($)`, `(%)`, `(/)`, and `(+)`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for s in self.0 {
+            write!(f, "{}", s)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// An owned Code 93 barcode
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Code93Buf(Vec<Code93Symbol>);
+
+impl Code93Buf {
+    /// Borrow as a `Code93`
+    pub fn as_code93(&self) -> Code93<'_> {
+        Code93(&self.0)
+    }
+
+    /// The symbols, delimiters and check characters included
+    pub fn symbols(&self) -> &[Code93Symbol] {
+        &self.0
+    }
+
+    /// Every module of the printed barcode, quiet zones included
+    pub fn modules(&self) -> Vec<bool> {
+        self.as_code93().modules()
+    }
+}
+
+impl Format for Code93Buf {
+    fn checksum(&self) -> bool {
+        self.as_code93().checksum()
+    }
+}
+
+impl<T> Decode<T> for Code93Buf
+    where for<'a> Code93<'a>: Decode<T>
+{
+    fn decode(&self) -> Result<T> {
+        self.as_code93().decode()
+    }
+}
+
+impl fmt::Display for Code93Buf {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.as_code93().fmt(f)
+    }
+}
+
+/// Read the symbols from a row of modules, `true` for dark
+///
+/// Light modules on either side are skipped, and the rest must be 9
+/// modules per symbol plus the final bar.  Fails with `BadFormat` if a
+/// symbol's modules match no pattern; the symbols are not otherwise
+/// validated.
+///
+/// ```
+/// use feather_code::barcode::code93::{scan_modules, Code93Buf};
+/// use feather_code::barcode::format::Encode;
+///
+/// let code: Code93Buf = "TEST93".encode().unwrap();
+///
+/// assert_eq!(scan_modules(&code.modules()), Ok(code));
+/// ```
+pub fn scan_modules(modules: &[bool]) -> Result<Code93Buf> {
+    let bars = render::bars(modules)?;
+
+    if bars.len() % 9 != 1 {
+        return Err(Error::BadFormat(format!("{} modules of bars is not 9 per symbol plus 1",
+                                            bars.len())));
+    }
+
+    bars[..bars.len() - 1].chunks(9)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let mut dark = [false; 9];
+            dark.copy_from_slice(chunk);
+
+            Code93Symbol::from_modules(dark).ok_or_else(|| {
+                Error::BadFormat(format!("unrecognized modules for symbol {}", i))
+            })
+        })
+        .collect::<Result<Vec<_>>>()
+        .map(Code93Buf)
+}
+
+impl Encode<Code93Buf> for str {
+    /// Encode ASCII text, writing characters outside the 43 of Code 39 as
+    /// shift pairs, and add both check characters
+    ///
+    /// Fails with `BadFormat` for non-ASCII text.
+    ///
+    /// ```
+    /// use feather_code::barcode::code93::Code93Buf;
+    /// use feather_code::barcode::format::Encode;
+    ///
+    /// let code: Code93Buf = "Feather".encode().unwrap();
+    /// assert_eq!(code.to_string(), "*F(+)E(+)A(+)T(+)H(+)E(+)R2U*");
+    /// ```
+    fn encode(&self) -> Result<Code93Buf> {
+        if let Some(c) = self.chars().find(|c| !c.is_ascii()) {
+            return Err(Error::BadFormat(format!("cannot encode non-ASCII character {:?}", c)));
+        }
+
+        let mut symbols = vec![Code93Symbol::Star];
+        for b in self.bytes() {
+            let (shift, s) = ascii_symbols(b);
+            symbols.extend(shift);
+            symbols.push(s);
+        }
+
+        let (c, k) = check_symbols(&symbols[1..]);
+        symbols.extend_from_slice(&[c, k, Code93Symbol::Star]);
+
+        Ok(Code93Buf(symbols))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    fn symbols(text: &str) -> Vec<Code93Symbol> {
+        text.chars().map(|c| Code93Symbol::from_char(c).unwrap_or(Code93Symbol::Star)).collect()
+    }
+
+    #[test]
+    fn test93_reference() {
+        // The specification's example: "TEST93" has check characters + and 6
+        let symbols = symbols("*TEST93+6*");
+        let code = Code93::new(&symbols);
+
+        assert!(code.checksum());
+        assert_eq!(code.decode(), Ok(String::from("TEST93")));
+        assert_eq!(Encode::<Code93Buf>::encode("TEST93").unwrap().symbols(), &symbols[..]);
+    }
+
+    #[test]
+    fn full_ascii() {
+        let text: String = (0..128u8).map(char::from).collect();
+        let code: Code93Buf = text.encode().unwrap();
+
+        assert!(code.checksum());
+        assert_eq!(code.decode(), Ok(text));
+
+        let lower: Code93Buf = "a".encode().unwrap();
+        assert_eq!(&lower.symbols()[1..3], &[Code93Symbol::ShiftPlus, Code93Symbol::A]);
+        let nul: Code93Buf = "\0".encode().unwrap();
+        assert_eq!(&nul.symbols()[1..3], &[Code93Symbol::ShiftPercent, Code93Symbol::U]);
+    }
+
+    #[test]
+    fn empty_data() {
+        let code: Code93Buf = "".encode().unwrap();
+
+        assert_eq!(code.to_string(), "*00*");
+        assert_eq!(code.decode(), Ok(String::new()));
+    }
+
+    #[test]
+    fn catches_single_symbol_errors() {
+        let code: Code93Buf = "PJJ-123C lot 42".encode().unwrap();
+        let n = code.symbols().len();
+
+        for i in 1..n - 1 {
+            for u in 0..47 {
+                let mut symbols = code.symbols().to_vec();
+                if symbols[i].as_u8() == u {
+                    continue;
+                }
+                symbols[i] = Code93Symbol::try_from_u8(u).unwrap();
+
+                assert!(!Code93::new(&symbols).checksum(), "{} at {}", u, i);
+            }
+        }
+    }
+
+    /// Delimit `data` and add its check characters, whatever it holds
+    fn framed(data: &[Code93Symbol]) -> Vec<Code93Symbol> {
+        let (c, k) = check_symbols(data);
+        let mut symbols = vec![Code93Symbol::Star];
+        symbols.extend_from_slice(data);
+        symbols.extend_from_slice(&[c, k, Code93Symbol::Star]);
+
+        symbols
+    }
+
+    #[test]
+    fn rejects_bad_shifts() {
+        let symbols = framed(&[Code93Symbol::ShiftPlus, Code93Symbol::D1]);
+        assert!(Code93::new(&symbols).checksum());
+        assert_eq!(Code93::new(&symbols).decode(),
+                   Err(Error::BadSymbolAt { index: 1, value: 1, context: AFTER_SHIFT }));
+
+        let symbols = framed(&[Code93Symbol::ShiftPercent, Code93Symbol::ShiftSlash]);
+        assert_eq!(Code93::new(&symbols).decode(),
+                   Err(Error::BadSymbolAt { index: 1, value: 45, context: AFTER_SHIFT }));
+
+        let symbols = framed(&[Code93Symbol::D1, Code93Symbol::ShiftPlus]);
+        assert_eq!(Code93::new(&symbols).decode(),
+                   Err(Error::BadFormat(String::from("(+) with nothing to shift"))));
+    }
+
+    #[test]
+    fn rejects_bad_framing() {
+        for text in &["TEST93+6*", "*TEST93+6", "*6*", "*TE*ST93+6*", "*TEST93+7*"] {
+            let symbols = symbols(text);
+            let code = Code93::new(&symbols);
+
+            assert!(!code.checksum(), "{}", text);
+            assert!(code.decode().is_err(), "{}", text);
+        }
+
+        assert_eq!(Encode::<Code93Buf>::encode("caf\u{e9}"),
+                   Err(Error::BadFormat(String::from("cannot encode non-ASCII character \
+                                                      '\u{e9}'"))));
+    }
+
+    #[test]
+    fn scan_rendered_modules() {
+        let code: Code93Buf = "Feather 93".encode().unwrap();
+        let modules = code.modules();
+
+        assert_eq!(modules.len(), 2 * QUIET_ZONE + 9 * code.symbols().len() + 1);
+        assert_eq!(scan_modules(&modules[QUIET_ZONE - 1..]), Ok(code.clone()));
+
+        let mut damaged = modules.clone();
+        damaged[QUIET_ZONE + 12] = !damaged[QUIET_ZONE + 12];
+        assert!(scan_modules(&damaged).is_err());
+        assert!(scan_modules(&modules[..modules.len() - QUIET_ZONE - 1]).is_err());
+        assert_eq!(scan_modules(&[false; 20]),
+                   Err(Error::BadFormat(String::from("no bars found"))));
+    }
+
+    quickcheck! {
+        fn round_trip(bytes: Vec<u8>) -> bool {
+            let text: String = bytes.iter().map(|&b| char::from(b & 0x7f)).collect();
+            let code: Code93Buf = text.encode().unwrap();
+
+            code.checksum() && code.decode() == Ok(text)
+        }
+    }
+}
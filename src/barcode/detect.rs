@@ -0,0 +1,259 @@
+//  This Source Code Form is subject to the terms of the Mozilla Public
+//  License, v. 2.0. If a copy of the MPL was not distributed with this
+//  file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Reading a row of modules without knowing its symbology
+//!
+//! [`detect`](fn.detect.html) tries the `scan_modules` of every symbology
+//! that can be rendered as modules, and decodes the ones whose bars fit.
+//! The same bars can pass more than one check, so
+//! [`detect_all`](fn.detect_all.html) reports every reading, best first.
+//!
+//! ```
+//! use feather_code::barcode::detect::{detect, Detected};
+//! use feather_code::barcode::code93::Code93Buf;
+//! use feather_code::barcode::format::{BarcodeFormat, Encode};
+//!
+//! let code: Code93Buf = "FEATHER".encode().unwrap();
+//!
+//! assert_eq!(detect(&code.modules()),
+//!            Ok(Detected { format: BarcodeFormat::Code93, text: String::from("FEATHER") }));
+//! ```
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use barcode::code128::{self, ScanOptions};
+use barcode::code39::{self, Code39};
+use barcode::code93;
+use barcode::ean13::{self, EAN13};
+use barcode::format::{BarcodeFormat, Checksum, Decode, Error, Result};
+use barcode::msi::{self, CheckScheme};
+use barcode::pharmacode;
+use barcode::render;
+
+/// MSI check schemes to try, the default first: a barcode with one check
+/// digit passes a double check about one time in ten, and one with two
+/// always passes the single check of its last digit, so neither reading
+/// proves the scheme
+const MSI_SCHEMES: [CheckScheme; 5] = [
+    CheckScheme::Mod10,
+    CheckScheme::Mod1010,
+    CheckScheme::Mod1110,
+    CheckScheme::Mod11,
+    CheckScheme::None,
+];
+
+/// A barcode found by `detect`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Detected {
+    /// The symbology the modules were read as
+    pub format: BarcodeFormat,
+    /// The decoded text, without check characters; a Pharmacode's value in
+    /// decimal
+    pub text: String,
+}
+
+impl fmt::Display for Detected {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// The check status of `code` and its text, as found in `format`
+fn candidate<F: Decode<String>>(format: BarcodeFormat, code: &F) -> (Checksum, Result<Detected>) {
+    (code.checksum_status(), code.decode().map(|text| Detected { format, text }))
+}
+
+/// Identify and decode a row of modules, `true` for dark
+///
+/// Every symbology whose bars fit the modules is decoded: Code 128, EAN-13,
+/// Code 39, Code 93, MSI, and Pharmacode, in that order.  A format whose
+/// check validates is preferred over one without a check, such as
+/// Pharmacode, and ties go to that order.  Code 39 is read without a check
+/// character and MSI with a single check digit, their defaults, whenever
+/// that reading holds; `detect_all` also reports the other schemes.  Fails
+/// with the first decoding error when no format decodes, or `BadFormat`
+/// when the bars fit none.
+pub fn detect(modules: &[bool]) -> Result<Detected> {
+    let mut readings = detect_all(modules)?;
+
+    Ok(readings.swap_remove(0))
+}
+
+/// Every reading of a row of modules, the one `detect` picks first
+///
+/// Each format is read with its default check scheme first, then with the
+/// others: Code 39 without a check character and then with one, MSI with
+/// one check digit and then with two, eleven, or none.  The first reading
+/// of each format that decodes is ranked as `detect` ranks formats; the
+/// readings with other schemes follow in the order they were tried.
+/// Readings whose check fails are left out, and so are repeated ones.
+/// Fails as `detect` does when nothing is read.
+pub fn detect_all(modules: &[bool]) -> Result<Vec<Detected>> {
+    render::bars(modules)?;
+
+    let mut candidates: Vec<(Checksum, Result<Detected>)> = Vec::new();
+
+    if let Ok(scan) = code128::scan_modules(modules, &ScanOptions::default()) {
+        candidates.push(candidate(BarcodeFormat::Code128, &scan.code));
+    }
+
+    if let Ok(digits) = ean13::scan_modules(modules) {
+        candidates.push(candidate(BarcodeFormat::EAN13, &EAN13::new(&digits)));
+    }
+
+    // Without a check character first: any last character is someone's
+    // check, one time in 43
+    if let Ok(symbols) = code39::scan_modules(modules) {
+        candidates.push(candidate(BarcodeFormat::Code39, &Code39::new(&symbols)));
+        candidates.push(candidate(BarcodeFormat::Code39, &Code39::with_check(&symbols)));
+    }
+
+    if let Ok(code) = code93::scan_modules(modules) {
+        candidates.push(candidate(BarcodeFormat::Code93, &code));
+    }
+
+    for &scheme in &MSI_SCHEMES {
+        if let Ok(code) = msi::scan_modules(modules, scheme) {
+            candidates.push(candidate(BarcodeFormat::Msi, &code));
+        }
+    }
+
+    if let Ok(code) = pharmacode::scan_modules(modules) {
+        candidates.push(candidate(BarcodeFormat::Pharmacode, &code));
+    }
+
+    let mut error = None;
+    let mut primary: Vec<(Checksum, Detected)> = Vec::new();
+    let mut others: Vec<Detected> = Vec::new();
+
+    for (status, result) in candidates {
+        match result {
+            Ok(_) if status == Checksum::Invalid => {}
+            Ok(reading) => {
+                if primary.iter().any(|p| p.1 == reading) || others.contains(&reading) {
+                    continue;
+                }
+
+                if primary.iter().any(|p| p.1.format == reading.format) {
+                    others.push(reading);
+                } else {
+                    primary.push((status, reading));
+                }
+            }
+            Err(e) => {
+                error = error.or(Some(e));
+            }
+        }
+    }
+
+    if primary.is_empty() {
+        return Err(error.unwrap_or_else(|| {
+            Error::BadFormat(String::from("modules match no supported barcode"))
+        }));
+    }
+
+    // Stable, so formats with the same status keep their order
+    primary.sort_by_key(|p| p.0 != Checksum::Valid);
+
+    Ok(primary.into_iter().map(|p| p.1).chain(others).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use barcode::code128::{Code128Buf, Pattern};
+    use barcode::code39::Code39Symbol;
+    use barcode::code93::Code93Buf;
+    use barcode::format::{Encode, Format};
+    use barcode::pharmacode::PharmacodeBuf;
+
+    fn found(format: BarcodeFormat, text: &str) -> Result<Detected> {
+        Ok(Detected { format, text: String::from(text) })
+    }
+
+    fn code39(text: &str) -> Vec<Code39Symbol> {
+        text.chars().map(|c| Code39Symbol::from_char(c).unwrap()).collect()
+    }
+
+    #[test]
+    fn detects_each_rendered_format() {
+        let code128: Code128Buf<Pattern> = "PJJ123C".encode().unwrap();
+        let code93: Code93Buf = "Feather 93".encode().unwrap();
+        let pharmacode: PharmacodeBuf = 1234u32.encode().unwrap();
+        let isbn = [9, 7, 8, 3, 1, 6, 1, 4, 8, 4, 1, 0, 0];
+
+        assert_eq!(detect(&code128.modules()), found(BarcodeFormat::Code128, "PJJ123C"));
+        assert_eq!(detect(&code93.modules()), found(BarcodeFormat::Code93, "Feather 93"));
+        assert_eq!(detect(&pharmacode.modules()), found(BarcodeFormat::Pharmacode, "1234"));
+        assert_eq!(detect(&EAN13::new(&isbn).modules()),
+                   found(BarcodeFormat::EAN13, "9783161484100"));
+
+        // Some of these also pass a double check, such as 1230
+        for n in 1000..2000 {
+            let text = n.to_string();
+            let msi = msi::encode_with(&text, CheckScheme::default()).unwrap();
+
+            assert_eq!(detect(&msi.modules()), found(BarcodeFormat::Msi, &text));
+        }
+
+        for text in &["*HACK FSU $1.50*", "*CODE39W*", "*A*", "*12345*", "*FEATHER-39 %*"] {
+            let symbols = code39(text);
+            let code = Code39::new(&symbols);
+
+            assert_eq!(detect(&code.modules()),
+                       found(BarcodeFormat::Code39, text.trim_matches('*')));
+        }
+    }
+
+    #[test]
+    fn reports_other_check_schemes() {
+        for &scheme in &MSI_SCHEMES {
+            for text in &["80524", "1230", "1234"] {
+                let msi = msi::encode_with(text, scheme).unwrap();
+                let readings = detect_all(&msi.modules()).unwrap();
+
+                assert!(readings.contains(&Detected { format: BarcodeFormat::Msi,
+                                                      text: text.to_string() }),
+                        "{} {:?} read as {:?}", text, scheme, readings);
+            }
+        }
+
+        let symbols = code39("*CODE39W*");
+        let checked = Code39::with_check(&symbols);
+        let readings = detect_all(&checked.modules()).unwrap();
+
+        assert_eq!(readings[0], Detected { format: BarcodeFormat::Code39,
+                                           text: String::from("CODE39W") });
+        assert!(readings.contains(&Detected { format: BarcodeFormat::Code39,
+                                              text: String::from("CODE39") }));
+    }
+
+    #[test]
+    fn prefers_the_default_check() {
+        let code = msi::encode_with("1234", CheckScheme::Mod1010).unwrap();
+
+        // The same bars read with one check digit keep the first as data
+        let single = msi::scan_modules(&code.modules(), CheckScheme::Mod10).unwrap();
+        assert!(single.checksum());
+
+        let first = single.decode().unwrap();
+        let readings = detect_all(&code.modules()).unwrap();
+
+        assert_eq!(detect(&code.modules()).map(|d| d.to_string()), Ok(first.clone()));
+        // Then the double check, before the bars read without one
+        assert_eq!(readings.iter().map(|d| d.to_string()).collect::<Vec<_>>(),
+                   vec![first, String::from("1234"), String::from("123448")]);
+    }
+
+    #[test]
+    fn rejects_unknown_modules() {
+        assert_eq!(detect(&[false; 20]),
+                   Err(Error::BadFormat(String::from("no bars found"))));
+        assert_eq!(detect(&[true, false, true, true, false, false, false, true]),
+                   Err(Error::BadFormat(String::from("modules match no supported barcode"))));
+    }
+}
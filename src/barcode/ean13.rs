@@ -0,0 +1,315 @@
+//  This Source Code Form is subject to the terms of the Mozilla Public
+//  License, v. 2.0. If a copy of the MPL was not distributed with this
+//  file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! EAN-13 barcodes
+//!
+//! An EAN-13 barcode is thirteen digits, the last of which is a check
+//! digit.  It marks retail products and, with the `978` and `979`
+//! prefixes, books by their ISBN-13.
+//!
+//! ```
+//! use feather_code::barcode::ean13::EAN13;
+//! use feather_code::barcode::format::{Decode, Format};
+//!
+//! // ISBN 978-3-16-148410-0
+//! let digits: [u8; 13] = [9, 7, 8, 3, 1, 6, 1, 4, 8, 4, 1, 0, 0];
+//! let code = EAN13::new(&digits);
+//!
+//! assert!(code.checksum());
+//! assert_eq!(code.decode(), Ok(String::from("9783161484100")));
+//! ```
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use barcode::format::{Decode, Error, Format, Result, IN_DATA};
+use barcode::render::{self, QUIET_ZONE};
+
+/// Number of digits in every EAN-13 barcode, including the check digit
+pub const DIGITS: usize = 13;
+
+/// Modules between the quiet zones: three guards and twelve 7 module
+/// digits
+pub const MODULES: usize = 95;
+
+/// The guard at each end, bar space bar
+const END_GUARD: [bool; 3] = [true, false, true];
+
+/// The guard between the halves
+const CENTER_GUARD: [bool; 5] = [false, true, false, true, false];
+
+/// The left-hand odd parity (L) code of each digit, as the low 7 bits with
+/// the first module highest; even parity (G) codes are these reversed and
+/// inverted, and right-hand (R) codes these inverted
+const L_CODES: [u8; 10] = [
+    0b0001101, 0b0011001, 0b0010011, 0b0111101, 0b0100011,
+    0b0110001, 0b0101111, 0b0111011, 0b0110111, 0b0001011,
+];
+
+/// Which of the left-hand digits use G codes, first digit highest, for
+/// each value of the first digit, which isn't drawn itself
+const PARITY: [u8; 10] = [
+    0b000000, 0b001011, 0b001101, 0b001110, 0b010011,
+    0b011001, 0b011100, 0b010101, 0b010110, 0b011010,
+];
+
+/// The 7 modules drawing `digit` as an L, G, or R code
+fn digit_code(digit: u8, even: bool, right: bool) -> u8 {
+    let l = L_CODES[digit as usize % 10];
+
+    match (even, right) {
+        (_, true) => !l & 0x7f,
+        (true, false) => (!l & 0x7f).reverse_bits() >> 1,
+        (false, false) => l,
+    }
+}
+
+/// An EAN-13 barcode borrowing its digits, each a value in `0..=9`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EAN13<'a>(&'a [u8]);
+
+/// The check digit for the first twelve digits of a barcode
+///
+/// Digits are weighted 1 and 3 alternately from the left; the check digit
+/// brings their sum up to a multiple of ten.
+///
+/// ```
+/// use feather_code::barcode::ean13::check_digit;
+///
+/// assert_eq!(check_digit(&[9, 7, 8, 0, 3, 0, 6, 4, 0, 6, 1, 5]), 7);
+/// ```
+pub fn check_digit(digits: &[u8]) -> u8 {
+    let sum: usize = digits.iter()
+        .enumerate()
+        .map(|(i, &d)| if i % 2 == 0 { d as usize } else { 3 * d as usize })
+        .sum();
+
+    ((10 - sum % 10) % 10) as u8
+}
+
+impl<'a> EAN13<'a> {
+    /// Wrap thirteen digits, the check digit last
+    pub fn new(digits: &'a [u8]) -> EAN13<'a> {
+        EAN13(digits)
+    }
+
+    /// The digits, as given
+    pub fn digits(&self) -> &'a [u8] {
+        self.0
+    }
+
+    /// Every module of the printed barcode, `true` for dark
+    ///
+    /// Runs from the leading quiet zone to the trailing one, as for
+    /// `Code128::modules`.  The first digit is drawn only as the parity of
+    /// the next six; digits out of range are drawn modulo 10.
+    pub fn modules(&self) -> Vec<bool> {
+        let first = self.0.first().map_or(0, |&d| d % 10);
+        let rest = self.0.get(1..).unwrap_or(&[]);
+        let mut modules = vec![false; QUIET_ZONE];
+        modules.extend_from_slice(&END_GUARD);
+
+        for (i, &d) in rest.iter().enumerate() {
+            if i == 6 {
+                modules.extend_from_slice(&CENTER_GUARD);
+            }
+
+            let even = i < 6 && PARITY[first as usize] >> (5 - i) & 1 == 1;
+            let code = digit_code(d, even, i >= 6);
+            modules.extend((0..7).rev().map(|bit| code >> bit & 1 == 1));
+        }
+
+        modules.extend_from_slice(&END_GUARD);
+        modules.extend_from_slice(&[false; QUIET_ZONE]);
+        modules
+    }
+
+    /// Fail unless there are thirteen digits, all in `0..=9`
+    fn check(&self) -> Result<()> {
+        if self.0.len() != DIGITS {
+            return Err(Error::InvalidLength(format!("EAN-13 barcodes have {} digits, not {}",
+                                                    DIGITS, self.0.len())));
+        }
+
+        match self.0.iter().position(|&d| d > 9) {
+            Some(index) => {
+                Err(Error::BadSymbolAt { index, value: self.0[index], context: IN_DATA })
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+/// Read the thirteen digits from a row of modules, `true` for dark
+///
+/// Light modules on either side are skipped, and the rest must be the
+/// `MODULES` modules of the guards and twelve drawn digits.  The first
+/// digit is recovered from the parity of the next six.  Fails with
+/// `BadFormat` otherwise; the check digit is not validated.
+///
+/// ```
+/// use feather_code::barcode::ean13::{scan_modules, EAN13};
+///
+/// let digits: [u8; 13] = [9, 7, 8, 3, 1, 6, 1, 4, 8, 4, 1, 0, 0];
+///
+/// assert_eq!(scan_modules(&EAN13::new(&digits).modules()), Ok(digits.to_vec()));
+/// ```
+pub fn scan_modules(modules: &[bool]) -> Result<Vec<u8>> {
+    let bars = render::bars(modules)?;
+
+    if bars.len() != MODULES || bars[..3] != END_GUARD || bars[45..50] != CENTER_GUARD ||
+       bars[MODULES - 3..] != END_GUARD {
+        return Err(Error::BadFormat(String::from("modules don't frame EAN-13 digits")));
+    }
+
+    let mut digits = vec![0];
+    let mut parity = 0;
+
+    for i in 0..12 {
+        let at = if i < 6 { 3 + 7 * i } else { 50 + 7 * (i - 6) };
+        let code = bars[at..at + 7].iter().fold(0, |code, &dark| code << 1 | dark as u8);
+        // L or G codes on the left, R codes on the right
+        let sides: &[(bool, bool)] = if i < 6 {
+            &[(false, false), (true, false)]
+        } else {
+            &[(false, true)]
+        };
+
+        let found = sides.iter()
+            .flat_map(|&(even, right)| (0..10).map(move |d| (d, even, right)))
+            .find(|&(d, even, right)| digit_code(d, even, right) == code);
+
+        match found {
+            Some((d, even, right)) => {
+                digits.push(d);
+                if !right {
+                    parity = parity << 1 | even as u8;
+                }
+            }
+            None => {
+                return Err(Error::BadFormat(format!("unrecognized modules for digit {}",
+                                                    i + 1)));
+            }
+        }
+    }
+
+    match PARITY.iter().position(|&p| p == parity) {
+        Some(first) => digits[0] = first as u8,
+        None => return Err(Error::BadFormat(String::from("unrecognized parity pattern"))),
+    }
+
+    Ok(digits)
+}
+
+impl<'a> Format for EAN13<'a> {
+    /// Whether the barcode is thirteen digits ending in the right check
+    /// digit
+    fn checksum(&self) -> bool {
+        self.check().is_ok() && check_digit(&self.0[..DIGITS - 1]) == self.0[DIGITS - 1]
+    }
+}
+
+impl<'a> Decode<String> for EAN13<'a> {
+    /// The thirteen digits as text, check digit included
+    fn decode(&self) -> Result<String> {
+        self.check()?;
+
+        if !self.checksum() {
+            return Err(Error::BadFormat(String::from("check digit does not match")));
+        }
+
+        Ok(self.0.iter().map(|&d| char::from(b'0' + d)).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ISBN: [u8; 13] = [9, 7, 8, 3, 1, 6, 1, 4, 8, 4, 1, 0, 0];
+
+    #[test]
+    fn isbn_13() {
+        let code = EAN13::new(&ISBN);
+
+        assert!(code.checksum());
+        assert_eq!(code.decode(), Ok(String::from("9783161484100")));
+        assert_eq!(check_digit(&ISBN[..12]), 0);
+    }
+
+    #[test]
+    fn rejects_wrong_check_digit() {
+        let mut digits = ISBN;
+        digits[12] = 7;
+        let code = EAN13::new(&digits);
+
+        assert!(!code.checksum());
+        assert_eq!(code.decode(),
+                   Err(Error::BadFormat(String::from("check digit does not match"))));
+    }
+
+    #[test]
+    fn catches_single_digit_errors() {
+        for i in 0..12 {
+            let mut digits = ISBN;
+            digits[i] = (digits[i] + 1) % 10;
+
+            assert!(!EAN13::new(&digits).checksum(), "digit {}", i);
+        }
+    }
+
+    #[test]
+    fn rejects_bad_layout() {
+        assert!(!EAN13::new(&ISBN[..12]).checksum());
+        let message = "EAN-13 barcodes have 13 digits, not 12";
+        assert_eq!(EAN13::new(&ISBN[..12]).decode(),
+                   Err(Error::InvalidLength(String::from(message))));
+
+        let mut digits = ISBN;
+        digits[3] = 13;
+        assert_eq!(EAN13::new(&digits).decode(),
+                   Err(Error::BadSymbolAt { index: 3, value: 13, context: IN_DATA }));
+    }
+
+    #[test]
+    fn modules_round_trip_every_first_digit() {
+        for first in 0..10 {
+            let mut digits = [first, 4, 0, 0, 6, 3, 8, 1, 3, 3, 3, 9, 0];
+            digits[12] = check_digit(&digits[..12]);
+            let modules = EAN13::new(&digits).modules();
+
+            assert_eq!(modules.len(), 2 * QUIET_ZONE + MODULES);
+            assert_eq!(scan_modules(&modules), Ok(digits.to_vec()), "{}", first);
+        }
+    }
+
+    #[test]
+    fn modules_match_reference_codes() {
+        let modules = EAN13::new(&ISBN).modules();
+        let bits = |from: usize| -> String {
+            modules[QUIET_ZONE + from..QUIET_ZONE + from + 7].iter()
+                .map(|&dark| if dark { '1' } else { '0' })
+                .collect()
+        };
+
+        // After 9, the 7 is an L code, the 8 a G code, and the last digit an
+        // R code
+        assert_eq!(bits(3), "0111011");
+        assert_eq!(bits(10), "0001001");
+        assert_eq!(bits(85), "1110010");
+    }
+
+    #[test]
+    fn scan_rejects_bad_modules() {
+        let mut modules = EAN13::new(&ISBN).modules();
+
+        assert_eq!(scan_modules(&modules[..modules.len() - QUIET_ZONE - 1]),
+                   Err(Error::BadFormat(String::from("modules don't frame EAN-13 digits"))));
+
+        // The first digit's modules, 0111011, become 0111111
+        modules[QUIET_ZONE + 3 + 4] = true;
+        assert_eq!(scan_modules(&modules),
+                   Err(Error::BadFormat(String::from("unrecognized modules for digit 1"))));
+    }
+}
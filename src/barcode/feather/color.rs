@@ -0,0 +1,421 @@
+//  This Source Code Form is subject to the terms of the Mozilla Public
+//  License, v. 2.0. If a copy of the MPL was not distributed with this
+//  file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Several feather codes overlaid in the channels of one color image
+//!
+//! Up to three feather codes, the *tracks*, share a grid: the first is
+//! drawn in the red channel, the second in green, and the third in blue.
+//! In each channel a dark module of that track is `0` and anything else is
+//! `255`, so modules dark in every track are black and modules light in
+//! every track are white.  Tracks narrower than the image are padded with
+//! light columns on the right, and channels without a track are left
+//! entirely light.
+//!
+//! A reader thresholds each channel at `128` and reads it as a plain
+//! feather code.
+//!
+//! A single text can also be spread over the tracks to fit more in one
+//! image: it is cut into up to three consecutive pieces of nearly equal
+//! length, and decoding to a `String` joins the tracks back together in
+//! channel order.  The first track starts with a header: FNC2, which Code
+//! 128 uses to append one symbol's message to the next, then four Code C
+//! digit pairs holding the number of tracks and a CRC-16 of the whole
+//! text.  A spread text with a track missing or out of place is rejected
+//! rather than read short.  The checksum holds only if every track's does,
+//! and for spread text, the header's as well.
+//!
+//! Requires the `image` feature.
+//!
+//! ```
+//! use feather_code::barcode::feather::color::FeatherColor;
+//! use feather_code::barcode::format::{Decode, Encode};
+//!
+//! let color: FeatherColor = ["HackFSU", "2024", "feather"][..].encode().unwrap();
+//! let read = FeatherColor::from_image(&color.to_image()).unwrap();
+//!
+//! let tracks: Vec<String> = read.decode().unwrap();
+//! assert_eq!(tracks, ["HackFSU", "2024", "feather"]);
+//! ```
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use image::{Rgb, RgbImage};
+
+use super::{crc16, Feather, HEIGHT, QUIET_ZONE, VANE};
+use barcode::code128::{Code128Builder, Encoding, Symbology};
+use barcode::format::{Decode, Encode, Error, Format, Result};
+
+/// Number of tracks an image can hold, one per channel
+pub const CHANNELS: usize = 3;
+
+/// Digits of the header of spread text: two for the number of tracks, then
+/// six for the CRC-16
+const HEADER_DIGITS: usize = 8;
+
+/// FNC2 in symbologies A and B
+const FNC2: u8 = 97;
+
+/// Feather codes drawn in the red, green, and blue channels of an image
+#[derive(Debug, PartialEq)]
+pub struct FeatherColor(Vec<Feather>);
+
+impl FeatherColor {
+    /// Overlay one to three feather codes, in red, green, and blue order
+    pub fn new(tracks: Vec<Feather>) -> Result<FeatherColor> {
+        if tracks.is_empty() || tracks.len() > CHANNELS {
+            return Err(Error::BadFormat(format!("expected 1 to {} tracks, found {}",
+                                                CHANNELS, tracks.len())));
+        }
+
+        Ok(FeatherColor(tracks))
+    }
+
+    /// Spread ASCII text over up to three tracks, with a header in the
+    /// first
+    ///
+    /// Fails with `BadFormat` for empty or non-ASCII text.
+    ///
+    /// ```
+    /// use feather_code::barcode::feather::color::FeatherColor;
+    /// use feather_code::barcode::format::Decode;
+    ///
+    /// let color = FeatherColor::encode_str("HackFSU feather codes").unwrap();
+    ///
+    /// assert_eq!(color.tracks().len(), 3);
+    /// assert_eq!(Decode::<String>::decode(&color), Ok(String::from("HackFSU feather codes")));
+    /// ```
+    pub fn encode_str(text: &str) -> Result<FeatherColor> {
+        if let Some(c) = text.chars().find(|c| !c.is_ascii()) {
+            return Err(Error::BadFormat(format!("cannot encode non-ASCII character {:?}", c)));
+        }
+
+        if text.is_empty() {
+            return Err(Error::BadFormat(String::from("nothing to encode")));
+        }
+
+        let pieces: Vec<&[u8]> = text.as_bytes().chunks(text.len().div_ceil(CHANNELS)).collect();
+        let header = format!("{:02}{:06}", pieces.len(), crc16(text.as_bytes()));
+
+        let first = Code128Builder::new()
+            .fnc2()
+            .push_c_digits(&header)
+            .text(&text[..pieces[0].len()])
+            .build()
+            .map(Feather::new);
+
+        let tracks = Some(first).into_iter()
+            .chain(pieces[1..].iter().map(|piece| Feather::from_bytes(piece)))
+            .collect::<Result<Vec<Feather>>>()?;
+
+        FeatherColor::new(tracks)
+    }
+
+    /// The text spread over the tracks, checked against the header, or
+    /// `None` if the first track has no header
+    ///
+    /// Fails with `DecodeErr` if the header is malformed, counts other
+    /// than the tracks there are, or has a CRC that doesn't match.
+    fn spread_text(&self) -> Result<Option<String>> {
+        let marked = match self.0[0].code().symbols() {
+            [start, fnc2, ..] => {
+                start.as_u8() != Symbology::C.start_value() && fnc2.as_u8() == FNC2
+            }
+            _ => false,
+        };
+
+        if !marked {
+            return Ok(None);
+        }
+
+        let tracks: Vec<String> = self.decode()?;
+        let header = tracks[0].get(..HEADER_DIGITS)
+            .filter(|header| header.bytes().all(|b| b.is_ascii_digit()))
+            .ok_or_else(|| Error::DecodeErr(String::from("missing header of spread text")))?;
+
+        let count: usize = header[..2].parse().unwrap();
+        let crc: u32 = header[2..].parse().unwrap();
+
+        if count != self.0.len() {
+            return Err(Error::DecodeErr(format!("spread text has {} tracks, found {}",
+                                                count, self.0.len())));
+        }
+
+        let text = String::from(&tracks[0][HEADER_DIGITS..]) + &tracks[1..].concat();
+
+        if crc != crc16(text.as_bytes()) as u32 {
+            return Err(Error::DecodeErr(String::from("CRC-16 does not match the spread text")));
+        }
+
+        Ok(Some(text))
+    }
+
+    /// The feather code in each channel, in red, green, and blue order
+    pub fn tracks(&self) -> &[Feather] {
+        &self.0
+    }
+
+    /// Draw the tracks with one pixel per module
+    pub fn to_image(&self) -> RgbImage {
+        let width = self.0.iter().map(Feather::width).max().unwrap_or(0);
+        let mut img = RgbImage::from_pixel(width as u32, HEIGHT as u32, Rgb([255; 3]));
+
+        for (c, track) in self.0.iter().enumerate() {
+            for (y, row) in track.modules().iter().enumerate() {
+                for (x, &dark) in row.iter().enumerate() {
+                    if dark {
+                        img.get_pixel_mut(x as u32, y as u32).0[c] = 0;
+                    }
+                }
+            }
+        }
+
+        img
+    }
+
+    /// Read the tracks from an image drawn with one pixel per module
+    ///
+    /// Tracks are read from the red channel onwards, stopping at the first
+    /// entirely light channel; any channel after it must be light too.
+    /// Fails with `BadFormat` if a channel is not a feather code, or like
+    /// decoding to a `String` if the image holds spread text that doesn't
+    /// check out, such as with a channel blanked.
+    pub fn from_image(img: &RgbImage) -> Result<FeatherColor> {
+        let channels: Vec<Vec<Vec<bool>>> = (0..CHANNELS).map(|c| {
+            img.rows()
+                .map(|row| row.map(|p| p.0[c] < 128).collect())
+                .collect()
+        }).collect();
+
+        let used = channels.iter()
+            .take_while(|rows| rows.iter().any(|row| row.contains(&true)))
+            .count();
+
+        if channels[used..].iter().any(|rows| rows.iter().any(|row| row.contains(&true))) {
+            return Err(Error::BadFormat(String::from("tracks must fill channels in order")));
+        }
+
+        let tracks = channels[..used].iter()
+            .map(|rows| Feather::from_modules(&trim(rows)?))
+            .collect::<Result<Vec<Feather>>>()?;
+
+        let color = FeatherColor::new(tracks)?;
+        color.spread_text()?;
+
+        Ok(color)
+    }
+}
+
+/// Drop the light padding to the right of a track's quiet zone
+fn trim(rows: &[Vec<bool>]) -> Result<Vec<Vec<bool>>> {
+    if rows.len() != HEIGHT {
+        return Err(Error::BadFormat(format!("feather codes are {} modules tall, not {}",
+                                            HEIGHT, rows.len())));
+    }
+
+    let width = match rows[VANE].iter().rposition(|&dark| dark) {
+        Some(last) => last + 1 + QUIET_ZONE,
+        None => return Err(Error::BadFormat(String::from("track has no shaft"))),
+    };
+
+    if rows.iter().any(|row| row.len() < width || row[width..].contains(&true)) {
+        return Err(Error::BadFormat(String::from("missing quiet zone")));
+    }
+
+    Ok(rows.iter().map(|row| row[..width].to_vec()).collect())
+}
+
+impl Format for FeatherColor {
+    fn checksum(&self) -> bool {
+        self.0.iter().all(Feather::checksum) && self.spread_text().is_ok()
+    }
+}
+
+impl<T> Decode<Vec<T>> for FeatherColor
+    where Feather: Decode<T>
+{
+    /// Decode every track, in red, green, and blue order; the first track
+    /// of spread text keeps its header digits
+    fn decode(&self) -> Result<Vec<T>> {
+        self.0.iter().map(Feather::decode).collect()
+    }
+}
+
+impl Decode<String> for FeatherColor {
+    /// Join the text of every track, in red, green, and blue order,
+    /// checking and leaving out the header of spread text
+    fn decode(&self) -> Result<String> {
+        if let Some(text) = self.spread_text()? {
+            return Ok(text);
+        }
+
+        let tracks: Vec<String> = self.decode()?;
+
+        Ok(tracks.concat())
+    }
+}
+
+impl Encode<FeatherColor> for [&str] {
+    /// Encode each string as a track, in red, green, and blue order
+    fn encode(&self) -> Result<FeatherColor> {
+        let tracks = self.iter()
+            .map(|s| Feather::encode(s))
+            .collect::<Result<Vec<Feather>>>()?;
+
+        FeatherColor::new(tracks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use barcode::code128::{Code128Buf, Pattern};
+
+    #[test]
+    fn round_trip_three_tracks() {
+        let color: FeatherColor = ["PJJ123C", "42184020500", "SHiFT!"][..].encode().unwrap();
+        let img = color.to_image();
+        let read = FeatherColor::from_image(&img).unwrap();
+
+        assert_eq!(read, color);
+        assert_eq!(Decode::<Vec<String>>::decode(&read),
+                   Ok(vec![String::from("PJJ123C"),
+                           String::from("42184020500"),
+                           String::from("SHiFT!")]));
+    }
+
+    #[test]
+    fn round_trip_spread_text() {
+        let text = "HackFSU 2024: feather codes in color";
+        let color = FeatherColor::encode_str(text).unwrap();
+        let read = FeatherColor::from_image(&color.to_image()).unwrap();
+
+        let header = format!("03{:06}", crc16(text.as_bytes()));
+
+        assert_eq!(Decode::<Vec<String>>::decode(&read),
+                   Ok(vec![header + "HackFSU 2024",
+                           String::from(": feather co"),
+                           String::from("des in color")]));
+        assert_eq!(Decode::<String>::decode(&read), Ok(String::from(text)));
+        assert!(read.checksum());
+    }
+
+    #[test]
+    fn spread_text_needs_every_track() {
+        let color = FeatherColor::encode_str("HackFSU 2024: feather codes in color").unwrap();
+        let mut img = color.to_image();
+        for p in img.pixels_mut() {
+            p.0[2] = 255;
+        }
+
+        assert_eq!(FeatherColor::from_image(&img),
+                   Err(Error::DecodeErr(String::from("spread text has 3 tracks, found 2"))));
+
+        let mut tracks = color.0;
+        tracks.pop();
+        let short = FeatherColor::new(tracks).unwrap();
+
+        assert!(!short.checksum());
+        assert_eq!(Decode::<String>::decode(&short),
+                   Err(Error::DecodeErr(String::from("spread text has 3 tracks, found 2"))));
+    }
+
+    #[test]
+    fn spread_text_checks_track_order() {
+        let color = FeatherColor::encode_str("abcdefghi").unwrap();
+        let mut tracks = color.0;
+        tracks.swap(1, 2);
+        let swapped = FeatherColor::new(tracks).unwrap();
+
+        assert!(!swapped.checksum());
+        assert_eq!(Decode::<String>::decode(&swapped),
+                   Err(Error::DecodeErr(String::from("CRC-16 does not match the spread text"))));
+    }
+
+    #[test]
+    fn short_text_uses_fewer_tracks() {
+        let one = FeatherColor::encode_str("a").unwrap();
+        let two = FeatherColor::encode_str("abcd").unwrap();
+
+        assert_eq!(one.tracks().len(), 1);
+        assert_eq!(two.tracks().len(), 2);
+        assert_eq!(Decode::<String>::decode(&two), Ok(String::from("abcd")));
+    }
+
+    #[test]
+    fn spread_text_rejects_bad_input() {
+        assert_eq!(FeatherColor::encode_str(""),
+                   Err(Error::BadFormat(String::from("nothing to encode"))));
+        assert_eq!(FeatherColor::encode_str("caf\u{e9}"),
+                   Err(Error::BadFormat(String::from("cannot encode non-ASCII character \
+                                                      '\u{e9}'"))));
+    }
+
+    #[test]
+    fn spread_checksum_needs_every_track() {
+        let color = FeatherColor::encode_str("spread over three").unwrap();
+        let mut tracks: Vec<Feather> = color.0;
+        let mut symbols = tracks[1].code().symbols().to_vec();
+        symbols[1] = Pattern::C1;
+        tracks[1] = Feather::new(Code128Buf::new(symbols));
+
+        let damaged = FeatherColor::new(tracks).unwrap();
+        assert!(!damaged.checksum());
+        assert!(Decode::<String>::decode(&damaged).is_err());
+    }
+
+    #[test]
+    fn image_is_as_wide_as_the_widest_track() {
+        let color: FeatherColor = ["1", "a much longer track"][..].encode().unwrap();
+        let img = color.to_image();
+
+        assert_eq!(img.width() as usize, color.tracks()[1].width());
+        assert_eq!(img.height() as usize, HEIGHT);
+        assert_eq!(FeatherColor::from_image(&img).unwrap(), color);
+    }
+
+    #[test]
+    fn unused_channels_are_light() {
+        let color: FeatherColor = ["HackFSU"][..].encode().unwrap();
+        let img = color.to_image();
+
+        assert!(img.pixels().all(|p| p.0[1] == 255 && p.0[2] == 255));
+        assert!(img.pixels().any(|p| p.0[0] == 0));
+    }
+
+    #[test]
+    fn shared_dark_modules_are_black() {
+        let color: FeatherColor = ["same", "same", "same"][..].encode().unwrap();
+        let img = color.to_image();
+
+        assert!(img.pixels().all(|p| p.0 == [0; 3] || p.0 == [255; 3]));
+    }
+
+    #[test]
+    fn rejects_track_count() {
+        assert!(Encode::<FeatherColor>::encode(&[][..]).is_err());
+        assert!(Encode::<FeatherColor>::encode(&["a", "b", "c", "d"][..]).is_err());
+    }
+
+    #[test]
+    fn rejects_gap_in_channels() {
+        let color: FeatherColor = ["red", "green", "blue"][..].encode().unwrap();
+        let mut img = color.to_image();
+        for p in img.pixels_mut() {
+            p.0[1] = 255;
+        }
+
+        assert_eq!(FeatherColor::from_image(&img),
+                   Err(Error::BadFormat(String::from("tracks must fill channels in order"))));
+    }
+
+    #[test]
+    fn rejects_damaged_channel() {
+        let color: FeatherColor = ["red", "green"][..].encode().unwrap();
+        let mut img = color.to_image();
+        img.get_pixel_mut(0, 0).0[1] = 0;
+
+        assert!(FeatherColor::from_image(&img).is_err());
+    }
+}
@@ -0,0 +1,554 @@
+//  This Source Code Form is subject to the terms of the Mozilla Public
+//  License, v. 2.0. If a copy of the MPL was not distributed with this
+//  file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Feather codes: Code 128 barcodes drawn as a feather
+//!
+//! A feather code is a grid of square modules, each dark or light, laid out
+//! so that a line scanned across its middle reads as an ordinary Code 128
+//! barcode.  The bars grow out of that line as the barbs of a feather.
+//!
+//! # Layout
+//!
+//! The grid is [`HEIGHT`](constant.HEIGHT.html) modules tall and
+//! `11 * n + 2 + 2 * QUIET_ZONE` modules wide for a barcode of `n` symbols,
+//! counting the start, check, and stop symbols.  Rows are numbered from the
+//! top and columns from the left.
+//!
+//! - The first and last [`QUIET_ZONE`](constant.QUIET_ZONE.html) columns
+//!   are light.
+//! - Row [`VANE`](constant.VANE.html), the *shaft*, holds the symbols in
+//!   order: each symbol is drawn from its
+//!   [`bar_widths`](../code128/encodings/enum.Pattern.html#method.bar_widths)
+//!   as dark bars and light spaces, and the stop symbol is followed by a
+//!   2 module dark bar.
+//! - Every dark module of the shaft extends up and down into a *barb*.  At
+//!   column `x` counted from the first bar, in a barcode `w` modules wide
+//!   without its quiet zones, the barb covers the rows within
+//!   `barb_len(x, w)` of the shaft:
+//!
+//!   ```text
+//!   barb_len(x, w) = MIN_BARB + (VANE - MIN_BARB) * 4 * x * (w - 1 - x) / (w - 1)²
+//!   ```
+//!
+//!   rounded to the nearest module, so the barbs are shortest at the ends
+//!   of the code and reach the top and bottom rows in the middle.
+//! - All other modules are light.
+//!
+//! Every barb is at least `MIN_BARB` long, so the rows within `MIN_BARB` of
+//! the shaft are all plain Code 128 scan lines.  A reader recovers the
+//! symbols from the shaft alone; the barbs let it tell a feather code from
+//! a damaged or unrelated barcode.
+//!
+//! ```
+//! use feather_code::barcode::feather::Feather;
+//! use feather_code::barcode::format::Decode;
+//!
+//! let feather = Feather::encode("HackFSU").unwrap();
+//! let read = Feather::from_modules(&feather.modules()).unwrap();
+//!
+//! assert_eq!(read.decode(), Ok(String::from("HackFSU")));
+//! ```
+
+#[cfg(feature = "image")]
+pub mod color;
+pub mod stacked;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use barcode::code128::{Code128Buf, Code128Builder, Item, Pattern};
+use barcode::format::{Decode, Encode, Error, Format, Result};
+use barcode::render;
+
+/// Light columns on each side of the bars
+pub const QUIET_ZONE: usize = render::QUIET_ZONE;
+
+/// Row of the shaft, and the length of the longest barbs
+pub const VANE: usize = 16;
+
+/// Length of the shortest barbs
+pub const MIN_BARB: usize = 4;
+
+/// Height of every feather code in modules
+pub const HEIGHT: usize = 2 * VANE + 1;
+
+/// Rows above and below the shaft covered by a barb at column `x` of bars
+/// `width` modules wide
+pub fn barb_len(x: usize, width: usize) -> usize {
+    if width < 2 {
+        return VANE;
+    }
+
+    let span = (width - 1) * (width - 1);
+
+    MIN_BARB + ((VANE - MIN_BARB) * 4 * x * (width - 1 - x) + span / 2) / span
+}
+
+/// How a feather's payload is protected beyond the Code 128 check symbol
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum IntegrityMode {
+    /// Only the mod-103 check symbol, which catches a single bad symbol
+    #[default]
+    Mod103Only,
+    /// A CRC-16 of the payload after the text, which catches most damage
+    /// to several symbols
+    ///
+    /// The mode is marked by FNC3 as the first data symbol, which Code 128
+    /// keeps for reader instructions, so plain data never begins with it.
+    /// The CRC is written after the text as FNC3 followed by three Code C
+    /// digit pairs, as sixteen bits need five digits.
+    Crc16,
+}
+
+/// Settings for `Feather::encode_with`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct EncodeOptions {
+    /// Protection added to the payload
+    pub integrity: IntegrityMode,
+}
+
+/// Text read by `Feather::decode_verified`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Verified {
+    /// The payload, without any CRC
+    pub text: String,
+    /// The protection found and checked
+    pub integrity: IntegrityMode,
+}
+
+/// Digits of the CRC written by `IntegrityMode::Crc16`
+const CRC_DIGITS: usize = 6;
+
+/// FNC3 in symbologies A and B
+const FNC3: u8 = 96;
+
+/// The CRC-16/CCITT-FALSE of `bytes`: polynomial `0x1021`, starting from
+/// `0xffff`
+///
+/// ```
+/// use feather_code::barcode::feather::crc16;
+///
+/// assert_eq!(crc16(b"123456789"), 0x29b1);
+/// ```
+pub fn crc16(bytes: &[u8]) -> u16 {
+    bytes.iter().fold(0xffff, |crc, &b| {
+        (0..8).fold(crc ^ (b as u16) << 8, |crc, _| {
+            if crc & 0x8000 != 0 { crc << 1 ^ 0x1021 } else { crc << 1 }
+        })
+    })
+}
+
+/// A Code 128 barcode drawn as a feather
+#[derive(Debug, PartialEq)]
+pub struct Feather(Code128Buf<Pattern>);
+
+impl Feather {
+    /// Draw a Code 128 barcode as a feather
+    pub fn new(code: Code128Buf<Pattern>) -> Feather {
+        Feather(code)
+    }
+
+    /// Encode ASCII text as a feather, failing like `Encode<Code128Buf>`
+    pub fn encode(text: &str) -> Result<Feather> {
+        Encode::<Code128Buf<Pattern>>::encode(text).map(Feather)
+    }
+
+    /// Encode arbitrary bytes as a feather, using FNC4 as `encode_bytes`
+    /// does
+    pub fn from_bytes(bytes: &[u8]) -> Result<Feather> {
+        Encode::<Code128Buf<Pattern>>::encode(bytes).map(Feather)
+    }
+
+    /// Encode ASCII text as a feather with the protection `options` asks
+    /// for
+    ///
+    /// ```
+    /// use feather_code::barcode::feather::{EncodeOptions, Feather, IntegrityMode};
+    ///
+    /// let options = EncodeOptions { integrity: IntegrityMode::Crc16 };
+    /// let feather = Feather::encode_with("HackFSU", &options).unwrap();
+    /// let verified = feather.decode_verified().unwrap();
+    ///
+    /// assert_eq!(verified.text, "HackFSU");
+    /// assert_eq!(verified.integrity, IntegrityMode::Crc16);
+    /// ```
+    pub fn encode_with(text: &str, options: &EncodeOptions) -> Result<Feather> {
+        let builder = match options.integrity {
+            IntegrityMode::Mod103Only => Code128Builder::new().text(text),
+            IntegrityMode::Crc16 => {
+                let crc = format!("{:06}", crc16(text.as_bytes()));
+                Code128Builder::new().fnc3().text(text).fnc3().push_c_digits(&crc)
+            }
+        };
+
+        builder.build().map(Feather)
+    }
+
+    /// Decode the text, checking and removing a CRC if there is one
+    ///
+    /// A leading FNC3 marks a CRC, which must then end the data as FNC3
+    /// followed by exactly six digits; without the mark, the data is all
+    /// text.  Fails like decoding the `code`, or with `DecodeErr` if the
+    /// CRC is missing or doesn't match the payload.
+    pub fn decode_verified(&self) -> Result<Verified> {
+        let (bytes, integrity) = self.verified_bytes()?;
+        let text = bytes.into_iter().map(char::from).collect();
+
+        Ok(Verified { text, integrity })
+    }
+
+    /// The payload bytes and the protection found, checking and removing
+    /// a CRC if there is one
+    fn verified_bytes(&self) -> Result<(Vec<u8>, IntegrityMode)> {
+        let items = self.0.as_code128().items()?;
+        let bytes = |items: &[Item]| -> Vec<u8> {
+            items.iter()
+                .filter_map(|item| match *item {
+                    Item::Byte(b) => Some(b),
+                    Item::Function { .. } => None,
+                })
+                .collect()
+        };
+
+        let items = match items.split_first() {
+            Some((&Item::Function { value: FNC3, .. }, rest)) => rest,
+            _ => return Ok((bytes(&items), IntegrityMode::Mod103Only)),
+        };
+
+        let split = items.len().saturating_sub(CRC_DIGITS + 1);
+        let (payload, trailer) = items.split_at(split);
+
+        let crc = match trailer.split_first() {
+            Some((&Item::Function { value: FNC3, .. }, digits))
+                if digits.len() == CRC_DIGITS => {
+                digits.iter().try_fold(0u32, |crc, item| match *item {
+                    Item::Byte(b) if b.is_ascii_digit() => Some(crc * 10 + (b - b'0') as u32),
+                    _ => None,
+                })
+            }
+            _ => None,
+        };
+
+        let bytes = bytes(payload);
+
+        match crc {
+            None => Err(Error::DecodeErr(String::from("missing CRC-16 after the payload"))),
+            Some(crc) if crc == crc16(&bytes) as u32 => Ok((bytes, IntegrityMode::Crc16)),
+            Some(_) => Err(Error::DecodeErr(String::from("CRC-16 does not match the payload"))),
+        }
+    }
+
+    /// The underlying Code 128 barcode
+    pub fn code(&self) -> &Code128Buf<Pattern> {
+        &self.0
+    }
+
+    /// Width of the grid in modules, including the quiet zones
+    pub fn width(&self) -> usize {
+        self.shaft().len()
+    }
+
+    /// Modules of the shaft row, `true` for dark
+    fn shaft(&self) -> Vec<bool> {
+        self.0.modules()
+    }
+
+    /// The grid of modules, row by row from the top, `true` for dark
+    pub fn modules(&self) -> Vec<Vec<bool>> {
+        let shaft = self.shaft();
+        let bars = shaft.len() - 2 * QUIET_ZONE;
+
+        (0..HEIGHT).map(|y| {
+            let from_shaft = VANE.abs_diff(y);
+
+            shaft.iter()
+                .enumerate()
+                .map(|(c, &dark)| {
+                    dark && from_shaft <= barb_len(c - QUIET_ZONE, bars)
+                })
+                .collect()
+        }).collect()
+    }
+
+    /// Read a feather code from a grid of modules, `true` for dark
+    ///
+    /// The symbols are read from the shaft, then every module is checked
+    /// against the layout they produce.  Fails with `BadFormat` if the grid
+    /// is not a feather code; the symbols themselves are not validated
+    /// until the feather is decoded.
+    pub fn from_modules(rows: &[Vec<bool>]) -> Result<Feather> {
+        if rows.len() != HEIGHT {
+            return Err(Error::BadFormat(format!("feather codes are {} modules tall, not {}",
+                                                HEIGHT, rows.len())));
+        }
+
+        let shaft = &rows[VANE];
+        let width = shaft.len();
+
+        if width < 2 * QUIET_ZONE || shaft[..QUIET_ZONE].contains(&true) ||
+           shaft[width - QUIET_ZONE..].contains(&true) {
+            return Err(Error::BadFormat(String::from("missing quiet zone")));
+        }
+
+        let bars = &shaft[QUIET_ZONE..width - QUIET_ZONE];
+        if bars.first() != Some(&true) {
+            return Err(Error::BadFormat(String::from("bars must start after the quiet zone")));
+        }
+
+        let mut runs = Vec::new();
+        let mut run = 0;
+        for (i, &dark) in bars.iter().enumerate() {
+            run += 1;
+            if bars.get(i + 1) != Some(&dark) {
+                runs.push(run);
+                run = 0;
+            }
+        }
+
+        if runs.len() % 6 != 1 || runs.last() != Some(&2) {
+            return Err(Error::BadFormat(String::from("missing final bar after stop symbol")));
+        }
+
+        let mut symbols = Vec::with_capacity(runs.len() / 6);
+        for (i, widths) in runs[..runs.len() - 1].chunks(6).enumerate() {
+            let mut w = [0; 6];
+            for (w, &run) in w.iter_mut().zip(widths) {
+                *w = if run <= 4 { run as u8 } else { 0 };
+            }
+
+            match Pattern::from_bar_widths(w) {
+                Some(p) => symbols.push(p),
+                None => {
+                    return Err(Error::BadFormat(format!("unrecognized bar widths for symbol {}",
+                                                        i)));
+                }
+            }
+        }
+
+        let feather = Feather(Code128Buf::new(symbols));
+        let expected = feather.modules();
+
+        for (y, (row, expected)) in rows.iter().zip(&expected).enumerate() {
+            if row.len() != width {
+                return Err(Error::BadFormat(format!("row {} is {} modules wide, not {}",
+                                                    y, row.len(), width)));
+            }
+
+            if let Some(x) = row.iter().zip(expected).position(|(a, b)| a != b) {
+                return Err(Error::BadFormat(format!("module at row {}, column {} is not part of \
+                                                     the feather",
+                                                    y, x)));
+            }
+        }
+
+        Ok(feather)
+    }
+}
+
+impl Format for Feather {
+    fn checksum(&self) -> bool {
+        self.0.checksum()
+    }
+}
+
+impl Decode<String> for Feather {
+    /// Decode the text like `decode_verified`, so a CRC is checked and
+    /// left out
+    fn decode(&self) -> Result<String> {
+        self.decode_verified().map(|verified| verified.text)
+    }
+}
+
+impl Decode<Vec<u8>> for Feather {
+    /// Decode the bytes, applying FNC4 and checking and leaving out a CRC
+    /// like `decode_verified`
+    fn decode(&self) -> Result<Vec<u8>> {
+        self.verified_bytes().map(|(bytes, _)| bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use barcode::code128::{self, rendered_width_modules};
+    use barcode::render::RenderOptions;
+
+    const PJJ123C: [u8; 10] = [103, 48, 42, 42, 17, 18, 19, 35, 54, 106];
+
+    fn pjj123c() -> Feather {
+        Feather::new(Code128Buf::new(PJJ123C.iter().map(|&u| Pattern::from(u)).collect()))
+    }
+
+    #[test]
+    fn round_trip_through_modules() {
+        let feather = Feather::encode("HackFSU 2024").unwrap();
+        let read = Feather::from_modules(&feather.modules()).unwrap();
+
+        assert_eq!(read, feather);
+        assert_eq!(read.decode(), Ok(String::from("HackFSU 2024")));
+    }
+
+    #[test]
+    fn round_trip_bytes() {
+        // All 256 bytes need more than MAX_SYMBOLS symbols
+        let bytes: Vec<u8> = (0..=255).collect();
+        let oversize = code128::EncodeOptions { allow_oversize: true };
+        let feather = Feather::new(code128::encode_with(&bytes, &oversize).unwrap());
+        let read = Feather::from_modules(&feather.modules()).unwrap();
+
+        assert_eq!(Decode::<Vec<u8>>::decode(&read), Ok(bytes));
+
+        let feather = Feather::from_bytes(b"caf\xe9").unwrap();
+        assert_eq!(Decode::<Vec<u8>>::decode(&feather), Ok(b"caf\xe9".to_vec()));
+    }
+
+    #[test]
+    fn crc_round_trip() {
+        let crc = EncodeOptions { integrity: IntegrityMode::Crc16 };
+
+        for text in &["HackFSU", "PJJ123C", "42184020500", "a"] {
+            let feather = Feather::encode_with(text, &crc).unwrap();
+            let read = Feather::from_modules(&feather.modules()).unwrap();
+
+            assert_eq!(read.decode_verified(),
+                       Ok(Verified { text: String::from(*text), integrity: IntegrityMode::Crc16 }));
+        }
+    }
+
+    #[test]
+    fn crc_absent_by_default() {
+        let feather = Feather::encode_with("HackFSU", &EncodeOptions::default()).unwrap();
+
+        assert_eq!(feather, Feather::encode("HackFSU").unwrap());
+        assert_eq!(feather.decode_verified(),
+                   Ok(Verified {
+                       text: String::from("HackFSU"),
+                       integrity: IntegrityMode::Mod103Only,
+                   }));
+    }
+
+    #[test]
+    fn crc_catches_what_mod103_misses() {
+        let crc = EncodeOptions { integrity: IntegrityMode::Crc16 };
+        let feather = Feather::encode_with("HackFSU", &crc).unwrap();
+        let mut symbols: Vec<u8> = feather.code().symbols().iter().map(|p| p.as_u8()).collect();
+
+        // Weighted 2 and 3 in the check symbol, so these cancel out
+        symbols[2] += 3;
+        symbols[3] -= 2;
+        let damaged = Feather::new(Code128Buf::new(symbols.into_iter().map(Pattern::from)
+                                                           .collect()));
+
+        let mismatch = Err(Error::DecodeErr(String::from("CRC-16 does not match the payload")));
+        assert!(damaged.checksum());
+        assert_eq!(Decode::<String>::decode(damaged.code()).unwrap()[..7], *"K_ckFSU");
+        assert_eq!(damaged.decode_verified(), mismatch);
+        assert_eq!(Decode::<String>::decode(&damaged), mismatch.map(|v: Verified| v.text));
+    }
+
+    #[test]
+    fn fnc3_in_plain_data_is_not_a_crc() {
+        let code = Code128Builder::new().text("LOT").fnc3().text("123456").build().unwrap();
+        let feather = Feather::new(code);
+
+        assert!(feather.checksum());
+        assert_eq!(feather.decode(), Ok(String::from("LOT123456")));
+        assert_eq!(feather.decode_verified().map(|v| v.integrity),
+                   Ok(IntegrityMode::Mod103Only));
+    }
+
+    #[test]
+    fn crc_mark_needs_a_crc() {
+        let code = Code128Builder::new().fnc3().text("LOT123456").build().unwrap();
+
+        assert_eq!(Feather::new(code).decode_verified(),
+                   Err(Error::DecodeErr(String::from("missing CRC-16 after the payload"))));
+    }
+
+    #[test]
+    fn decode_leaves_out_crc() {
+        let crc = EncodeOptions { integrity: IntegrityMode::Crc16 };
+        let feather = Feather::encode_with("HackFSU", &crc).unwrap();
+
+        assert_eq!(feather.decode(), Ok(String::from("HackFSU")));
+        assert_eq!(Decode::<Vec<u8>>::decode(&feather), Ok(b"HackFSU".to_vec()));
+        assert_eq!(Decode::<String>::decode(feather.code()).unwrap().len(), 13);
+    }
+
+    #[test]
+    fn grid_dimensions() {
+        let feather = pjj123c();
+        let modules = feather.modules();
+
+        assert_eq!(modules.len(), HEIGHT);
+        assert!(modules.iter().all(|row| row.len() == feather.width()));
+        assert_eq!(Some(feather.width()),
+                   rendered_width_modules("PJJ123C", &RenderOptions::default()));
+    }
+
+    #[test]
+    fn scan_band_is_plain_code128() {
+        let modules = pjj123c().modules();
+
+        for row in &modules[VANE - MIN_BARB..VANE + MIN_BARB + 1] {
+            assert_eq!(row, &modules[VANE]);
+        }
+        assert!(modules[0].contains(&true));
+        assert_ne!(modules[0], modules[VANE]);
+    }
+
+    #[test]
+    fn barbs_taper_to_the_ends() {
+        assert_eq!(barb_len(0, 112), MIN_BARB);
+        assert_eq!(barb_len(111, 112), MIN_BARB);
+        assert_eq!(barb_len(55, 112), VANE);
+        assert_eq!(barb_len(56, 112), VANE);
+        assert_eq!(barb_len(28, 112), 13);
+    }
+
+    #[test]
+    fn shaft_starts_with_start_symbol() {
+        let modules = pjj123c().modules();
+        let shaft = &modules[VANE][QUIET_ZONE..QUIET_ZONE + 11];
+
+        // Start A is 2 1 1 4 1 2
+        let expected = [true, true, false, true, false, false, false, false, true, false, false];
+        assert_eq!(shaft, expected);
+    }
+
+    #[test]
+    fn rejects_stray_module() {
+        let mut modules = pjj123c().modules();
+        modules[0][0] = true;
+
+        assert_eq!(Feather::from_modules(&modules),
+                   Err(Error::BadFormat(String::from("module at row 0, column 0 is not part of \
+                                                      the feather"))));
+    }
+
+    #[test]
+    fn rejects_plain_barcode() {
+        let modules = pjj123c().modules();
+        let plain: Vec<Vec<bool>> = (0..HEIGHT).map(|_| modules[VANE].clone()).collect();
+
+        assert!(Feather::from_modules(&plain).is_err());
+    }
+
+    #[test]
+    fn rejects_wrong_height() {
+        let modules = pjj123c().modules();
+
+        assert!(Feather::from_modules(&modules[1..]).is_err());
+    }
+
+    #[test]
+    fn rejects_bad_bar_widths() {
+        let mut modules = pjj123c().modules();
+        for row in &mut modules {
+            row[QUIET_ZONE + 1] = true;
+        }
+
+        assert!(Feather::from_modules(&modules).is_err());
+    }
+}
@@ -0,0 +1,324 @@
+//  This Source Code Form is subject to the terms of the Mozilla Public
+//  License, v. 2.0. If a copy of the MPL was not distributed with this
+//  file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Long texts split over several stacked Code 128 rows
+//!
+//! Each row is an ordinary Code 128 barcode starting in Code C.  Its first
+//! data symbol is a digit pair: the row's index, then the number of rows
+//! less one, so up to [`MAX_ROWS`](constant.MAX_ROWS.html) rows are
+//! possible.  The first row follows that with a second pair, the stack
+//! check, which ties the rows together: each byte of the whole text
+//! weighted by its position from one, summed modulo 100.  The rest of each
+//! row is the next piece of the text, encoded as usual.
+//!
+//! Rows can be read in any order; the headers put them back together.
+//!
+//! ```
+//! use feather_code::barcode::feather::stacked::StackedCode;
+//!
+//! let stacked = StackedCode::encode("HackFSU stacked feather codes", 12).unwrap();
+//! let mut rows = stacked.code128_rows();
+//! rows.reverse();
+//!
+//! assert_eq!(StackedCode::decode(&rows), Ok(String::from("HackFSU stacked feather codes")));
+//! ```
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Debug;
+
+use barcode::code128::{Code128, Code128Buf, Code128Builder, Encoding, Pattern};
+use barcode::format::{Decode, Error, Format, Result};
+
+/// Most rows a stack can have, as the row count is a single digit
+pub const MAX_ROWS: usize = 10;
+
+/// Code 128 rows making up one text
+#[derive(Debug, PartialEq)]
+pub struct StackedCode(Vec<Code128Buf<Pattern>>);
+
+/// The stack check of `text`
+fn stack_check(text: &[u8]) -> u8 {
+    let sum = text.iter()
+        .enumerate()
+        .fold(0, |acc, (i, &b)| (acc + (i % 100 + 1) * b as usize) % 100);
+
+    sum as u8
+}
+
+/// The row header and, for the first row, the stack check, as Code C digits
+fn header(index: usize, count: usize, check: u8) -> String {
+    if index == 0 {
+        format!("{}{}{:02}", index, count - 1, check)
+    } else {
+        format!("{}{}", index, count - 1)
+    }
+}
+
+impl StackedCode {
+    /// Split ASCII text over rows of at most `max_row_symbols` symbols,
+    /// counting each row's start, check, and stop symbols
+    ///
+    /// Each row takes as much of the text as fits.  Fails with `BadFormat`
+    /// for empty or non-ASCII text, rows too short to hold any text, or
+    /// text needing more than `MAX_ROWS` rows.
+    pub fn encode(text: &str, max_row_symbols: usize) -> Result<StackedCode> {
+        if let Some(c) = text.chars().find(|c| !c.is_ascii()) {
+            return Err(Error::BadFormat(format!("cannot encode non-ASCII character {:?}", c)));
+        }
+
+        if text.is_empty() {
+            return Err(Error::BadFormat(String::from("nothing to encode")));
+        }
+
+        let check = stack_check(text.as_bytes());
+
+        // Split first, then number the rows: the header is the same length
+        // whatever the count
+        let mut pieces = Vec::new();
+        let mut rest = text;
+        while !rest.is_empty() {
+            let index = pieces.len();
+            if index == MAX_ROWS {
+                return Err(Error::BadFormat(format!("text needs more than {} rows", MAX_ROWS)));
+            }
+
+            let fits = |n: usize| {
+                Code128Builder::new()
+                    .push_c_digits(&header(index, MAX_ROWS, check))
+                    .text(&rest[..n])
+                    .build()
+                    .is_ok_and(|row| row.symbols().len() <= max_row_symbols)
+            };
+
+            let mut n = 0;
+            while n < rest.len() && fits(n + 1) {
+                n += 1;
+            }
+
+            if n == 0 {
+                return Err(Error::BadFormat(format!("{} symbols is too few for a row",
+                                                    max_row_symbols)));
+            }
+
+            pieces.push(&rest[..n]);
+            rest = &rest[n..];
+        }
+
+        let count = pieces.len();
+        let rows = pieces.iter()
+            .enumerate()
+            .map(|(index, piece)| {
+                Code128Builder::new()
+                    .push_c_digits(&header(index, count, check))
+                    .text(piece)
+                    .build()
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(StackedCode(rows))
+    }
+
+    /// The rows, in order
+    pub fn rows(&self) -> &[Code128Buf<Pattern>] {
+        &self.0
+    }
+
+    /// The rows borrowed as `Code128`s, in order
+    pub fn code128_rows(&self) -> Vec<Code128<'_, Pattern>> {
+        self.0.iter().map(Code128Buf::as_code128).collect()
+    }
+
+    /// Put rows supplied in any order back together into the text
+    ///
+    /// Fails if a row doesn't decode, the rows disagree on their number,
+    /// a row is missing or repeated, or the stack check doesn't match.
+    pub fn decode<E: Encoding + Debug>(rows: &[Code128<E>]) -> Result<String> {
+        let mut pieces: Vec<Option<String>> = Vec::new();
+        let mut check = None;
+
+        for row in rows {
+            let text: String = row.decode()?;
+            let digits: Vec<u8> = text.bytes()
+                .take(2)
+                .filter(u8::is_ascii_digit)
+                .map(|b| b - b'0')
+                .collect();
+
+            let (index, count) = match digits[..] {
+                [index, last] => (index as usize, last as usize + 1),
+                _ => return Err(Error::BadFormat(String::from("row has no row header"))),
+            };
+
+            if pieces.is_empty() {
+                pieces.resize(count, None);
+            } else if pieces.len() != count {
+                return Err(Error::BadFormat(String::from("rows disagree on the row count")));
+            }
+
+            let mut piece = &text[2..];
+            if index == 0 {
+                match piece.get(..2).and_then(|c| c.parse::<u8>().ok()) {
+                    Some(c) => check = Some(c),
+                    None => return Err(Error::BadFormat(String::from("missing stack check"))),
+                }
+                piece = &piece[2..];
+            }
+
+            match pieces.get_mut(index) {
+                Some(slot @ None) => *slot = Some(String::from(piece)),
+                Some(Some(_)) => return Err(Error::BadFormat(format!("row {} repeated", index))),
+                None => {
+                    return Err(Error::BadFormat(format!("row {} of only {} rows", index, count)));
+                }
+            }
+        }
+
+        if pieces.is_empty() {
+            return Err(Error::BadFormat(String::from("no rows")));
+        }
+
+        let mut text = String::new();
+        for (index, piece) in pieces.iter().enumerate() {
+            match *piece {
+                Some(ref piece) => text.push_str(piece),
+                None => return Err(Error::BadFormat(format!("row {} missing", index))),
+            }
+        }
+
+        if check != Some(stack_check(text.as_bytes())) {
+            return Err(Error::BadFormat(String::from("stack check does not match")));
+        }
+
+        Ok(text)
+    }
+}
+
+impl Format for StackedCode {
+    /// Whether every row passes its own checksum
+    fn checksum(&self) -> bool {
+        self.0.iter().all(Code128Buf::checksum)
+    }
+}
+
+impl Decode<String> for StackedCode {
+    fn decode(&self) -> Result<String> {
+        StackedCode::decode(&self.code128_rows())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use barcode::code128::{weighted_checksum, Symbology};
+
+    const TEXT: &str = "Feather codes from HackFSU, stacked into rows 2024-10-16";
+
+    #[test]
+    fn rows_start_with_header() {
+        let stacked = StackedCode::encode("PJJ123C lot 42", 8).unwrap();
+        let count = stacked.rows().len();
+
+        for (index, row) in stacked.rows().iter().enumerate() {
+            assert!(row.checksum());
+            assert!(row.symbols().len() <= 8);
+            assert_eq!(row.start_symbology(), Some(Symbology::C));
+            assert_eq!(row.symbols()[1].as_u8() as usize, index * 10 + count - 1);
+        }
+    }
+
+    #[test]
+    fn round_trip_two_to_ten_rows() {
+        let mut seen = Vec::new();
+
+        for (len, max) in (40..TEXT.len()).step_by(4).flat_map(|l| (6..40).map(move |m| (l, m))) {
+            let text = &TEXT[..len];
+            let stacked = match StackedCode::encode(text, max) {
+                Ok(stacked) => stacked,
+                Err(_) => continue,
+            };
+            let count = stacked.rows().len();
+            seen.push(count);
+
+            assert_eq!(Decode::<String>::decode(&stacked), Ok(String::from(text)));
+
+            let mut rows = stacked.code128_rows();
+            rows.rotate_left(count / 2);
+            rows.swap(0, count - 1);
+            assert_eq!(StackedCode::decode(&rows), Ok(String::from(text)), "{} rows", count);
+        }
+
+        for count in 2..=MAX_ROWS {
+            assert!(seen.contains(&count), "no stack of {} rows", count);
+        }
+    }
+
+    #[test]
+    fn single_row() {
+        let stacked = StackedCode::encode("1234", 20).unwrap();
+
+        assert_eq!(stacked.rows().len(), 1);
+        assert_eq!(Decode::<String>::decode(&stacked), Ok(String::from("1234")));
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        assert!(StackedCode::encode("", 20).is_err());
+        assert!(StackedCode::encode("caf\u{e9}", 20).is_err());
+        assert_eq!(StackedCode::encode("abc", 5),
+                   Err(Error::BadFormat(String::from("5 symbols is too few for a row"))));
+        assert_eq!(StackedCode::encode(TEXT, 7),
+                   Err(Error::BadFormat(String::from("text needs more than 10 rows"))));
+    }
+
+    #[test]
+    fn rejects_corrupted_row_index() {
+        let stacked = StackedCode::encode(TEXT, 20).unwrap();
+        let mut rows: Vec<Vec<u8>> = stacked.rows().iter().map(|r| r.to_bytes()).collect();
+        let count = rows.len() as u8;
+
+        // Renumber the last row as row 0 and fix up its check symbol, so
+        // the row itself is still valid
+        let last = rows.last_mut().unwrap();
+        last[1] = count - 1;
+        let n = last.len();
+        last[n - 2] = weighted_checksum(Symbology::C, &last[1..n - 2]);
+
+        let codes: Vec<Code128<u8>> = rows.iter().map(|r| Code128::new(r)).collect();
+        assert!(codes.iter().all(Format::checksum));
+        assert!(StackedCode::decode(&codes).is_err());
+
+        // Damaging the header without fixing the check fails the row
+        let mut rows: Vec<Vec<u8>> = stacked.rows().iter().map(|r| r.to_bytes()).collect();
+        rows[1][1] += 10;
+        let codes: Vec<Code128<u8>> = rows.iter().map(|r| Code128::new(r)).collect();
+        assert!(StackedCode::decode(&codes).is_err());
+    }
+
+    #[test]
+    fn rejects_missing_and_reordered_text() {
+        let stacked = StackedCode::encode(TEXT, 20).unwrap();
+        let rows = stacked.code128_rows();
+
+        assert_eq!(StackedCode::decode(&rows[1..]),
+                   Err(Error::BadFormat(String::from("row 0 missing"))));
+        assert_eq!(StackedCode::decode(&[rows[0].clone(), rows[0].clone()]),
+                   Err(Error::BadFormat(String::from("row 0 repeated"))));
+        assert_eq!(StackedCode::decode::<Pattern>(&[]),
+                   Err(Error::BadFormat(String::from("no rows"))));
+    }
+
+    #[test]
+    fn stack_check_catches_swapped_pieces() {
+        let a = StackedCode::encode("abcdefgh", 7).unwrap();
+        let b = StackedCode::encode("efghabcd", 7).unwrap();
+        assert_eq!(a.rows().len(), b.rows().len());
+
+        // Row 0 of one stack with the other rows of another
+        let mut rows = b.code128_rows();
+        rows[0] = a.code128_rows()[0].clone();
+
+        assert!(StackedCode::decode(&rows).is_err());
+    }
+}
@@ -0,0 +1,101 @@
+//  This Source Code Form is subject to the terms of the Mozilla Public
+//  License, v. 2.0. If a copy of the MPL was not distributed with this
+//  file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! C interface to the Code 128 decoder
+//!
+//! Barcodes are passed as arrays of symbol values.  The declarations are
+//! in `include/feather_code.h`, which must be kept in step with this
+//! module.
+//!
+//! Requires the `ffi` feature.
+
+use alloc::string::String;
+use core::{ptr, slice};
+
+use barcode::code128::Code128;
+use barcode::format::{Decode, Format};
+
+/// The call succeeded
+pub const FEATHER_CODE_OK: i32 = 0;
+
+/// A required pointer was null
+pub const FEATHER_CODE_ERR_NULL: i32 = -1;
+
+/// The symbols are not a valid barcode
+pub const FEATHER_CODE_ERR_DECODE: i32 = -2;
+
+/// The output buffer is too small for the decoded text
+pub const FEATHER_CODE_ERR_BUFFER: i32 = -3;
+
+/// Decode `len` symbol values at `data` into the buffer at `out`
+///
+/// On entry `*out_len` is the size of the buffer; on return it is the
+/// length in bytes of the decoded text, which is valid UTF-8.  Characters
+/// extended by FNC4 decode to Latin-1 characters above 127, which take two
+/// bytes each, so the text may be longer than the symbols.  No terminating
+/// NUL is written.  Returns `FEATHER_CODE_OK`, or:
+///
+/// * `FEATHER_CODE_ERR_NULL` if `data` or `out_len` is null, or `out` is
+///   null with a nonzero `*out_len`
+/// * `FEATHER_CODE_ERR_DECODE` if the symbols don't decode, leaving
+///   `*out_len` unchanged
+/// * `FEATHER_CODE_ERR_BUFFER` if the text is longer than `*out_len`, in
+///   which case `*out_len` is set to the length needed and nothing is
+///   written
+///
+/// Passing a null `out` with `*out_len` of zero queries the length.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes, `out_len` to a writable
+/// `usize`, and `out`, unless null, to `*out_len` writable bytes not
+/// overlapping `data`.
+#[no_mangle]
+pub unsafe extern "C" fn feather_code_decode(data: *const u8,
+                                             len: usize,
+                                             out: *mut u8,
+                                             out_len: *mut usize)
+                                             -> i32 {
+    if data.is_null() || out_len.is_null() || (out.is_null() && *out_len != 0) {
+        return FEATHER_CODE_ERR_NULL;
+    }
+
+    let symbols = slice::from_raw_parts(data, len);
+    let text: String = match Code128::new(symbols).decode() {
+        Ok(text) => text,
+        Err(_) => return FEATHER_CODE_ERR_DECODE,
+    };
+
+    let capacity = *out_len;
+    *out_len = text.len();
+
+    if text.len() > capacity {
+        return FEATHER_CODE_ERR_BUFFER;
+    }
+
+    if !text.is_empty() {
+        ptr::copy_nonoverlapping(text.as_ptr(), out, text.len());
+    }
+
+    FEATHER_CODE_OK
+}
+
+/// Check `len` symbol values at `data`
+///
+/// Returns `1` if they form a barcode with a matching check symbol, `0` if
+/// not, and `FEATHER_CODE_ERR_NULL` if `data` is null.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn feather_code_checksum(data: *const u8, len: usize) -> i32 {
+    if data.is_null() {
+        return FEATHER_CODE_ERR_NULL;
+    }
+
+    let symbols = slice::from_raw_parts(data, len);
+
+    if Code128::new(symbols).checksum() { 1 } else { 0 }
+}
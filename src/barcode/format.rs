@@ -0,0 +1,512 @@
+//  This Source Code Form is subject to the terms of the Mozilla Public
+//  License, v. 2.0. If a copy of the MPL was not distributed with this
+//  file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Traits and errors shared by every barcode format
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::result;
+use core::str::FromStr;
+
+#[cfg(feature = "serde")]
+use serde::{de, Deserialize, Deserializer, Serialize};
+
+use barcode::code128::Symbology;
+
+/// `BadSymbolAt` context for a symbol in the body of the data
+pub const IN_DATA: &str = "data symbol";
+
+/// `BadSymbolAt` context for the symbol following a shift
+pub const AFTER_SHIFT: &str = "shifted character";
+
+/// `BadSymbolAt` context for a function code where they are rejected
+pub const FUNCTION_CODE: &str = "symbol where function codes are rejected";
+
+/// Errors produced while validating or decoding a barcode
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Error {
+    /// The symbols are not laid out as the format requires
+    BadFormat(String),
+    /// A symbol could not be interpreted
+    DecodeErr(String),
+    /// The content is too long, too short, or otherwise the wrong length
+    /// for the format
+    InvalidLength(String),
+    /// The symbol at `index` has no meaning where it appears
+    ///
+    /// Indices count data symbols, from `0` for the symbol after the
+    /// start symbol.
+    BadSymbolAt {
+        /// Position of the offending symbol
+        index: usize,
+        /// Value of the offending symbol
+        value: u8,
+        /// Where the symbol appeared, such as `IN_DATA` or `AFTER_SHIFT`
+        // Spelled out so serde doesn't borrow it from the input
+        #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_context"))]
+        context: &'static ::core::primitive::str,
+    },
+    /// The symbol at `index` tries to change symbology where it can't,
+    /// such as a start symbol among the data or a switch straight after a
+    /// shift
+    ///
+    /// Indices count data symbols as for `BadSymbolAt`.
+    BadTransitionAt {
+        /// Position of the offending symbol
+        index: usize,
+        /// Symbology active at the symbol
+        from: Symbology,
+        /// Value of the offending symbol
+        to: u8,
+    },
+}
+
+/// Map a deserialized `BadSymbolAt` context back onto the known contexts
+#[cfg(feature = "serde")]
+fn deserialize_context<'de, D: Deserializer<'de>>(deserializer: D)
+                                                  -> result::Result<&'static str, D::Error> {
+    let context = String::deserialize(deserializer)?;
+
+    [IN_DATA, AFTER_SHIFT, FUNCTION_CODE].iter()
+        .find(|&&known| known == context)
+        .cloned()
+        .ok_or_else(|| de::Error::custom(format!("unknown context {:?}", context)))
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::BadFormat(ref msg) => write!(f, "bad format: {}", msg),
+            Error::DecodeErr(ref msg) => write!(f, "decode error: {}", msg),
+            Error::InvalidLength(ref msg) => write!(f, "invalid length: {}", msg),
+            Error::BadSymbolAt { index, value, context } => {
+                write!(f, "symbol {} ({}) is not a valid {}", index, value, context)
+            }
+            Error::BadTransitionAt { index, from, to } => {
+                write!(f, "symbol {} ({}) cannot switch from symbology {:?}", index, to, from)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl ::std::error::Error for Error {}
+
+/// Result type for barcode operations
+pub type Result<T> = result::Result<T, Error>;
+
+/// What `Format::checksum_status` found
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Checksum {
+    /// The check digits match the contents
+    Valid,
+    /// The check digits don't match, or the barcode is too malformed to
+    /// check
+    Invalid,
+    /// The barcode carries no check
+    NotApplicable,
+}
+
+/// A barcode format, usually with an integrity check
+pub trait Format {
+    /// Check whether the barcode's check digit matches its contents
+    ///
+    /// For barcodes without a check, this is only whether the barcode is
+    /// well formed.
+    fn checksum(&self) -> bool;
+
+    /// Whether the barcode carries a check at all
+    ///
+    /// True unless overridden; Pharmacode, and Code 39, Codabar, and MSI
+    /// barcodes built without check digits, return false.
+    fn has_checksum(&self) -> bool {
+        true
+    }
+
+    /// Tell a matching check apart from a missing one
+    ///
+    /// ```
+    /// use feather_code::barcode::code39::{Code39, Code39Symbol};
+    /// use feather_code::barcode::format::{Checksum, Format};
+    ///
+    /// let symbols: Vec<Code39Symbol> = "*CODE39W*".chars()
+    ///     .map(|c| Code39Symbol::from_char(c).unwrap())
+    ///     .collect();
+    ///
+    /// assert_eq!(Code39::with_check(&symbols).checksum_status(), Checksum::Valid);
+    /// assert_eq!(Code39::new(&symbols).checksum_status(), Checksum::NotApplicable);
+    /// ```
+    fn checksum_status(&self) -> Checksum {
+        if !self.has_checksum() {
+            Checksum::NotApplicable
+        } else if self.checksum() {
+            Checksum::Valid
+        } else {
+            Checksum::Invalid
+        }
+    }
+}
+
+/// A barcode that can be decoded into a `T`
+pub trait Decode<T>: Format {
+    /// Decode the barcode
+    fn decode(&self) -> Result<T>;
+}
+
+impl<T: Format + ?Sized> Format for &T {
+    fn checksum(&self) -> bool {
+        (**self).checksum()
+    }
+
+    fn has_checksum(&self) -> bool {
+        (**self).has_checksum()
+    }
+}
+
+impl<T: Decode<U> + ?Sized, U> Decode<U> for &T {
+    /// Decode the referenced barcode, so references work wherever barcodes do
+    ///
+    /// ```
+    /// use feather_code::barcode::code128::Code128;
+    /// use feather_code::barcode::format::{Decode, Result};
+    ///
+    /// let symbols: [u8; 10] = [103, 48, 42, 42, 17, 18, 19, 35, 54, 106];
+    /// let codes = [Code128::new(&symbols)];
+    ///
+    /// let texts: Vec<Result<String>> = codes.iter().map(|c| c.decode()).collect();
+    /// assert_eq!(texts, [Ok(String::from("PJJ123C"))]);
+    /// ```
+    fn decode(&self) -> Result<U> {
+        (**self).decode()
+    }
+}
+
+/// A value that can be encoded as a barcode of type `T`
+pub trait Encode<T> {
+    /// Encode the value
+    fn encode(&self) -> Result<T>;
+}
+
+/// Decode a barcode's text and parse it into any `FromStr` type
+///
+/// Implemented for every barcode that decodes to a `String`, so numeric
+/// codes can be read straight into a number:
+///
+/// ```
+/// use feather_code::barcode::code128::Code128;
+/// use feather_code::barcode::format::DecodeAs;
+///
+/// let symbols: [u8; 6] = [105, 12, 34, 56, 44, 106];
+/// let qty: u32 = Code128::new(&symbols).decode_as().unwrap();
+/// assert_eq!(qty, 123456);
+/// ```
+pub trait DecodeAs {
+    /// Decode the barcode, then parse the text with `T::from_str`
+    ///
+    /// A parse failure is reported as `DecodeErr` carrying both the decoded
+    /// text and the message of the underlying parse error.
+    fn decode_as<T>(&self) -> Result<T>
+    where
+        T: FromStr,
+        T::Err: fmt::Display;
+}
+
+impl<D: Decode<String> + ?Sized> DecodeAs for D {
+    fn decode_as<T>(&self) -> Result<T>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        let text = self.decode()?;
+
+        text.parse().map_err(|e| {
+            Error::DecodeErr(format!("unable to parse {:?}: {}", text, e))
+        })
+    }
+}
+
+/// Barcode formats, as told apart by `AutoDetect` from symbol values and
+/// by [`detect`](../detect/fn.detect.html) from modules
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BarcodeFormat {
+    /// Code 128: a start symbol first and the stop symbol last
+    Code128,
+    /// Code 39: `*` delimiters, valued 43, at both ends
+    Code39,
+    /// Code 93, which only `detect` tells apart
+    Code93,
+    /// EAN-13: exactly thirteen digits
+    EAN13,
+    /// Interleaved 2 of 5: an even number of digits
+    ITF,
+    /// MSI, which only `detect` tells apart
+    Msi,
+    /// Pharmacode, which only `detect` tells apart
+    Pharmacode,
+    /// A format added with `AutoDetect::register_detector`, by name
+    Other(&'static str),
+}
+
+/// A function recognizing a barcode format from raw symbol values
+pub type Detector = fn(&[u8]) -> Option<BarcodeFormat>;
+
+/// Guesses the format of raw symbol values, as a scanner reports them
+///
+/// Only the framing and length are looked at, not the check digits, so a
+/// guess is a hint for which decoder to try rather than a guarantee.
+///
+/// ```
+/// use feather_code::barcode::format::{AutoDetect, BarcodeFormat};
+///
+/// let symbols = [103, 48, 42, 42, 17, 18, 19, 35, 54, 106];
+/// assert_eq!(AutoDetect::detect(&symbols), Some(BarcodeFormat::Code128));
+///
+/// let isbn = [9, 7, 8, 3, 1, 6, 1, 4, 8, 4, 1, 0, 0];
+/// assert_eq!(AutoDetect::detect(&isbn), Some(BarcodeFormat::EAN13));
+/// ```
+///
+/// Other formats can be recognized by registering detectors, which are
+/// tried before the built-in ones:
+///
+/// ```
+/// use feather_code::barcode::format::{AutoDetect, BarcodeFormat};
+///
+/// fn upc_a(data: &[u8]) -> Option<BarcodeFormat> {
+///     if data.len() == 12 && data.iter().all(|&d| d <= 9) {
+///         Some(BarcodeFormat::Other("UPC-A"))
+///     } else {
+///         None
+///     }
+/// }
+///
+/// let mut detect = AutoDetect::new();
+/// detect.register_detector(upc_a);
+///
+/// assert_eq!(detect.identify(&[0; 12]), Some(BarcodeFormat::Other("UPC-A")));
+/// assert_eq!(AutoDetect::detect(&[0; 12]), Some(BarcodeFormat::ITF));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct AutoDetect {
+    detectors: Vec<Detector>,
+}
+
+impl AutoDetect {
+    /// A detector knowing only the built-in formats
+    pub fn new() -> AutoDetect {
+        AutoDetect::default()
+    }
+
+    /// Guess the format with the built-in detectors alone
+    pub fn detect(data: &[u8]) -> Option<BarcodeFormat> {
+        BUILT_IN.iter().filter_map(|detector| detector(data)).next()
+    }
+
+    /// Try `detector` before the built-in detectors, after any registered
+    /// earlier
+    pub fn register_detector(&mut self, detector: Detector) -> &mut AutoDetect {
+        self.detectors.push(detector);
+        self
+    }
+
+    /// Guess the format with the registered detectors, then the built-in
+    /// ones
+    pub fn identify(&self, data: &[u8]) -> Option<BarcodeFormat> {
+        self.detectors.iter()
+            .filter_map(|detector| detector(data))
+            .next()
+            .or_else(|| AutoDetect::detect(data))
+    }
+}
+
+/// The built-in detectors, most distinctive framing first
+static BUILT_IN: [Detector; 4] = [detect_code128, detect_code39, detect_ean13, detect_itf];
+
+fn detect_code128(data: &[u8]) -> Option<BarcodeFormat> {
+    match (data.first(), data.last()) {
+        (Some(103..=105), Some(106)) if data.len() >= 3 => Some(BarcodeFormat::Code128),
+        _ => None,
+    }
+}
+
+fn detect_code39(data: &[u8]) -> Option<BarcodeFormat> {
+    let framed = data.len() >= 2 && data[0] == 43 && data[data.len() - 1] == 43;
+
+    if framed && data.iter().all(|&s| s <= 43) {
+        Some(BarcodeFormat::Code39)
+    } else {
+        None
+    }
+}
+
+fn detect_ean13(data: &[u8]) -> Option<BarcodeFormat> {
+    if data.len() == 13 && data.iter().all(|&d| d <= 9) {
+        Some(BarcodeFormat::EAN13)
+    } else {
+        None
+    }
+}
+
+fn detect_itf(data: &[u8]) -> Option<BarcodeFormat> {
+    if !data.is_empty() && data.len().is_multiple_of(2) && data.iter().all(|&d| d <= 9) {
+        Some(BarcodeFormat::ITF)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn error_display() {
+        assert_eq!(Error::BadFormat(String::from("missing stop symbol")).to_string(),
+                   "bad format: missing stop symbol");
+        assert_eq!(Error::DecodeErr(String::from("unable to parse \"x\"")).to_string(),
+                   "decode error: unable to parse \"x\"");
+        assert_eq!(Error::InvalidLength(String::from("odd number of digits")).to_string(),
+                   "invalid length: odd number of digits");
+        assert_eq!(Error::BadSymbolAt { index: 4, value: 106, context: IN_DATA }.to_string(),
+                   "symbol 4 (106) is not a valid data symbol");
+        assert_eq!(Error::BadTransitionAt { index: 2, from: Symbology::A, to: 99 }.to_string(),
+                   "symbol 2 (99) cannot switch from symbology A");
+    }
+
+    #[test]
+    fn references_decode() {
+        use barcode::code128::Code128;
+
+        let symbols = [103u8, 48, 42, 42, 17, 18, 19, 35, 54, 106];
+        let code = Code128::new(&symbols);
+        let by_ref = &&code;
+
+        fn text<D: Decode<String>>(d: D) -> Result<String> {
+            d.decode()
+        }
+
+        assert!(by_ref.checksum());
+        assert_eq!(text(by_ref), Ok(String::from("PJJ123C")));
+        assert_eq!(Decode::<String>::decode(by_ref), Ok(String::from("PJJ123C")));
+        assert_eq!(text(&code), text(code));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn error_into_box_dyn_error() {
+        use std::boxed::Box;
+        use std::error;
+
+        fn parse() -> ::std::result::Result<(), Box<dyn error::Error>> {
+            Err(Error::BadFormat(String::from("missing start symbol")))?
+        }
+
+        assert_eq!(parse().unwrap_err().to_string(), "bad format: missing start symbol");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn error_serde_round_trip() {
+        let errs = [
+            Error::DecodeErr(String::from("unrecognized encoding 106")),
+            Error::InvalidLength(String::from("233 symbols")),
+            Error::BadSymbolAt { index: 3, value: 106, context: AFTER_SHIFT },
+            Error::BadTransitionAt { index: 0, from: Symbology::C, to: 104 },
+        ];
+
+        for err in &errs {
+            let json = ::serde_json::to_string(err).unwrap();
+            assert_eq!(&::serde_json::from_str::<Error>(&json).unwrap(), err);
+
+            let bytes = ::bincode::serialize(err).unwrap();
+            assert_eq!(&::bincode::deserialize::<Error>(&bytes).unwrap(), err);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn error_deserialize_rejects_unknown_context() {
+        let json = r#"{"BadSymbolAt":{"index":0,"value":106,"context":"elsewhere"}}"#;
+
+        assert!(::serde_json::from_str::<Error>(json).is_err());
+    }
+
+    #[test]
+    fn detect_builtin_formats() {
+        assert_eq!(AutoDetect::detect(&[103, 48, 42, 42, 17, 18, 19, 35, 54, 106]),
+                   Some(BarcodeFormat::Code128));
+        assert_eq!(AutoDetect::detect(&[105, 12, 34, 56, 44, 106]),
+                   Some(BarcodeFormat::Code128));
+        assert_eq!(AutoDetect::detect(&[43, 12, 24, 13, 14, 3, 9, 43]),
+                   Some(BarcodeFormat::Code39));
+        assert_eq!(AutoDetect::detect(&[9, 7, 8, 3, 1, 6, 1, 4, 8, 4, 1, 0, 0]),
+                   Some(BarcodeFormat::EAN13));
+        assert_eq!(AutoDetect::detect(&[1, 5, 4, 0, 0, 1, 4, 1, 2, 8, 8, 7, 6, 3]),
+                   Some(BarcodeFormat::ITF));
+    }
+
+    #[test]
+    fn detect_nothing_for_unframed_data() {
+        assert_eq!(AutoDetect::detect(&[]), None);
+        assert_eq!(AutoDetect::detect(&[103, 106]), None);
+        assert_eq!(AutoDetect::detect(&[103, 48, 42]), None);
+        assert_eq!(AutoDetect::detect(&[1, 2, 3]), None);
+        assert_eq!(AutoDetect::detect(&[43, 44, 43]), None);
+    }
+
+    #[test]
+    fn registered_detectors_come_first() {
+        fn anything(_: &[u8]) -> Option<BarcodeFormat> {
+            Some(BarcodeFormat::Other("anything"))
+        }
+        fn nothing(_: &[u8]) -> Option<BarcodeFormat> {
+            None
+        }
+
+        let mut detect = AutoDetect::new();
+        assert_eq!(detect.identify(&[0; 13]), Some(BarcodeFormat::EAN13));
+
+        detect.register_detector(nothing);
+        assert_eq!(detect.identify(&[0; 13]), Some(BarcodeFormat::EAN13));
+
+        detect.register_detector(anything);
+        assert_eq!(detect.identify(&[0; 13]), Some(BarcodeFormat::Other("anything")));
+    }
+
+    #[test]
+    fn checksum_status_tells_missing_checks_apart() {
+        use barcode::code128::{Code128Buf, Pattern};
+        use barcode::codabar::{self, CodabarBuf};
+        use barcode::msi::{self, CheckScheme};
+        use barcode::pharmacode::PharmacodeBuf;
+
+        // Each code is passed by reference, so this also goes through the
+        // forwarding impl for &T
+        fn status<F: Format>(code: F) -> Checksum {
+            code.checksum_status()
+        }
+
+        let code128: Code128Buf<Pattern> = "PJJ123C".encode().unwrap();
+        let mut symbols = code128.to_bytes();
+        symbols[1] += 1;
+        let broken = Code128Buf::from_bytes(&symbols).unwrap();
+        let pharmacode: PharmacodeBuf = 1234u32.encode().unwrap();
+        let unchecked_msi = msi::encode_with("1234", CheckScheme::None).unwrap();
+        let checked_msi = msi::encode_with("1234", CheckScheme::Mod11).unwrap();
+        let unchecked_codabar: CodabarBuf = "40156".encode().unwrap();
+        let options = codabar::EncodeOptions { check: true, ..codabar::EncodeOptions::default() };
+        let checked_codabar = codabar::encode_with("40156", &options).unwrap();
+
+        assert_eq!(status(&code128), Checksum::Valid);
+        assert_eq!(status(&broken), Checksum::Invalid);
+        assert_eq!(status(&pharmacode), Checksum::NotApplicable);
+        assert_eq!(status(&unchecked_msi), Checksum::NotApplicable);
+        assert_eq!(status(&checked_msi), Checksum::Valid);
+        assert_eq!(status(&unchecked_codabar), Checksum::NotApplicable);
+        assert_eq!(status(&checked_codabar), Checksum::Valid);
+        assert!(pharmacode.checksum() && unchecked_msi.checksum());
+    }
+}
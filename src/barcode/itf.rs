@@ -0,0 +1,149 @@
+//  This Source Code Form is subject to the terms of the Mozilla Public
+//  License, v. 2.0. If a copy of the MPL was not distributed with this
+//  file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Interleaved 2 of 5 barcodes
+//!
+//! An ITF barcode is an even number of digits, drawn in pairs: the first
+//! digit of each pair in the bars and the second in the spaces between
+//! them.  The last digit is a check digit.  ITF-14, the fourteen digit
+//! form, carries the GTIN of a shipping carton.
+//!
+//! ```
+//! use feather_code::barcode::itf::ITF;
+//! use feather_code::barcode::format::{Decode, Format};
+//!
+//! let digits: [u8; 14] = [1, 5, 4, 0, 0, 1, 4, 1, 2, 8, 8, 7, 6, 3];
+//! let code = ITF::new(&digits);
+//!
+//! assert!(code.checksum());
+//! assert_eq!(code.decode(), Ok(String::from("15400141288763")));
+//! ```
+
+use alloc::string::String;
+
+use barcode::format::{Decode, Error, Format, Result, IN_DATA};
+
+/// An ITF barcode borrowing its digits, each a value in `0..=9`, the check
+/// digit last
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ITF<'a>(&'a [u8]);
+
+/// The check digit for the digits before it
+///
+/// Digits are weighted 3 and 1 alternately from the right, starting with 3
+/// next to the check digit; the check digit brings their sum up to a
+/// multiple of ten.
+///
+/// ```
+/// use feather_code::barcode::itf::check_digit;
+///
+/// assert_eq!(check_digit(&[1, 5, 4, 0, 0, 1, 4, 1, 2, 8, 8, 7, 6]), 3);
+/// ```
+pub fn check_digit(digits: &[u8]) -> u8 {
+    let sum: usize = digits.iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| if i % 2 == 0 { 3 * d as usize } else { d as usize })
+        .sum();
+
+    ((10 - sum % 10) % 10) as u8
+}
+
+impl<'a> ITF<'a> {
+    /// Wrap digits, the check digit last
+    pub fn new(digits: &'a [u8]) -> ITF<'a> {
+        ITF(digits)
+    }
+
+    /// The digits, as given
+    pub fn digits(&self) -> &'a [u8] {
+        self.0
+    }
+
+    /// Fail with `InvalidLength` unless there is a nonzero, even number of
+    /// digits, and with `BadSymbolAt` unless they are all in `0..=9`
+    fn check(&self) -> Result<()> {
+        if self.0.is_empty() || !self.0.len().is_multiple_of(2) {
+            return Err(Error::InvalidLength(format!("ITF needs an even number of digits, not {}",
+                                                    self.0.len())));
+        }
+
+        match self.0.iter().position(|&d| d > 9) {
+            Some(index) => {
+                Err(Error::BadSymbolAt { index, value: self.0[index], context: IN_DATA })
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl<'a> Format for ITF<'a> {
+    /// Whether the barcode is an even number of digits ending in the right
+    /// check digit
+    fn checksum(&self) -> bool {
+        let n = self.0.len();
+
+        self.check().is_ok() && check_digit(&self.0[..n - 1]) == self.0[n - 1]
+    }
+}
+
+impl<'a> Decode<String> for ITF<'a> {
+    /// The digits as text, check digit included
+    fn decode(&self) -> Result<String> {
+        self.check()?;
+
+        if !self.checksum() {
+            return Err(Error::BadFormat(String::from("check digit does not match")));
+        }
+
+        Ok(self.0.iter().map(|&d| char::from(b'0' + d)).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ITF14: [u8; 14] = [1, 5, 4, 0, 0, 1, 4, 1, 2, 8, 8, 7, 6, 3];
+
+    #[test]
+    fn itf_14() {
+        let code = ITF::new(&ITF14);
+
+        assert!(code.checksum());
+        assert_eq!(code.decode(), Ok(String::from("15400141288763")));
+    }
+
+    #[test]
+    fn short_codes() {
+        // "1234567" checks to 0
+        let digits = [1, 2, 3, 4, 5, 6, 7, 0];
+
+        assert_eq!(ITF::new(&digits).decode(), Ok(String::from("12345670")));
+        assert_eq!(ITF::new(&[0, 0]).decode(), Ok(String::from("00")));
+    }
+
+    #[test]
+    fn rejects_odd_length() {
+        let code = ITF::new(&ITF14[..13]);
+
+        assert!(!code.checksum());
+        let msg = "ITF needs an even number of digits, not 13";
+        assert_eq!(code.decode(), Err(Error::InvalidLength(String::from(msg))));
+        let msg = "ITF needs an even number of digits, not 0";
+        assert_eq!(ITF::new(&[]).decode(), Err(Error::InvalidLength(String::from(msg))));
+    }
+
+    #[test]
+    fn rejects_bad_digits() {
+        let mut digits = ITF14;
+        digits[13] = 4;
+        assert_eq!(ITF::new(&digits).decode(),
+                   Err(Error::BadFormat(String::from("check digit does not match"))));
+
+        digits[2] = 10;
+        assert_eq!(ITF::new(&digits).decode(),
+                   Err(Error::BadSymbolAt { index: 2, value: 10, context: IN_DATA }));
+    }
+}
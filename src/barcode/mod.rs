@@ -0,0 +1,23 @@
+//  This Source Code Form is subject to the terms of the Mozilla Public
+//  License, v. 2.0. If a copy of the MPL was not distributed with this
+//  file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Barcode formats and the traits they share
+
+pub mod codabar;
+pub mod code128;
+pub mod code39;
+pub mod code93;
+pub mod detect;
+pub mod ean13;
+pub mod feather;
+pub mod itf;
+pub mod msi;
+pub mod pharmacode;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod format;
+pub mod render;
+pub mod testing;
+#[cfg(feature = "wasm")]
+pub mod wasm;
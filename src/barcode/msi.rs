@@ -0,0 +1,504 @@
+//  This Source Code Form is subject to the terms of the Mozilla Public
+//  License, v. 2.0. If a copy of the MPL was not distributed with this
+//  file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! MSI Plessey barcodes
+//!
+//! MSI, found on warehouse and shelf labels, writes only digits, each as
+//! its four bits from the highest: a one is a wide bar and a narrow space,
+//! a zero a narrow bar and a wide space.  One or two check digits are
+//! usual, computed by whichever [`CheckScheme`](enum.CheckScheme.html) the
+//! labels use, so the barcode must say which that is.
+//!
+//! ```
+//! use feather_code::barcode::msi::{CheckScheme, MsiBuf};
+//! use feather_code::barcode::format::{Decode, Encode, Format};
+//!
+//! let code: MsiBuf = "1234567".encode().unwrap();
+//!
+//! assert_eq!(code.digits(), [1, 2, 3, 4, 5, 6, 7, 4]);
+//! assert_eq!(code.scheme(), CheckScheme::Mod10);
+//! assert!(code.checksum());
+//! assert_eq!(code.decode(), Ok(String::from("1234567")));
+//! ```
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use barcode::format::{Decode, Encode, Error, Format, Result, IN_DATA};
+use barcode::render::{self, QUIET_ZONE};
+
+/// Modules of the start pattern, drawn like a one bit
+const START: [bool; 3] = ONE;
+
+/// Modules of a one bit: a wide bar and a narrow space
+const ONE: [bool; 3] = [true, true, false];
+
+/// Modules of a zero bit: a narrow bar and a wide space
+const ZERO: [bool; 3] = [true, false, false];
+
+/// Modules of the stop pattern: a narrow bar, a wide space, and a narrow
+/// bar
+const STOP: [bool; 4] = [true, false, false, true];
+
+/// How the check digits of an MSI barcode are computed
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum CheckScheme {
+    /// No check digit
+    None,
+    /// One Luhn check digit, the usual choice
+    #[default]
+    Mod10,
+    /// One check digit weighting the digits 2 to 7 from the right, modulo
+    /// 11
+    Mod11,
+    /// Two Luhn check digits, the second covering the first
+    Mod1010,
+    /// A modulo 11 check digit, then a Luhn check digit covering it
+    Mod1110,
+}
+
+/// The Luhn check digit for `digits`: every other digit doubled from the
+/// rightmost, the digits of the results summed, and the check digit
+/// bringing the sum up to a multiple of ten
+///
+/// ```
+/// use feather_code::barcode::msi::mod10;
+///
+/// assert_eq!(mod10(&[1, 2, 3, 4, 5, 6, 7]), 4);
+/// ```
+pub fn mod10(digits: &[u8]) -> u8 {
+    let sum: usize = digits.iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| if i % 2 == 0 { 2 * d as usize } else { d as usize })
+        .map(|d| d / 10 + d % 10)
+        .sum();
+
+    ((10 - sum % 10) % 10) as u8
+}
+
+/// The modulo 11 check digit for `digits`, weighting them 2 to 7 from the
+/// rightmost and cycling
+///
+/// `None` when the check would be 10, which a single digit can't hold.
+///
+/// ```
+/// use feather_code::barcode::msi::mod11;
+///
+/// assert_eq!(mod11(&[1, 2, 3, 4, 5, 6, 7]), Some(4));
+/// assert_eq!(mod11(&[6]), None);
+/// ```
+pub fn mod11(digits: &[u8]) -> Option<u8> {
+    let sum: usize = digits.iter()
+        .rev()
+        .zip((2..=7).cycle())
+        .map(|(&d, w)| w * d as usize)
+        .sum();
+
+    match (11 - sum % 11) % 11 {
+        10 => None,
+        check => Some(check as u8),
+    }
+}
+
+impl CheckScheme {
+    /// Number of check digits the scheme adds
+    pub fn len(self) -> usize {
+        match self {
+            CheckScheme::None => 0,
+            CheckScheme::Mod10 | CheckScheme::Mod11 => 1,
+            CheckScheme::Mod1010 | CheckScheme::Mod1110 => 2,
+        }
+    }
+
+    /// Whether the scheme adds no check digits
+    pub fn is_empty(self) -> bool {
+        self == CheckScheme::None
+    }
+
+    /// Append the check digits for `digits`
+    ///
+    /// Fails with `BadFormat` when a modulo 11 check would be 10.
+    fn append(self, digits: &mut Vec<u8>) -> Result<()> {
+        let eleven = |digits: &[u8]| {
+            mod11(digits).ok_or_else(|| {
+                Error::BadFormat(String::from("modulo 11 check digit would be 10"))
+            })
+        };
+
+        match self {
+            CheckScheme::None => {}
+            CheckScheme::Mod10 => digits.push(mod10(digits)),
+            CheckScheme::Mod11 => digits.push(eleven(digits)?),
+            CheckScheme::Mod1010 => {
+                digits.push(mod10(digits));
+                digits.push(mod10(digits));
+            }
+            CheckScheme::Mod1110 => {
+                digits.push(eleven(digits)?);
+                digits.push(mod10(digits));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Settings for `Msi::decode_with`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecodeOptions {
+    /// Whether to leave the check digits out of the text
+    pub strip_check: bool,
+}
+
+impl Default for DecodeOptions {
+    /// Strip the check digits, as `decode` does
+    fn default() -> DecodeOptions {
+        DecodeOptions { strip_check: true }
+    }
+}
+
+/// An MSI barcode borrowing its digits, each a value in `0..=9`, the check
+/// digits last
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Msi<'a> {
+    digits: &'a [u8],
+    scheme: CheckScheme,
+}
+
+impl<'a> Msi<'a> {
+    /// Wrap digits ending in the check digits of `scheme`
+    pub fn new(digits: &'a [u8], scheme: CheckScheme) -> Msi<'a> {
+        Msi { digits, scheme }
+    }
+
+    /// The digits, as given
+    pub fn digits(&self) -> &'a [u8] {
+        self.digits
+    }
+
+    /// The scheme the check digits were computed by
+    pub fn scheme(&self) -> CheckScheme {
+        self.scheme
+    }
+
+    /// Split out the data and check digits, failing unless there is data
+    /// before the check digits and every digit is in `0..=9`
+    fn parts(&self) -> Result<(&'a [u8], &'a [u8])> {
+        let n = self.digits.len();
+        if n <= self.scheme.len() {
+            return Err(Error::BadFormat(format!("MSI barcode of {} digits has no data", n)));
+        }
+
+        if let Some(index) = self.digits.iter().position(|&d| d > 9) {
+            return Err(Error::BadSymbolAt { index, value: self.digits[index], context: IN_DATA });
+        }
+
+        Ok(self.digits.split_at(n - self.scheme.len()))
+    }
+
+    /// Whether the check digits are the ones `scheme` gives for the data
+    fn check_matches(&self, data: &[u8], check: &[u8]) -> bool {
+        let mut expected = data.to_vec();
+
+        self.scheme.append(&mut expected).is_ok() && &expected[data.len()..] == check
+    }
+
+    /// The digits as text, keeping or stripping the check digits as
+    /// `options` says
+    ///
+    /// ```
+    /// use feather_code::barcode::msi::{CheckScheme, DecodeOptions, Msi};
+    ///
+    /// let digits: [u8; 9] = [1, 2, 3, 4, 5, 6, 7, 4, 1];
+    /// let code = Msi::new(&digits, CheckScheme::Mod1110);
+    ///
+    /// assert_eq!(code.decode_with(&DecodeOptions { strip_check: false }),
+    ///            Ok(String::from("123456741")));
+    /// ```
+    pub fn decode_with(&self, options: &DecodeOptions) -> Result<String> {
+        let (data, check) = self.parts()?;
+
+        if !self.check_matches(data, check) {
+            return Err(Error::BadFormat(String::from("check digits do not match")));
+        }
+
+        let digits = if options.strip_check { data } else { self.digits };
+
+        Ok(digits.iter().map(|&d| char::from(b'0' + d)).collect())
+    }
+
+    /// Every module of the printed barcode, `true` for dark, each bar and
+    /// space one module narrow or two wide
+    ///
+    /// Runs from the leading quiet zone to the trailing one, as for
+    /// `Code128::modules`.  Digits out of range are drawn as their lowest
+    /// four bits.
+    pub fn modules(&self) -> Vec<bool> {
+        let mut modules = vec![false; QUIET_ZONE];
+        modules.extend_from_slice(&START);
+
+        for &d in self.digits {
+            for bit in (0..4).rev() {
+                modules.extend_from_slice(if d >> bit & 1 == 1 { &ONE } else { &ZERO });
+            }
+        }
+
+        modules.extend_from_slice(&STOP);
+        modules.extend_from_slice(&[false; QUIET_ZONE]);
+        modules
+    }
+}
+
+impl<'a> Format for Msi<'a> {
+    /// Whether the barcode has data and the check digits of its scheme
+    fn checksum(&self) -> bool {
+        match self.parts() {
+            Ok((data, check)) => self.check_matches(data, check),
+            Err(_) => false,
+        }
+    }
+
+    fn has_checksum(&self) -> bool {
+        !self.scheme.is_empty()
+    }
+}
+
+impl<'a> Decode<String> for Msi<'a> {
+    /// The digits as text, without the check digits
+    fn decode(&self) -> Result<String> {
+        self.decode_with(&DecodeOptions::default())
+    }
+}
+
+/// An owned MSI barcode
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MsiBuf {
+    digits: Vec<u8>,
+    scheme: CheckScheme,
+}
+
+impl MsiBuf {
+    /// Borrow as an `Msi`
+    pub fn as_msi(&self) -> Msi<'_> {
+        Msi { digits: &self.digits, scheme: self.scheme }
+    }
+
+    /// The digits, check digits included
+    pub fn digits(&self) -> &[u8] {
+        &self.digits
+    }
+
+    /// The scheme the check digits were computed by
+    pub fn scheme(&self) -> CheckScheme {
+        self.scheme
+    }
+
+    /// Every module of the printed barcode, quiet zones included
+    pub fn modules(&self) -> Vec<bool> {
+        self.as_msi().modules()
+    }
+}
+
+impl Format for MsiBuf {
+    fn checksum(&self) -> bool {
+        self.as_msi().checksum()
+    }
+
+    fn has_checksum(&self) -> bool {
+        !self.scheme.is_empty()
+    }
+}
+
+impl<T> Decode<T> for MsiBuf
+    where for<'a> Msi<'a>: Decode<T>
+{
+    fn decode(&self) -> Result<T> {
+        self.as_msi().decode()
+    }
+}
+
+/// Encode the digits of `text`, adding the check digits of `scheme`
+///
+/// Fails with `BadFormat` for empty text, a character other than a digit,
+/// or, with a modulo 11 scheme, data whose check would be 10.
+///
+/// ```
+/// use feather_code::barcode::msi::{encode_with, CheckScheme};
+///
+/// let code = encode_with("1234567", CheckScheme::Mod1010).unwrap();
+///
+/// assert_eq!(code.digits(), [1, 2, 3, 4, 5, 6, 7, 4, 1]);
+/// ```
+pub fn encode_with(text: &str, scheme: CheckScheme) -> Result<MsiBuf> {
+    if text.is_empty() {
+        return Err(Error::BadFormat(String::from("nothing to encode")));
+    }
+
+    let mut digits = Vec::with_capacity(text.len() + scheme.len());
+    for c in text.chars() {
+        match c.to_digit(10) {
+            Some(d) if c.is_ascii() => digits.push(d as u8),
+            _ => return Err(Error::BadFormat(format!("cannot encode {:?} in MSI", c))),
+        }
+    }
+
+    scheme.append(&mut digits)?;
+
+    Ok(MsiBuf { digits, scheme })
+}
+
+/// Read the digits from a row of modules, `true` for dark, taking the last
+/// ones for the check digits of `scheme`
+///
+/// Light modules on either side are skipped, and the rest must be the
+/// start pattern, 12 modules per digit, and the stop pattern.  Fails with
+/// `BadFormat` otherwise; the digits are not validated, since the modules
+/// can't say which scheme was used.
+///
+/// ```
+/// use feather_code::barcode::msi::{encode_with, scan_modules, CheckScheme};
+///
+/// let code = encode_with("1234567", CheckScheme::Mod1010).unwrap();
+///
+/// assert_eq!(scan_modules(&code.modules(), CheckScheme::Mod1010), Ok(code));
+/// ```
+pub fn scan_modules(modules: &[bool], scheme: CheckScheme) -> Result<MsiBuf> {
+    let bars = render::bars(modules)?;
+    let n = bars.len();
+
+    if n < START.len() + STOP.len() || !(n - START.len() - STOP.len()).is_multiple_of(12) ||
+       bars[..START.len()] != START || bars[n - STOP.len()..] != STOP {
+        return Err(Error::BadFormat(String::from("modules don't frame MSI digits")));
+    }
+
+    let digits = bars[START.len()..n - STOP.len()].chunks(12)
+        .enumerate()
+        .map(|(i, chunk)| {
+            chunk.chunks(3).try_fold(0, |digit, bit| match *bit {
+                [true, true, false] => Some(digit << 1 | 1),
+                [true, false, false] => Some(digit << 1),
+                _ => None,
+            }).ok_or_else(|| Error::BadFormat(format!("unrecognized modules for digit {}", i)))
+        })
+        .collect::<Result<Vec<u8>>>()?;
+
+    Ok(MsiBuf { digits, scheme })
+}
+
+impl Encode<MsiBuf> for str {
+    /// Encode with one Luhn check digit
+    fn encode(&self) -> Result<MsiBuf> {
+        encode_with(self, CheckScheme::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHEMES: [CheckScheme; 5] = [CheckScheme::None, CheckScheme::Mod10,
+                                       CheckScheme::Mod11, CheckScheme::Mod1010,
+                                       CheckScheme::Mod1110];
+
+    #[test]
+    fn reference_check_digits() {
+        let checks = [(CheckScheme::None, &[][..]), (CheckScheme::Mod10, &[4][..]),
+                      (CheckScheme::Mod11, &[4][..]), (CheckScheme::Mod1010, &[4, 1][..]),
+                      (CheckScheme::Mod1110, &[4, 1][..])];
+
+        for &(scheme, check) in &checks {
+            let code = encode_with("1234567", scheme).unwrap();
+
+            assert_eq!(&code.digits()[7..], check, "{:?}", scheme);
+            assert!(code.checksum());
+            assert_eq!(code.decode(), Ok(String::from("1234567")));
+        }
+    }
+
+    #[test]
+    fn checksum_uses_scheme() {
+        let digits = [1, 2, 3, 4, 5, 6, 7, 4];
+
+        assert!(Msi::new(&digits, CheckScheme::Mod10).checksum());
+        assert!(Msi::new(&digits, CheckScheme::Mod11).checksum());
+        assert!(Msi::new(&digits, CheckScheme::None).checksum());
+        assert!(!Msi::new(&digits, CheckScheme::Mod1010).checksum());
+
+        let wrong = [1, 2, 3, 4, 5, 6, 7, 5];
+        assert!(!Msi::new(&wrong, CheckScheme::Mod10).checksum());
+        assert_eq!(Msi::new(&wrong, CheckScheme::Mod10).decode(),
+                   Err(Error::BadFormat(String::from("check digits do not match"))));
+    }
+
+    #[test]
+    fn round_trip_every_scheme() {
+        for n in 0..2000u32 {
+            let text = format!("{}", n * 7919);
+
+            for &scheme in &SCHEMES {
+                let code = match encode_with(&text, scheme) {
+                    Ok(code) => code,
+                    Err(_) => {
+                        assert!(scheme == CheckScheme::Mod11 || scheme == CheckScheme::Mod1110);
+                        continue;
+                    }
+                };
+                let options = DecodeOptions { strip_check: false };
+
+                assert!(code.checksum(), "{} {:?}", text, scheme);
+                assert_eq!(code.decode(), Ok(text.clone()));
+                assert_eq!(code.as_msi().decode_with(&options).unwrap().len(),
+                           text.len() + scheme.len());
+            }
+        }
+    }
+
+    #[test]
+    fn modules_of_one_digit() {
+        let code = encode_with("6", CheckScheme::None).unwrap();
+        let modules: Vec<u8> = code.modules().iter().map(|&m| m as u8).collect();
+        let n = modules.len();
+
+        assert!(modules[..QUIET_ZONE].iter().all(|&m| m == 0));
+        assert!(modules[n - QUIET_ZONE..].iter().all(|&m| m == 0));
+        // Start, then 0110, then stop
+        assert_eq!(modules[QUIET_ZONE..n - QUIET_ZONE],
+                   [1, 1, 0, 1, 0, 0, 1, 1, 0, 1, 1, 0, 1, 0, 0, 1, 0, 0, 1]);
+    }
+
+    #[test]
+    fn scan_rendered_modules() {
+        for &scheme in &SCHEMES {
+            let code = encode_with("9876543210", scheme).unwrap();
+
+            assert_eq!(scan_modules(&code.modules(), scheme), Ok(code));
+        }
+
+        let code = encode_with("42", CheckScheme::Mod10).unwrap();
+        let mut modules = code.modules();
+        assert!(scan_modules(&modules[..modules.len() - QUIET_ZONE - 1], CheckScheme::Mod10)
+                    .is_err());
+
+        // A bar three modules wide is neither bit
+        modules[QUIET_ZONE + 5] = true;
+        assert_eq!(scan_modules(&modules, CheckScheme::Mod10),
+                   Err(Error::BadFormat(String::from("unrecognized modules for digit 0"))));
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        assert_eq!(Encode::<MsiBuf>::encode("12a4"),
+                   Err(Error::BadFormat(String::from("cannot encode 'a' in MSI"))));
+        assert!(Encode::<MsiBuf>::encode("\u{661}").is_err());
+        assert_eq!(Encode::<MsiBuf>::encode(""),
+                   Err(Error::BadFormat(String::from("nothing to encode"))));
+        assert_eq!(encode_with("6", CheckScheme::Mod11),
+                   Err(Error::BadFormat(String::from("modulo 11 check digit would be 10"))));
+
+        assert!(Msi::new(&[4], CheckScheme::Mod10).decode().is_err());
+        assert_eq!(Msi::new(&[1, 12, 4], CheckScheme::Mod10).decode(),
+                   Err(Error::BadSymbolAt { index: 1, value: 12, context: IN_DATA }));
+    }
+}
@@ -0,0 +1,318 @@
+//  This Source Code Form is subject to the terms of the Mozilla Public
+//  License, v. 2.0. If a copy of the MPL was not distributed with this
+//  file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Pharmacode barcodes
+//!
+//! Pharmacode, printed on medicine packaging to check that the right
+//! material is on the line, holds a single number from
+//! [`MIN`](constant.MIN.html) to [`MAX`](constant.MAX.html) as up to 16
+//! narrow and wide bars.  Read from the right, the bar at position `i`
+//! counts `2^i` if narrow and twice that if wide.  There is no check.
+//!
+//! ```
+//! use feather_code::barcode::pharmacode::PharmacodeBuf;
+//! use feather_code::barcode::format::{Decode, Encode};
+//!
+//! let code: PharmacodeBuf = 1234u32.encode().unwrap();
+//!
+//! assert_eq!(code.to_string(), "| | || || | || | | || ||");
+//! assert_eq!(code.decode(), Ok(1234));
+//! ```
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use barcode::format::{Decode, Encode, Error, Format, Result};
+use barcode::render::{self, QUIET_ZONE};
+
+/// Smallest value Pharmacode can hold, two narrow bars
+pub const MIN: u32 = 3;
+
+/// Largest value Pharmacode can hold, sixteen wide bars
+pub const MAX: u32 = 131_070;
+
+/// Most bars in a Pharmacode barcode
+pub const MAX_BARS: usize = 16;
+
+/// Modules of a narrow bar
+const NARROW: usize = 1;
+
+/// Modules of a wide bar
+const WIDE: usize = 3;
+
+/// Modules of the space between bars
+const SPACE: usize = 2;
+
+/// A Pharmacode barcode borrowing its bars, from the left, each `true` if
+/// wide
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pharmacode<'a>(&'a [bool]);
+
+impl<'a> Pharmacode<'a> {
+    /// Wrap bars from the left, each `true` if wide
+    pub fn new(wide: &'a [bool]) -> Pharmacode<'a> {
+        Pharmacode(wide)
+    }
+
+    /// The bars, as given
+    pub fn bars(&self) -> &'a [bool] {
+        self.0
+    }
+
+    /// The value of the bars, failing with `BadFormat` unless it is from
+    /// `MIN` to `MAX`
+    fn value(&self) -> Result<u32> {
+        if self.0.len() > MAX_BARS {
+            return Err(Error::BadFormat(format!("{} bars, more than the {} allowed",
+                                                self.0.len(), MAX_BARS)));
+        }
+
+        let value = self.0.iter()
+            .rev()
+            .enumerate()
+            .map(|(i, &wide)| if wide { 2 << i } else { 1 << i })
+            .sum();
+
+        if value < MIN {
+            return Err(Error::BadFormat(format!("Pharmacode value {} below {}", value, MIN)));
+        }
+
+        Ok(value)
+    }
+
+    /// Every module of the printed barcode, `true` for dark
+    ///
+    /// Narrow bars are one module, wide bars three, and the spaces between
+    /// them two.  Runs from the leading quiet zone to the trailing one, as
+    /// for `Code128::modules`.
+    pub fn modules(&self) -> Vec<bool> {
+        let mut modules = vec![false; QUIET_ZONE];
+
+        for (i, &wide) in self.0.iter().enumerate() {
+            if i > 0 {
+                modules.extend_from_slice(&[false; SPACE]);
+            }
+            modules.extend_from_slice(if wide { &[true; WIDE] } else { &[true; NARROW] });
+        }
+
+        modules.extend_from_slice(&[false; QUIET_ZONE]);
+        modules
+    }
+}
+
+impl<'a> Format for Pharmacode<'a> {
+    /// Whether the bars hold a value from `MIN` to `MAX`, as Pharmacode
+    /// has no check
+    fn checksum(&self) -> bool {
+        self.value().is_ok()
+    }
+
+    fn has_checksum(&self) -> bool {
+        false
+    }
+}
+
+impl<'a> Decode<u32> for Pharmacode<'a> {
+    fn decode(&self) -> Result<u32> {
+        self.value()
+    }
+}
+
+impl<'a> Decode<String> for Pharmacode<'a> {
+    /// The value in decimal
+    fn decode(&self) -> Result<String> {
+        self.value().map(|value| format!("{}", value))
+    }
+}
+
+impl<'a> fmt::Display for Pharmacode<'a> {
+    /// The bars as `|` for narrow and `||` for wide, separated by spaces
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, &wide) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            f.write_str(if wide { "||" } else { "|" })?;
+        }
+
+        Ok(())
+    }
+}
+
+/// An owned Pharmacode barcode
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PharmacodeBuf(Vec<bool>);
+
+impl PharmacodeBuf {
+    /// Borrow as a `Pharmacode`
+    pub fn as_pharmacode(&self) -> Pharmacode<'_> {
+        Pharmacode(&self.0)
+    }
+
+    /// The bars from the left, each `true` if wide
+    pub fn bars(&self) -> &[bool] {
+        &self.0
+    }
+
+    /// Every module of the printed barcode, quiet zones included
+    pub fn modules(&self) -> Vec<bool> {
+        self.as_pharmacode().modules()
+    }
+}
+
+impl Format for PharmacodeBuf {
+    fn checksum(&self) -> bool {
+        self.as_pharmacode().checksum()
+    }
+
+    fn has_checksum(&self) -> bool {
+        false
+    }
+}
+
+impl<T> Decode<T> for PharmacodeBuf
+    where for<'a> Pharmacode<'a>: Decode<T>
+{
+    fn decode(&self) -> Result<T> {
+        self.as_pharmacode().decode()
+    }
+}
+
+impl fmt::Display for PharmacodeBuf {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.as_pharmacode().fmt(f)
+    }
+}
+
+/// Read the bars from a row of modules, `true` for dark
+///
+/// Light modules on either side are skipped.  Fails with `BadFormat` unless
+/// every bar is one or three modules wide and every space two; the value
+/// is not checked.
+///
+/// ```
+/// use feather_code::barcode::pharmacode::{scan_modules, PharmacodeBuf};
+/// use feather_code::barcode::format::Encode;
+///
+/// let code: PharmacodeBuf = 1234u32.encode().unwrap();
+///
+/// assert_eq!(scan_modules(&code.modules()), Ok(code));
+/// ```
+pub fn scan_modules(modules: &[bool]) -> Result<PharmacodeBuf> {
+    let bars = render::bars(modules)?;
+    let mut wide = Vec::new();
+    let mut rest = bars;
+
+    while let Some(&dark) = rest.first() {
+        let run = rest.iter().take_while(|&&m| m == dark).count();
+
+        match (dark, run) {
+            (true, NARROW) => wide.push(false),
+            (true, WIDE) => wide.push(true),
+            (false, SPACE) => {}
+            _ => {
+                let index = bars.len() - rest.len();
+                return Err(Error::BadFormat(format!("{} module {} at module {}", run,
+                                                    if dark { "bar" } else { "space" },
+                                                    index)));
+            }
+        }
+        rest = &rest[run..];
+    }
+
+    Ok(PharmacodeBuf(wide))
+}
+
+impl Encode<PharmacodeBuf> for u32 {
+    /// Draw the value as bars, failing with `BadFormat` outside `MIN` to
+    /// `MAX`
+    fn encode(&self) -> Result<PharmacodeBuf> {
+        if !(MIN..=MAX).contains(self) {
+            return Err(Error::BadFormat(format!("Pharmacode holds {} to {}, not {}",
+                                                MIN, MAX, self)));
+        }
+
+        // Peel bars off the right: an even remainder takes a wide bar
+        let mut bars = Vec::with_capacity(MAX_BARS);
+        let mut n = *self;
+        while n > 0 {
+            let wide = n.is_multiple_of(2);
+            n = (n - if wide { 2 } else { 1 }) / 2;
+            bars.push(wide);
+        }
+        bars.reverse();
+
+        Ok(PharmacodeBuf(bars))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_every_value() {
+        for value in MIN..=MAX {
+            let code: PharmacodeBuf = value.encode().unwrap();
+
+            assert!(code.bars().len() <= MAX_BARS);
+            assert!(code.checksum());
+            assert_eq!(code.decode(), Ok(value));
+        }
+    }
+
+    #[test]
+    fn extremes() {
+        let min: PharmacodeBuf = MIN.encode().unwrap();
+        let max: PharmacodeBuf = MAX.encode().unwrap();
+
+        assert_eq!(min.bars(), [false, false]);
+        assert_eq!(max.bars(), [true; MAX_BARS]);
+        assert_eq!(Decode::<String>::decode(&max), Ok(String::from("131070")));
+    }
+
+    #[test]
+    fn modules_of_five() {
+        let code: PharmacodeBuf = 5u32.encode().unwrap();
+        let modules: Vec<u8> = code.modules().iter().map(|&m| m as u8).collect();
+        let n = modules.len();
+
+        // A wide bar counting 4 and a narrow one counting 1
+        assert_eq!(code.bars(), [true, false]);
+        assert_eq!(modules[QUIET_ZONE..n - QUIET_ZONE], [1, 1, 1, 0, 0, 1]);
+        assert!(modules[..QUIET_ZONE].iter().all(|&m| m == 0));
+        assert!(modules[n - QUIET_ZONE..].iter().all(|&m| m == 0));
+    }
+
+    #[test]
+    fn scan_rendered_modules() {
+        for value in (MIN..=MAX).step_by(97) {
+            let code: PharmacodeBuf = value.encode().unwrap();
+
+            assert_eq!(scan_modules(&code.modules()), Ok(code));
+        }
+
+        let code: PharmacodeBuf = 5u32.encode().unwrap();
+        let mut modules = code.modules();
+        modules[QUIET_ZONE + 3] = true;
+        assert_eq!(scan_modules(&modules),
+                   Err(Error::BadFormat(String::from("4 module bar at module 0"))));
+    }
+
+    #[test]
+    fn rejects_out_of_range() {
+        for &value in &[0, 1, 2, MAX + 1, u32::MAX] {
+            assert_eq!(Encode::<PharmacodeBuf>::encode(&value),
+                       Err(Error::BadFormat(format!("Pharmacode holds 3 to 131070, not {}",
+                                                    value))));
+        }
+
+        assert!(!Pharmacode::new(&[true]).checksum());
+        assert_eq!(Decode::<u32>::decode(&Pharmacode::new(&[])),
+                   Err(Error::BadFormat(String::from("Pharmacode value 0 below 3"))));
+        assert_eq!(Decode::<u32>::decode(&Pharmacode::new(&[false; 17])),
+                   Err(Error::BadFormat(String::from("17 bars, more than the 16 allowed"))));
+    }
+}
@@ -0,0 +1,40 @@
+//  This Source Code Form is subject to the terms of the Mozilla Public
+//  License, v. 2.0. If a copy of the MPL was not distributed with this
+//  file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Laying barcodes out for printing
+
+pub mod text;
+
+use alloc::string::String;
+
+use barcode::format::{Error, Result};
+
+/// Light modules Code 128 requires on each side of the bars
+pub const QUIET_ZONE: usize = 10;
+
+/// Layout settings for a rendered barcode
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RenderOptions {
+    /// Blank modules on each side of the bars
+    pub quiet_zone: usize,
+}
+
+impl Default for RenderOptions {
+    /// The 10 module quiet zone Code 128 requires
+    fn default() -> RenderOptions {
+        RenderOptions { quiet_zone: QUIET_ZONE }
+    }
+}
+
+/// The modules from the first dark one to the last, skipping the quiet
+/// zones, for reading a barcode back
+pub(crate) fn bars(modules: &[bool]) -> Result<&[bool]> {
+    let first = modules.iter().position(|&dark| dark);
+    let last = modules.iter().rposition(|&dark| dark);
+
+    match (first, last) {
+        (Some(first), Some(last)) => Ok(&modules[first..last + 1]),
+        _ => Err(Error::BadFormat(String::from("no bars found"))),
+    }
+}
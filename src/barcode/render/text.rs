@@ -0,0 +1,67 @@
+//  This Source Code Form is subject to the terms of the Mozilla Public
+//  License, v. 2.0. If a copy of the MPL was not distributed with this
+//  file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Flat module sequences, the starting point for any output format
+
+use alloc::vec::Vec;
+
+use super::QUIET_ZONE;
+use barcode::code128::Pattern;
+
+/// Expand symbols into modules: `1` for a bar and `0` for a space
+///
+/// The symbols are surrounded by the 10 module quiet zone, and the stop
+/// symbol is followed by its final 2 module bar.
+///
+/// ```
+/// use feather_code::barcode::code128::Pattern;
+/// use feather_code::barcode::render::text::render_bars;
+///
+/// let bars = render_bars(&[Pattern::C104, Pattern::C33, Pattern::C35, Pattern::C106]);
+///
+/// assert_eq!(bars.len(), 10 + 4 * 11 + 2 + 10);
+/// assert_eq!(bars[10..21], [1, 1, 0, 1, 0, 0, 1, 0, 0, 0, 0]);
+/// ```
+pub fn render_bars(patterns: &[Pattern]) -> Vec<u8> {
+    let mut bars = vec![0; QUIET_ZONE];
+
+    for p in patterns {
+        for (i, &w) in p.bar_widths().iter().enumerate() {
+            bars.extend((0..w).map(|_| if i % 2 == 0 { 1 } else { 0 }));
+        }
+
+        if *p == Pattern::stop() {
+            bars.extend(&[1, 1]);
+        }
+    }
+
+    bars.extend(&[0; QUIET_ZONE]);
+    bars
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PJJ123C: [u8; 10] = [103, 48, 42, 42, 17, 18, 19, 35, 54, 106];
+
+    #[test]
+    fn render_pjj123c() {
+        let patterns: Vec<Pattern> = PJJ123C.iter().map(|&u| Pattern::from(u)).collect();
+        let modules: usize = patterns.iter()
+            .map(|p| p.bar_widths().iter().map(|&w| w as usize).sum::<usize>())
+            .sum();
+        let bars = render_bars(&patterns);
+
+        assert_eq!(bars.len(), 10 + modules + 2 + 10);
+        assert!(bars[..10].iter().all(|&m| m == 0));
+        assert!(bars[bars.len() - 10..].iter().all(|&m| m == 0));
+        assert_eq!(bars[bars.len() - 12..bars.len() - 10], [1, 1]);
+    }
+
+    #[test]
+    fn render_empty() {
+        assert_eq!(render_bars(&[]), vec![0; 20]);
+    }
+}
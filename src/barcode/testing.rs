@@ -0,0 +1,166 @@
+//  This Source Code Form is subject to the terms of the Mozilla Public
+//  License, v. 2.0. If a copy of the MPL was not distributed with this
+//  file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Checks for `Encode` implementations, and inputs for fuzzing
+//!
+//! Shared by this crate's tests and available to crates adding their own
+//! encoders, so every encoder is held to the same standard.
+
+use alloc::string::String;
+#[cfg(feature = "arbitrary")]
+use alloc::vec::Vec;
+use core::fmt::Debug;
+
+#[cfg(feature = "arbitrary")]
+use arbitrary::{Arbitrary, Unstructured};
+
+#[cfg(feature = "arbitrary")]
+use barcode::code128::{weighted_checksum, Symbology};
+use barcode::code128::{Code128Buf, Encoding};
+use barcode::format::{Decode, Encode, Format};
+
+pub use barcode::code128::MAX_SYMBOLS;
+
+/// Encode `input` and check the result, panicking on any failure
+///
+/// The barcode must encode successfully, pass its checksum, decode back to
+/// `expected_decode`, and have no more than `MAX_SYMBOLS` symbols.
+///
+/// ```
+/// use feather_code::barcode::code128::Pattern;
+/// use feather_code::barcode::testing::verify_encoder;
+///
+/// verify_encoder::<str, Pattern>("PJJ123C", "PJJ123C");
+/// ```
+pub fn verify_encoder<T, E>(input: &T, expected_decode: &str)
+    where T: Encode<Code128Buf<E>> + ?Sized,
+          E: Encoding + Debug
+{
+    let code = match input.encode() {
+        Ok(code) => code,
+        Err(e) => panic!("encoding failed: {}", e),
+    };
+
+    assert!(code.checksum(), "checksum failed for {:?}", code.symbols());
+
+    let decoded: String = match code.as_code128().decode() {
+        Ok(text) => text,
+        Err(e) => panic!("decoding {:?} failed: {}", code.symbols(), e),
+    };
+
+    assert_eq!(decoded, expected_decode, "decoded text differs for {:?}", code.symbols());
+    assert!(code.symbols().len() <= MAX_SYMBOLS,
+            "{} symbols is more than the maximum of {}",
+            code.symbols().len(),
+            MAX_SYMBOLS);
+}
+
+/// Raw symbol values laid out almost like a Code 128 barcode
+///
+/// Generated with a real start symbol, any values in `0..=106` as data, a
+/// check symbol that is usually right, and the stop symbol.  Now and then
+/// the end is cut off, so the framing checks are exercised too.  Random
+/// bytes would almost never get past the start symbol and check symbol,
+/// leaving the data decoding untouched by a fuzzer.
+#[cfg(feature = "arbitrary")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AlmostValid(pub Vec<u8>);
+
+#[cfg(feature = "arbitrary")]
+impl<'a> Arbitrary<'a> for AlmostValid {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<AlmostValid> {
+        let start = Symbology::arbitrary(u)?;
+        let len = u.arbitrary_len::<u8>()?.min(MAX_SYMBOLS);
+        let data = (0..len)
+            .map(|_| u.int_in_range(0..=106))
+            .collect::<arbitrary::Result<Vec<u8>>>()?;
+
+        let check = if u.ratio(7, 8)? {
+            weighted_checksum(start, &data)
+        } else {
+            u.int_in_range(0..=106)?
+        };
+
+        let mut symbols = Vec::with_capacity(data.len() + 3);
+        symbols.push(start.start_value());
+        symbols.extend(data);
+        symbols.push(check);
+        symbols.push(u8::stop());
+
+        if u.ratio(1, 16)? {
+            let keep = u.choose_index(symbols.len())?;
+            symbols.truncate(keep);
+        }
+
+        Ok(AlmostValid(symbols))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use barcode::code128::{encode_with, EncodeOptions, Pattern};
+    use barcode::format::Result;
+
+    #[test]
+    fn verify_str_encoder() {
+        for text in &["PJJ123C", "42184020500", "SHiFT!", "\x00\x1fcontrol"] {
+            verify_encoder::<str, Pattern>(text, text);
+        }
+    }
+
+    #[test]
+    fn verify_bytes_encoder() {
+        verify_encoder::<[u8], Pattern>(b"HackFSU 2024", "HackFSU 2024");
+        verify_encoder::<[u8], Pattern>(b"\xc8ack", "\u{c8}ack");
+    }
+
+    #[test]
+    #[should_panic(expected = "decoded text differs")]
+    fn verify_rejects_wrong_text() {
+        verify_encoder::<str, Pattern>("PJJ123C", "PJJ123D");
+    }
+
+    #[test]
+    #[should_panic(expected = "encoding failed")]
+    fn verify_rejects_encode_errors() {
+        verify_encoder::<str, Pattern>("caf\u{e9}", "caf\u{e9}");
+    }
+
+    /// Text encoded without the length limit
+    struct Oversize(String);
+
+    impl Encode<Code128Buf<Pattern>> for Oversize {
+        fn encode(&self) -> Result<Code128Buf<Pattern>> {
+            encode_with(self.0.as_bytes(), &EncodeOptions { allow_oversize: true })
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "more than the maximum")]
+    fn verify_rejects_long_barcodes() {
+        let text: String = (0..MAX_SYMBOLS).map(|_| 'a').collect();
+
+        verify_encoder(&Oversize(text.clone()), &text);
+    }
+
+    #[cfg(feature = "arbitrary")]
+    quickcheck! {
+        fn almost_valid_is_mostly_framed(bytes: Vec<u8>) -> bool {
+            use barcode::code128::Code128;
+
+            let mut u = Unstructured::new(&bytes);
+            let symbols = match AlmostValid::arbitrary(&mut u) {
+                Ok(AlmostValid(symbols)) => symbols,
+                Err(_) => return true,
+            };
+            let code = Code128::new(&symbols);
+
+            // Nothing may panic, and whatever passes the checksum is framed
+            let _ = Decode::<String>::decode(&code);
+            let _ = code.decode_partial();
+            !code.checksum() || (symbols.len() >= 3 && symbols.last() == Some(&u8::stop()))
+        }
+    }
+}
@@ -0,0 +1,52 @@
+//  This Source Code Form is subject to the terms of the Mozilla Public
+//  License, v. 2.0. If a copy of the MPL was not distributed with this
+//  file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! JavaScript bindings for Code 128, through wasm-bindgen
+//!
+//! Symbols are passed from JavaScript as a `Uint8Array` of values.  The
+//! crate itself can't be built as a `cdylib`, since it is `no_std` without
+//! the `std` feature, so the `wasm` directory holds a small wrapper crate
+//! for `wasm-pack` to build.
+//!
+//! Requires the `wasm` feature.
+
+use alloc::string::{String, ToString};
+
+use wasm_bindgen::prelude::*;
+
+use barcode::code128::Code128;
+use barcode::format::{Decode, Format};
+
+/// Decode symbol values to text, throwing the error message on failure
+#[wasm_bindgen]
+pub fn decode_u8_slice(data: &[u8]) -> Result<String, JsValue> {
+    Code128::new(data).decode().map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Whether the symbol values form a valid barcode with a matching check
+/// symbol
+#[wasm_bindgen]
+pub fn verify_checksum(data: &[u8]) -> bool {
+    Code128::new(data).checksum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GS1: [u8; 11] = [105, 102, 42, 18, 40, 20, 50, 101, 16, 92, 106];
+
+    // Errors need a JavaScript host to build their `JsValue`, so only the
+    // success paths run natively
+    #[test]
+    fn decode_gs1() {
+        assert_eq!(decode_u8_slice(&GS1).ok(), Some(String::from("42184020500")));
+    }
+
+    #[test]
+    fn verify_gs1() {
+        assert!(verify_checksum(&GS1));
+        assert!(!verify_checksum(&GS1[..10]));
+    }
+}
@@ -2,6 +2,66 @@
 //  License, v. 2.0. If a copy of the MPL was not distributed with this
 //  file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+//! Feather Code is a custom visual encoding format extended from code128
+//!
+//! The building blocks live in the [`barcode`](barcode/index.html) module:
+//! the shared [`Format`](barcode/format/trait.Format.html) and
+//! [`Decode`](barcode/format/trait.Decode.html) traits, and the
+//! [`Code128`](barcode/code128/struct.Code128.html) symbology they are
+//! implemented for, and the [`Feather`](barcode/feather/struct.Feather.html)
+//! visual format drawing a Code 128 barcode as a feather.
+//! [`EAN13`](barcode/ean13/struct.EAN13.html) product barcodes,
+//! [`Code39`](barcode/code39/struct.Code39.html),
+//! [`Code93`](barcode/code93/struct.Code93.html),
+//! [`ITF`](barcode/itf/struct.ITF.html),
+//! [`Codabar`](barcode/codabar/struct.Codabar.html),
+//! [`Msi`](barcode/msi/struct.Msi.html), and
+//! [`Pharmacode`](barcode/pharmacode/struct.Pharmacode.html) barcodes
+//! implement the same traits.  Modules of unknown symbology can be read with
+//! [`detect`](barcode/detect/fn.detect.html).
+//!
+//! The crate is `no_std` and only needs an allocator: the default `std`
+//! feature can be disabled in favour of `alloc` for embedded targets.  The
+//! `image` feature adds [color feather codes](barcode/feather/color/index.html)
+//! and requires `std`.  The `wasm` feature adds
+//! [JavaScript bindings](barcode/wasm/index.html), the `ffi` feature a
+//! [C interface](barcode/ffi/index.html), and the `rayon` feature parallel
+//! batch decoding.  The `arbitrary` feature adds `Arbitrary` implementations
+//! for fuzzing, used by the targets under `fuzz/`, and the `testing` feature
+//! [quickcheck generators](barcode/code128/testing/index.html) of valid
+//! barcodes.
+
+#![no_std]
+
+#[cfg(not(feature = "alloc"))]
+compile_error!("feather-code requires either the `std` or the `alloc` feature");
+
+#[macro_use]
+extern crate alloc;
+#[cfg(any(feature = "std", test))]
+extern crate std;
+
+#[cfg(feature = "arbitrary")]
+extern crate arbitrary;
+#[cfg(feature = "image")]
+extern crate image;
+#[cfg(feature = "rayon")]
+extern crate rayon;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "wasm")]
+extern crate wasm_bindgen;
+
+#[cfg(test)]
+extern crate bincode;
+#[cfg(any(test, feature = "testing"))]
+#[cfg_attr(test, macro_use)]
+extern crate quickcheck;
+#[cfg(test)]
+extern crate serde_json;
+
+pub mod barcode;
+
 #[cfg(test)]
 mod tests {
     #[test]
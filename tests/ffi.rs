@@ -0,0 +1,108 @@
+//  This Source Code Form is subject to the terms of the Mozilla Public
+//  License, v. 2.0. If a copy of the MPL was not distributed with this
+//  file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! The C interface, called as C would call it
+//!
+//! Only touches memory it owns, so it runs under Miri.
+
+#![cfg(feature = "ffi")]
+
+extern crate feather_code;
+
+use std::ptr;
+
+use feather_code::barcode::ffi::*;
+
+const GS1: [u8; 11] = [105, 102, 42, 18, 40, 20, 50, 101, 16, 92, 106];
+
+#[test]
+fn decode_into_buffer() {
+    let mut out = [0u8; 32];
+    let mut out_len = out.len();
+
+    let status = unsafe {
+        feather_code_decode(GS1.as_ptr(), GS1.len(), out.as_mut_ptr(), &mut out_len)
+    };
+
+    assert_eq!(status, FEATHER_CODE_OK);
+    assert_eq!(&out[..out_len], b"42184020500");
+    assert!(out[out_len..].iter().all(|&b| b == 0));
+}
+
+#[test]
+fn decode_extended_characters_as_utf8() {
+    // Start B, FNC4, "A" extended to U+00C1, check, stop
+    let symbols = [104u8, 100, 33, 64, 106];
+    let mut out = [0u8; 8];
+    let mut out_len = out.len();
+
+    let status = unsafe {
+        feather_code_decode(symbols.as_ptr(), symbols.len(), out.as_mut_ptr(), &mut out_len)
+    };
+
+    assert_eq!(status, FEATHER_CODE_OK);
+    assert_eq!(&out[..out_len], "\u{c1}".as_bytes());
+    assert_eq!(&out[..out_len], [195, 129]);
+}
+
+#[test]
+fn decode_queries_length() {
+    let mut out_len = 0;
+
+    let status = unsafe {
+        feather_code_decode(GS1.as_ptr(), GS1.len(), ptr::null_mut(), &mut out_len)
+    };
+
+    assert_eq!(status, FEATHER_CODE_ERR_BUFFER);
+    assert_eq!(out_len, 11);
+}
+
+#[test]
+fn decode_short_buffer_is_untouched() {
+    let mut out = [0u8; 4];
+    let mut out_len = out.len();
+
+    let status = unsafe {
+        feather_code_decode(GS1.as_ptr(), GS1.len(), out.as_mut_ptr(), &mut out_len)
+    };
+
+    assert_eq!(status, FEATHER_CODE_ERR_BUFFER);
+    assert_eq!(out_len, 11);
+    assert_eq!(out, [0; 4]);
+}
+
+#[test]
+fn decode_invalid_symbols() {
+    let mut out = [0u8; 32];
+    let mut out_len = out.len();
+
+    let status = unsafe { feather_code_decode(GS1.as_ptr(), 10, out.as_mut_ptr(), &mut out_len) };
+
+    assert_eq!(status, FEATHER_CODE_ERR_DECODE);
+    assert_eq!(out_len, 32);
+}
+
+#[test]
+fn decode_null_pointers() {
+    let mut out = [0u8; 32];
+    let mut out_len = out.len();
+
+    unsafe {
+        assert_eq!(feather_code_decode(ptr::null(), 0, out.as_mut_ptr(), &mut out_len),
+                   FEATHER_CODE_ERR_NULL);
+        assert_eq!(feather_code_decode(GS1.as_ptr(), 11, out.as_mut_ptr(), ptr::null_mut()),
+                   FEATHER_CODE_ERR_NULL);
+        assert_eq!(feather_code_decode(GS1.as_ptr(), GS1.len(), ptr::null_mut(), &mut out_len),
+                   FEATHER_CODE_ERR_NULL);
+    }
+}
+
+#[test]
+fn checksum() {
+    unsafe {
+        assert_eq!(feather_code_checksum(GS1.as_ptr(), GS1.len()), 1);
+        assert_eq!(feather_code_checksum(GS1.as_ptr(), 10), 0);
+        assert_eq!(feather_code_checksum(ptr::null(), 0), FEATHER_CODE_ERR_NULL);
+    }
+}
@@ -0,0 +1,18 @@
+// Run after `wasm-pack build --target nodejs` in this directory:
+//
+//     node tests/decode.mjs
+
+import assert from 'node:assert/strict';
+import { createRequire } from 'node:module';
+
+const require = createRequire(import.meta.url);
+const { decode_u8_slice, verify_checksum } = require('../pkg/feather_code_wasm.js');
+
+const gs1 = new Uint8Array([105, 102, 42, 18, 40, 20, 50, 101, 16, 92, 106]);
+
+assert.equal(decode_u8_slice(gs1), '42184020500');
+assert.equal(verify_checksum(gs1), true);
+assert.equal(verify_checksum(gs1.subarray(0, 10)), false);
+assert.throws(() => decode_u8_slice(gs1.subarray(0, 10)), /missing start or stop symbol/);
+
+console.log('ok');